@@ -0,0 +1,117 @@
+//! Benchmarks of the hot paths of the renderer, run with `cargo bench`.
+//! The scenes and rays are generated from fixed seeds, so that the runs
+//! before and after a change trace the same work.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use raycasting_rs::{
+    camera::Camera,
+    hit::Hit,
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    render::Tracer,
+    scenes::{self, MaterialWeights},
+    sphere::Sphere,
+    vec3::{Color, Float, Vec3},
+    world::World,
+};
+
+/// Rays starting around the camera of the built-in scenes, pointing
+/// towards the origin with some spread.
+fn rays(count: usize) -> Vec<Ray> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut around = |x: Float| x + rng.gen_range(-1.0..1.0);
+
+    (0..count)
+        .map(|_| {
+            let start = Vec3(around(13.), around(2.), around(3.));
+            let target = Vec3(around(0.), around(0.), around(0.));
+            Ray::new(start, target - start)
+        })
+        .collect()
+}
+
+fn random_spheres() -> World {
+    World::from(scenes::random_spheres(
+        scenes::DEFAULT_SEED,
+        scenes::DEFAULT_GRID_EXTENT,
+        MaterialWeights::default(),
+    ))
+}
+
+fn ray_sphere(c: &mut Criterion) {
+    let sphere = Sphere::new(Vec3::ZERO, 1., Material::lambertian(Color::WHITE, None));
+    let rays = rays(1024);
+
+    c.bench_function("ray-sphere intersection", |b| {
+        b.iter(|| {
+            for r in &rays {
+                black_box(sphere.hit(black_box(r), Interval::new(0.001, Float::INFINITY)));
+            }
+        })
+    });
+}
+
+fn scatter(c: &mut Criterion) {
+    let materials = [
+        ("lambertian", Material::lambertian(Color::WHITE, None)),
+        ("metal", Material::metal(Color::WHITE, Some(0.3))),
+        ("dielectric", Material::dielectric(1.5, None)),
+    ];
+    let r = Ray::new(Vec3(0., 0., 2.), Vec3(0.1, -0.2, -1.));
+    let normal = Vec3(0., 0., 1.);
+
+    let mut group = c.benchmark_group("material scatter");
+    for (name, material) in materials {
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(material.scatter(black_box(r), normal, 1., true)))
+        });
+    }
+    group.finish();
+}
+
+fn bvh_traversal(c: &mut Criterion) {
+    let world = random_spheres();
+    let rays = rays(1024);
+
+    let t_range = Interval::new(0.001, Float::INFINITY);
+
+    let mut group = c.benchmark_group("BVH traversal");
+    group.bench_function("closest hit", |b| {
+        b.iter(|| {
+            for r in &rays {
+                black_box(world.hit(black_box(r), t_range));
+            }
+        })
+    });
+    group.bench_function("any hit", |b| {
+        b.iter(|| {
+            for r in &rays {
+                black_box(world.hit_any(black_box(r), t_range));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn full_render(c: &mut Criterion) {
+    let world = random_spheres();
+    let camera = Camera::new(16. / 9., 64, 20., Vec3(13., 2., 3.), Vec3::ZERO, 0.6, 10.)
+        .threads(Some(1))
+        .quiet();
+    let tracer = Tracer::new(20);
+
+    let mut group = c.benchmark_group("full render");
+    group.sample_size(10);
+    group.bench_function("random spheres 64x36, 4 samples", |b| {
+        b.iter(|| black_box(tracer.render(&camera, &world, 4)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ray_sphere, scatter, bvh_traversal, full_render);
+criterion_main!(benches);
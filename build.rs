@@ -0,0 +1,37 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The service of `proto/render_jobs.proto`, whose messages are
+    // defined in `src/grpc.rs`.
+    #[cfg(feature = "grpc")]
+    {
+        use tonic_build::manual::{Builder, Method, Service};
+
+        let methods = [
+            ("submit", "Submit", "JobRequest", "JobId"),
+            ("progress", "Progress", "JobId", "JobProgress"),
+            ("fetch_tile", "FetchTile", "TileRequest", "Tile"),
+            ("fetch_frame", "FetchFrame", "JobId", "Frame"),
+        ];
+
+        let service = methods
+            .into_iter()
+            .fold(
+                Service::builder().name("RenderJobs").package("raycasting"),
+                |service, (name, route_name, input, output)| {
+                    service.method(
+                        Method::builder()
+                            .name(name)
+                            .route_name(route_name)
+                            .input_type(format!("crate::grpc::{}", input))
+                            .output_type(format!("crate::grpc::{}", output))
+                            .codec_path("tonic_prost::ProstCodec")
+                            .build(),
+                    )
+                },
+            )
+            .build();
+
+        Builder::new().build_client(true).compile(&[service]);
+    }
+}
@@ -0,0 +1,132 @@
+#ifndef RAYCASTING_RS_H
+#define RAYCASTING_RS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum RtMaterialKind {
+  RT_LAMBERTIAN,
+  RT_METAL,
+  RT_DIELECTRIC,
+  /**
+   * Light source, its color can be brighter than white.
+   */
+  RT_EMISSIVE,
+} RtMaterialKind;
+
+/**
+ * Objects and camera of a scene, created with `rt_scene_new`.
+ */
+typedef struct RtScene RtScene;
+
+typedef struct RtCamera {
+  float look_from[3];
+  float look_at[3];
+  /**
+   * Direction pointing up in the image.
+   */
+  float up[3];
+  /**
+   * Vertical field of view in degrees.
+   */
+  float fov;
+  /**
+   * Aperture of the defocus blur cone in degrees.
+   */
+  float defocus_angle;
+  float focus_dist;
+} RtCamera;
+
+typedef struct RtMaterial {
+  enum RtMaterialKind kind;
+  float color[3];
+  /**
+   * Blur of the reflections and refractions, 0 disables it.
+   */
+  float fuzz;
+  /**
+   * Used only by the dielectrics.
+   */
+  float refraction_index;
+} RtMaterial;
+
+/**
+ * Camera with the defaults of the command line.
+ */
+struct RtCamera rt_camera_default(void);
+
+/**
+ * Create an empty scene seen by the default camera, to be released
+ * with `rt_scene_free`.
+ */
+struct RtScene *rt_scene_new(void);
+
+/**
+ * # Safety
+ *
+ * `scene` must be null or returned by `rt_scene_new` and not freed yet.
+ */
+void rt_scene_free(struct RtScene *scene);
+
+/**
+ * # Safety
+ *
+ * `scene` must be null or a live scene, and `camera` null or valid.
+ */
+int rt_scene_set_camera(struct RtScene *scene, const struct RtCamera *camera);
+
+/**
+ * # Safety
+ *
+ * `scene` must be null or a live scene, and `center` null or pointing
+ * to 3 floats.
+ */
+int rt_scene_add_sphere(struct RtScene *scene,
+                        const float *center,
+                        float radius,
+                        struct RtMaterial material);
+
+/**
+ * Add the mesh of the triangles whose corners are the `vertices`, given
+ * as `x, y, z` floats, at the `indices`, three for each triangle.
+ *
+ * # Safety
+ *
+ * `scene` must be null or a live scene, `vertices` must point to
+ * `3 * vertex_count` floats and `indices` to `index_count` integers.
+ */
+int rt_scene_add_mesh(struct RtScene *scene,
+                      const float *vertices,
+                      size_t vertex_count,
+                      const uint32_t *indices,
+                      size_t index_count,
+                      struct RtMaterial material);
+
+/**
+ * Render the `scene` in the caller's `pixels`, `width * height * 3`
+ * floats holding the linear RGB color of each pixel row by row.
+ *
+ * # Safety
+ *
+ * `scene` must be null or a live scene, and `pixels` null or pointing
+ * to `width * height * 3` floats.
+ */
+int rt_render(const struct RtScene *scene,
+              uint32_t width,
+              uint32_t height,
+              uint32_t samples,
+              uint32_t max_depth,
+              float *pixels);
+
+/**
+ * Message of the last error of the calling thread, valid until the
+ * next call failing on the same thread.
+ */
+const char *rt_last_error(void);
+
+#endif  /* RAYCASTING_RS_H */
@@ -0,0 +1,86 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interval::Interval,
+    ray::Ray,
+    vec3::{Float, Vec3},
+};
+
+/// Axis aligned bounding box, used to skip the objects missed by a ray
+/// without testing each of their primitives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Box enclosing all the `points`, which must not be empty.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let first = points
+            .next()
+            .expect("a bounding box needs at least a point");
+
+        points.fold(Self::new(first, first), |b, p| Self {
+            min: Vec3(b.min.0.min(p.0), b.min.1.min(p.1), b.min.2.min(p.2)),
+            max: Vec3(b.max.0.max(p.0), b.max.1.max(p.1), b.max.2.max(p.2)),
+        })
+    }
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box enclosing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_points([self.min, self.max, other.min, other.max])
+    }
+
+    pub fn center(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    /// Distance of the point `p` from the box, zero inside it.
+    pub fn distance(&self, p: Vec3) -> Float {
+        let gap = |x: Float, min: Float, max: Float| (min - x).max(x - max).max(0.);
+
+        Vec3::norm(Vec3(
+            gap(p.0, self.min.0, self.max.0),
+            gap(p.1, self.min.1, self.max.1),
+            gap(p.2, self.min.2, self.max.2),
+        ))
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        self.min += offset;
+        self.max += offset;
+    }
+
+    /// Whether the ray crosses the box within `t_range`, with the slab method.
+    pub fn hit(&self, r: &Ray, t_range: Interval) -> bool {
+        let axes = [
+            (r.start.0, r.dir.0, self.min.0, self.max.0),
+            (r.start.1, r.dir.1, self.min.1, self.max.1),
+            (r.start.2, r.dir.2, self.min.2, self.max.2),
+        ];
+        let (mut t_min, mut t_max) = (t_range.min, t_range.max);
+
+        for (start, dir, min, max) in axes {
+            let inv_dir = 1. / dir;
+            let (t0, t1) = ((min - start) * inv_dir, (max - start) * inv_dir);
+            let (t0, t1) = if inv_dir < 0. { (t1, t0) } else { (t0, t1) };
+
+            // Written so that NaNs, from rays lying on a slab, keep the range.
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,57 @@
+//! Pinning of the render threads to the cores the process is allowed to
+//! run on, so that they don't migrate between the cores and their caches.
+
+/// The cores the calling thread is allowed to run on, `None` when they
+/// can't be known, which is only supported on Linux.
+#[cfg(target_os = "linux")]
+pub fn allowed_cores() -> Option<Vec<usize>> {
+    use std::mem;
+
+    // SAFETY: the CPU set is a plain bit mask, valid when zeroed, and its
+    // size is the one passed to the call.
+    unsafe {
+        let mut allowed: libc::cpu_set_t = mem::zeroed();
+        if libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut allowed) != 0 {
+            return None;
+        }
+
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|core| libc::CPU_ISSET(*core, &allowed))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cores() -> Option<Vec<usize>> {
+    None
+}
+
+/// Pin the calling thread to the `index`-th of the cores the process is
+/// allowed to run on, wrapping around when there are more threads than
+/// cores. Returns whether the thread was pinned, which is only
+/// supported on Linux.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(index: usize) -> bool {
+    use std::mem;
+
+    let Some(cores) = allowed_cores() else {
+        return false;
+    };
+    let Some(core) = cores.get(index % cores.len().max(1)) else {
+        return false;
+    };
+
+    // SAFETY: as above, and the core is below `CPU_SETSIZE`.
+    unsafe {
+        let mut pinned: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(*core, &mut pinned);
+        libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &pinned) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_: usize) -> bool {
+    false
+}
@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use crate::{
+    object::Object,
+    quat::Quat,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Values that can be linearly interpolated.
+pub trait Lerp: Copy {
+    fn lerp(a: Self, b: Self, t: Float) -> Self;
+}
+
+impl Lerp for Float {
+    fn lerp(a: Self, b: Self, t: Float) -> Self {
+        a + t * (b - a)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(a: Self, b: Self, t: Float) -> Self {
+        a + t * (b - a)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(a: Self, b: Self, t: Float) -> Self {
+        Color::lerp(a, b, t)
+    }
+}
+
+/// The rotations are interpolated along the shortest arc.
+impl Lerp for Quat {
+    fn lerp(a: Self, b: Self, t: Float) -> Self {
+        Quat::slerp(a, b, t)
+    }
+}
+
+/// Value animated by linearly interpolating between keyframes,
+/// before the first and after the last keyframe the value is held.
+#[derive(Debug, Clone)]
+pub struct Keyframes<T> {
+    keys: Vec<(Float, T)>,
+}
+
+impl<T: Lerp> Keyframes<T> {
+    /// Create the animation from `(time, value)` pairs, `keys`
+    /// must contain at least one keyframe.
+    pub fn new(mut keys: Vec<(Float, T)>) -> Self {
+        assert!(!keys.is_empty(), "an animation needs at least one keyframe");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self { keys }
+    }
+
+    pub fn at(&self, time: Float) -> T {
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+
+        match next {
+            0 => self.keys[0].1,
+            n if n == self.keys.len() => self.keys[n - 1].1,
+            n => {
+                let (t0, v0) = self.keys[n - 1];
+                let (t1, v1) = self.keys[n];
+
+                T::lerp(v0, v1, (time - t0) / (t1 - t0))
+            }
+        }
+    }
+}
+
+/// Position at `time` of a camera starting at `start` and orbiting
+/// around the vertical axis through `center`, a full turn every `period`.
+pub fn turntable(start: Vec3, center: Vec3, period: Float, time: Float) -> Vec3 {
+    let turn = Quat::from_axis_angle(Vec3(0., 1., 0.), 360. * time / period);
+
+    center + turn.rotate(start - center)
+}
+
+/// Path followed by the camera during an animation.
+#[derive(Debug, Clone)]
+pub enum CameraPath {
+    /// Orbit around the point looked at, see [`turntable`].
+    Turntable { period: Float },
+    /// Positions of the camera.
+    Keyframes(Keyframes<Vec3>),
+}
+
+/// Motion of the camera and of some of the objects of a scene, the
+/// rest of the scene stays in place.
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub camera: Option<CameraPath>,
+    /// Translations of the objects in each range, from their position
+    /// in the scene.
+    pub objects: Vec<(Range<usize>, Keyframes<Vec3>)>,
+}
+
+impl Animation {
+    /// Position at `time` of a camera placed at `look_from` in the scene.
+    pub fn look_from(&self, look_from: Vec3, look_at: Vec3, time: Float) -> Vec3 {
+        match &self.camera {
+            Some(CameraPath::Turntable { period }) => turntable(look_from, look_at, *period, time),
+            Some(CameraPath::Keyframes(keyframes)) => keyframes.at(time),
+            None => look_from,
+        }
+    }
+
+    /// Translation at `time` of the object at index `object` in the
+    /// scene, zero when it isn't animated.
+    pub fn offset(&self, object: usize, time: Float) -> Vec3 {
+        self.objects
+            .iter()
+            .find(|(range, _)| range.contains(&object))
+            .map_or(Vec3::ZERO, |(_, keyframes)| keyframes.at(time))
+    }
+
+    /// Move the `objects`, as built from the scene, to where they are
+    /// at `time`.
+    pub fn move_objects(&self, objects: &mut [Object], time: Float) {
+        for (range, keyframes) in &self.objects {
+            let offset = keyframes.at(time);
+
+            for object in &mut objects[range.clone()] {
+                object.translate(offset);
+            }
+        }
+    }
+}
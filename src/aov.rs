@@ -0,0 +1,319 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    camera::Camera,
+    output,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Arbitrary output variables, auxiliary values collected at the
+/// first surface hit by a camera ray.
+#[derive(Debug, Clone, Copy)]
+pub struct Aov {
+    pub albedo: Color,
+    /// World-space normal facing the camera ray, zero when
+    /// the ray escapes the scene.
+    pub normal: Vec3,
+    /// Distance of the hit from the camera, infinite when
+    /// the ray escapes the scene.
+    pub depth: Float,
+    /// World-space position of the hit, zero when the ray
+    /// escapes the scene.
+    pub position: Vec3,
+    /// Displacement in pixels of the hit on the next frame of
+    /// an animation, only known after the whole frame is rendered.
+    pub motion: (Float, Float),
+    /// Fraction of the camera rays that hit a surface.
+    pub alpha: Float,
+    /// Index of the object hit, for a pixel it is the
+    /// object hit by most of the camera rays.
+    pub object: Option<u32>,
+    /// Identifier of the material hit, for a pixel it is the
+    /// material hit by most of the camera rays.
+    pub material: Option<u32>,
+    /// Number of nodes of the BVH visited by the camera ray, for a
+    /// pixel it is the average of its samples.
+    pub bvh_visits: Float,
+    /// Number of bounces of the path of the camera ray before it
+    /// escaped or was absorbed, for a pixel it is the average of its
+    /// samples.
+    pub bounces: Float,
+}
+
+impl Default for Aov {
+    fn default() -> Self {
+        Self {
+            albedo: Color::BLACK,
+            normal: Vec3::ZERO,
+            depth: Float::INFINITY,
+            position: Vec3::ZERO,
+            motion: (0., 0.),
+            alpha: 0.,
+            object: None,
+            material: None,
+            bvh_visits: 0.,
+            bounces: 0.,
+        }
+    }
+}
+
+impl Aov {
+    pub fn average(aovs: &[Aov]) -> Self {
+        let scale = 1.0 / aovs.len() as Float;
+        let hits = aovs.iter().filter(|aov| aov.depth.is_finite());
+        let hit_count = hits.clone().count();
+
+        Self {
+            albedo: aovs.iter().map(|aov| scale * aov.albedo).sum(),
+            normal: aovs
+                .iter()
+                .fold(Vec3::ZERO, |acc, aov| acc + scale * aov.normal),
+            depth: match hit_count {
+                0 => Float::INFINITY,
+                n => hits.clone().map(|aov| aov.depth).sum::<Float>() / n as Float,
+            },
+            position: match hit_count {
+                0 => Vec3::ZERO,
+                n => (1. / n as Float) * hits.fold(Vec3::ZERO, |acc, aov| acc + aov.position),
+            },
+            motion: (0., 0.),
+            alpha: hit_count as Float * scale,
+            object: Self::most_common(aovs.iter().map(|aov| aov.object)),
+            material: Self::most_common(aovs.iter().map(|aov| aov.material)),
+            bvh_visits: aovs.iter().map(|aov| scale * aov.bvh_visits).sum(),
+            bounces: aovs.iter().map(|aov| scale * aov.bounces).sum(),
+        }
+    }
+
+    /// Compute the motion of each pixel of a `width` pixels wide image,
+    /// the hits are moved together with their objects by `displacement`
+    /// and then projected with the `next` camera of the animation.
+    pub fn set_motion<D: Fn(u32) -> Vec3>(
+        aovs: &mut [Aov],
+        width: u32,
+        next: &Camera,
+        displacement: D,
+    ) {
+        for (i, aov) in aovs.iter_mut().enumerate() {
+            let Some(object) = aov.object else {
+                continue;
+            };
+            let (x, y) = ((i as u32 % width) as Float, (i as u32 / width) as Float);
+
+            aov.motion = next
+                .project(aov.position + displacement(object))
+                .map(|(next_x, next_y)| (next_x - x, next_y - y))
+                .unwrap_or_default();
+        }
+    }
+
+    fn most_common<I: Iterator<Item = Option<u32>>>(ids: I) -> Option<u32> {
+        let mut ids = ids.collect::<Vec<_>>();
+        ids.sort_unstable();
+
+        ids.chunk_by(|a, b| a == b)
+            .max_by_key(|run| run.len())
+            .and_then(|run| run[0])
+    }
+}
+
+/// Color coding of an identifier, the bits of the identifier
+/// are mixed so that close identifiers get distinct colors.
+fn id_color(id: Option<u32>) -> Color {
+    let Some(mut x) = id else {
+        return Color::BLACK;
+    };
+
+    // Finalizer of the MurmurHash3 hash function, the identifier is
+    // offset as 0 would be mapped to the black of the background.
+    x = x.wrapping_add(0x9e37_79b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^= x >> 16;
+
+    let [r, g, b, _] = x.to_le_bytes();
+    Color::new((r as Float / 255., g as Float / 255., b as Float / 255.))
+}
+
+/// Color of the heat `t` from 0 to 1 on a scale going from black
+/// through purple, red and orange to a pale yellow, which stays readable
+/// in gray.
+fn heat_color(t: Float) -> Color {
+    const STOPS: [(Float, Float, Float); 5] = [
+        (0., 0., 0.),
+        (0.34, 0.06, 0.43),
+        (0.87, 0.27, 0.23),
+        (0.99, 0.65, 0.04),
+        (0.99, 1., 0.64),
+    ];
+
+    let x = t.clamp(0., 1.) * (STOPS.len() - 1) as Float;
+    let i = (x.floor() as usize).min(STOPS.len() - 2);
+
+    Color::lerp(
+        Color::new(STOPS[i]),
+        Color::new(STOPS[i + 1]),
+        x - i as Float,
+    )
+}
+
+/// Auxiliary images that can be written next to the render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AovPass {
+    Albedo,
+    Normal,
+    Depth,
+    ObjectId,
+    MaterialId,
+    Motion,
+    /// Heatmap of the number of nodes of the BVH visited by the camera
+    /// rays, the hot spots are where the hierarchy fits the objects
+    /// badly.
+    Bvh,
+    /// Heatmap of the average number of bounces of the paths, the hot
+    /// spots are where the rays are trapped, like inside dielectrics.
+    Bounces,
+}
+
+impl AovPass {
+    pub const NAMES: [&'static str; 8] = [
+        "albedo",
+        "normal",
+        "depth",
+        "object-id",
+        "material-id",
+        "motion",
+        "bvh",
+        "bounces",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "albedo" => Some(Self::Albedo),
+            "normal" => Some(Self::Normal),
+            "depth" => Some(Self::Depth),
+            "object-id" => Some(Self::ObjectId),
+            "material-id" => Some(Self::MaterialId),
+            "motion" => Some(Self::Motion),
+            "bvh" => Some(Self::Bvh),
+            "bounces" => Some(Self::Bounces),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Albedo => "albedo",
+            Self::Normal => "normal",
+            Self::Depth => "depth",
+            Self::ObjectId => "object-id",
+            Self::MaterialId => "material-id",
+            Self::Motion => "motion",
+            Self::Bvh => "bvh",
+            Self::Bounces => "bounces",
+        }
+    }
+
+    /// Path of the pass, the name of the pass is added before
+    /// the extension of the `output` path.
+    pub fn path(self, output: &Path) -> PathBuf {
+        output::with_suffix(output, self.name())
+    }
+
+    /// Encode the pass as displayable colors, or store the raw
+    /// values when the output is `linear`.
+    pub fn colors(self, aovs: &[Aov], linear: bool) -> Vec<Color> {
+        match (self, linear) {
+            (Self::Albedo, false) => aovs.iter().map(|aov| aov.albedo.to_gamma()).collect(),
+            (Self::Albedo, true) => aovs.iter().map(|aov| aov.albedo).collect(),
+            // Map each component from [-1,1] to [0,1].
+            (Self::Normal, false) => aovs
+                .iter()
+                .map(|aov| {
+                    let n = 0.5 * (aov.normal + Vec3(1., 1., 1.));
+                    Color::new(n.into())
+                })
+                .collect(),
+            (Self::Normal, true) => aovs
+                .iter()
+                .map(|aov| Color::new(aov.normal.into()))
+                .collect(),
+            // Normalize the depth by the farthest hit, escaped rays are white.
+            (Self::Depth, false) => {
+                let max_depth = aovs
+                    .iter()
+                    .map(|aov| aov.depth)
+                    .filter(|d| d.is_finite())
+                    .fold(0., Float::max);
+
+                aovs.iter()
+                    .map(|aov| match aov.depth {
+                        d if d.is_finite() && max_depth > 0. => d / max_depth,
+                        _ => 1.,
+                    })
+                    .map(|d| Color::new((d, d, d)))
+                    .collect()
+            }
+            (Self::Depth, true) => aovs
+                .iter()
+                .map(|aov| Color::new((aov.depth, aov.depth, aov.depth)))
+                .collect(),
+            (Self::ObjectId, _) => aovs.iter().map(|aov| id_color(aov.object)).collect(),
+            (Self::MaterialId, _) => aovs.iter().map(|aov| id_color(aov.material)).collect(),
+            // Map each component from [-max,max] to [0,1], where max is
+            // the largest displacement, static pixels are gray.
+            (Self::Motion, false) => {
+                let max_motion = aovs
+                    .iter()
+                    .flat_map(|aov| [aov.motion.0.abs(), aov.motion.1.abs()])
+                    .fold(0., Float::max)
+                    .max(Float::EPSILON);
+
+                aovs.iter()
+                    .map(|aov| {
+                        let (x, y) = aov.motion;
+                        Color::new((0.5 + 0.5 * x / max_motion, 0.5 + 0.5 * y / max_motion, 0.5))
+                    })
+                    .collect()
+            }
+            (Self::Motion, true) => aovs
+                .iter()
+                .map(|aov| Color::new((aov.motion.0, aov.motion.1, 0.)))
+                .collect(),
+            // Scale the visits by the most of any pixel.
+            (Self::Bvh, false) => {
+                let max_visits = aovs
+                    .iter()
+                    .map(|aov| aov.bvh_visits)
+                    .fold(0., Float::max)
+                    .max(1.);
+
+                aovs.iter()
+                    .map(|aov| heat_color(aov.bvh_visits / max_visits))
+                    .collect()
+            }
+            (Self::Bvh, true) => aovs
+                .iter()
+                .map(|aov| Color::new((aov.bvh_visits, aov.bvh_visits, aov.bvh_visits)))
+                .collect(),
+            // Scale the bounces by the most of any pixel.
+            (Self::Bounces, false) => {
+                let max_bounces = aovs
+                    .iter()
+                    .map(|aov| aov.bounces)
+                    .fold(0., Float::max)
+                    .max(1.);
+
+                aovs.iter()
+                    .map(|aov| heat_color(aov.bounces / max_bounces))
+                    .collect()
+            }
+            (Self::Bounces, true) => aovs
+                .iter()
+                .map(|aov| Color::new((aov.bounces, aov.bounces, aov.bounces)))
+                .collect(),
+        }
+    }
+}
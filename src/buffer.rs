@@ -0,0 +1,183 @@
+use std::{
+    io::{self, Seek, Write},
+    ops::{Deref, DerefMut, Index, IndexMut},
+    path::Path,
+    slice::{ChunksExact, ChunksExactMut},
+};
+
+use crate::{
+    output::{self, ImageFormat},
+    vec3::Color,
+};
+
+/// Pixels of an image stored contiguously, row after row from the top,
+/// as the [`Camera`](crate::camera::Camera) maps them.
+///
+/// It dereferences to the slice of all the pixels, while single pixels
+/// are indexed by their `(x, y)` coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBuffer<T = Color> {
+    width: u32,
+    height: u32,
+    pixels: Vec<T>,
+}
+
+impl<T> ImageBuffer<T> {
+    /// Image of the `pixels` in row-major order, which must be exactly
+    /// `width * height`.
+    pub fn new(width: u32, height: u32, pixels: Vec<T>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "the pixels don't fill a {}x{} image",
+            width,
+            height
+        );
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Image of `width` by `height` pixels all set to `value`.
+    pub fn filled(width: u32, height: u32, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![value; width as usize * height as usize])
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixel at column `x` and row `y`, if inside the image.
+    pub fn get(&self, x: u32, y: u32) -> Option<&T> {
+        match x < self.width && y < self.height {
+            true => self.pixels.get(self.offset(x, y)),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
+        match x < self.width && y < self.height {
+            true => {
+                let offset = self.offset(x, y);
+                self.pixels.get_mut(offset)
+            }
+            false => None,
+        }
+    }
+
+    /// The rows of pixels, from the top.
+    pub fn rows(&self) -> ChunksExact<'_, T> {
+        // Chunks can't be empty, an image without columns has no rows.
+        self.pixels.chunks_exact(self.width.max(1) as usize)
+    }
+
+    pub fn rows_mut(&mut self) -> ChunksExactMut<'_, T> {
+        self.pixels.chunks_exact_mut(self.width.max(1) as usize)
+    }
+
+    pub fn into_pixels(self) -> Vec<T> {
+        self.pixels
+    }
+
+    /// Image of the same size with `f` applied to each pixel.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> ImageBuffer<U> {
+        ImageBuffer {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.into_iter().map(f).collect(),
+        }
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl ImageBuffer<Color> {
+    /// Save the image to `path` encoded as `format`, see [`output::save`].
+    pub fn save(&self, path: &Path, format: ImageFormat, alpha: Option<&[f32]>) -> io::Result<()> {
+        output::save(path, format, self.width, self.height, &self.pixels, alpha)
+    }
+
+    /// Write the image to `w` encoded as `format`, see [`output::write`].
+    pub fn write<W: Write + Seek>(
+        &self,
+        w: W,
+        format: ImageFormat,
+        alpha: Option<&[f32]>,
+    ) -> io::Result<()> {
+        output::write(w, format, self.width, self.height, &self.pixels, alpha)
+    }
+}
+
+impl<T> Deref for ImageBuffer<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.pixels
+    }
+}
+
+impl<T> DerefMut for ImageBuffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pixels
+    }
+}
+
+/// The pixel at column `x` and row `y`, panicking outside the image.
+impl<T> Index<(u32, u32)> for ImageBuffer<T> {
+    type Output = T;
+    fn index(&self, (x, y): (u32, u32)) -> &Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) outside of a {}x{} image",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+
+        &self.pixels[self.offset(x, y)]
+    }
+}
+
+impl<T> IndexMut<(u32, u32)> for ImageBuffer<T> {
+    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) outside of a {}x{} image",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+
+        let offset = self.offset(x, y);
+        &mut self.pixels[offset]
+    }
+}
+
+impl<T> IntoIterator for ImageBuffer<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.pixels.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ImageBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.pixels.iter()
+    }
+}
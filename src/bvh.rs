@@ -0,0 +1,217 @@
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    object::Object,
+    ray::Ray,
+    stats,
+    vec3::Vec3,
+};
+
+#[derive(Debug, Clone)]
+enum Node {
+    /// The leaves are numbered from left to right, as the objects are
+    /// stored, the `id` is the index of the object the hierarchy was
+    /// built from, which the statistics count.
+    Leaf { object: usize, id: usize },
+    /// The left child follows the branch, while the right one is
+    /// stored after the whole left subtree.
+    Branch { bounds: Aabb, right: usize },
+}
+
+/// Size of a [`Bvh`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Number of nodes from the root to the deepest leaf.
+    pub depth: usize,
+    /// Memory taken by the nodes.
+    pub bytes: usize,
+}
+
+/// Bounding volume hierarchy of the objects of the world, so that a
+/// ray only tests the objects whose boxes it crosses.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+}
+
+impl Bvh {
+    /// Build the hierarchy of the `objects`, splitting them in halves
+    /// along the axis where their centers are the most spread.
+    ///
+    /// The objects must then be stored in the order of the leaves, which
+    /// is returned as the index in `objects` of the object of each leaf.
+    /// This way the objects close in space are close in memory too, and
+    /// the traversal mostly reads memory already cached.
+    pub fn new(objects: &[Object]) -> (Self, Vec<usize>) {
+        let bounds = objects.iter().map(Object::bounds).collect::<Vec<_>>();
+        let mut indices = (0..objects.len()).collect::<Vec<_>>();
+        // A binary tree has one branch less than its leaves, so that the
+        // nodes are allocated once.
+        let mut bvh = Self {
+            nodes: Vec::with_capacity((2 * objects.len()).saturating_sub(1)),
+        };
+        let mut order = Vec::with_capacity(objects.len());
+
+        if !indices.is_empty() {
+            bvh.build(&bounds, &mut indices, &mut order);
+        }
+
+        (bvh, order)
+    }
+
+    fn build(&mut self, bounds: &[Aabb], indices: &mut [usize], order: &mut Vec<usize>) {
+        if let [object] = indices {
+            self.nodes.push(Node::Leaf {
+                object: order.len(),
+                id: *object,
+            });
+            order.push(*object);
+            return;
+        }
+
+        let enclosing = indices
+            .iter()
+            .map(|i| bounds[*i])
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        let centers = Aabb::from_points(indices.iter().map(|i| bounds[*i].center()));
+        let Vec3(x, y, z) = centers.max - centers.min;
+        let axis = if x >= y && x >= z {
+            0
+        } else if y >= z {
+            1
+        } else {
+            2
+        };
+        let key = |i: &usize| {
+            let c = bounds[*i].center();
+            [c.0, c.1, c.2][axis]
+        };
+        indices.sort_unstable_by(|a, b| key(a).total_cmp(&key(b)));
+
+        let branch = self.nodes.len();
+        self.nodes.push(Node::Branch {
+            bounds: enclosing,
+            right: 0,
+        });
+
+        let (left, right) = indices.split_at_mut(indices.len() / 2);
+        self.build(bounds, left, order);
+        let right_start = self.nodes.len();
+        self.build(bounds, right, order);
+
+        if let Node::Branch { right, .. } = &mut self.nodes[branch] {
+            *right = right_start;
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        BvhStats {
+            nodes: self.nodes.len(),
+            leaves: self
+                .nodes
+                .iter()
+                .filter(|node| matches!(node, Node::Leaf { .. }))
+                .count(),
+            depth: match self.nodes.is_empty() {
+                true => 0,
+                false => self.depth(0),
+            },
+            bytes: self.nodes.capacity() * size_of::<Node>(),
+        }
+    }
+
+    fn depth(&self, node: usize) -> usize {
+        match self.nodes[node] {
+            Node::Leaf { .. } => 1,
+            Node::Branch { right, .. } => 1 + self.depth(node + 1).max(self.depth(right)),
+        }
+    }
+
+    /// Closest hit of the ray `r` with the `objects` the hierarchy was
+    /// built from, stored in the order of its leaves, together with the
+    /// index of the object hit in `objects`.
+    pub fn hit<'a>(
+        &self,
+        objects: &'a [Object],
+        r: &Ray,
+        t_range: Interval,
+    ) -> Option<(usize, HitRecord<'a>)> {
+        self.traverse(objects, r, t_range).0
+    }
+
+    /// Like [`Bvh::hit`], also returning the number of nodes visited
+    /// to find the hit, which shows how well the hierarchy fits the
+    /// objects along the ray.
+    pub fn traverse<'a>(
+        &self,
+        objects: &'a [Object],
+        r: &Ray,
+        t_range: Interval,
+    ) -> (Option<(usize, HitRecord<'a>)>, u32) {
+        let mut visits = 0;
+        let hit = match self.nodes.is_empty() {
+            true => None,
+            false => self.hit_node(0, objects, r, t_range, &mut visits),
+        };
+
+        (hit, visits)
+    }
+
+    fn hit_node<'a>(
+        &self,
+        node: usize,
+        objects: &'a [Object],
+        r: &Ray,
+        t_range: Interval,
+        visits: &mut u32,
+    ) -> Option<(usize, HitRecord<'a>)> {
+        stats::count(|s| s.bvh_node_visits += 1);
+        *visits += 1;
+
+        match self.nodes[node] {
+            Node::Leaf { object, id } => {
+                stats::count(|s| s.intersection_tests += 1);
+                stats::count_object(id, |s| s.intersection_tests += 1);
+                objects[object].hit(r, t_range).map(|hit| (object, hit))
+            }
+            Node::Branch { bounds, right } => {
+                if !bounds.hit(r, t_range) {
+                    return None;
+                }
+
+                let left = self.hit_node(node + 1, objects, r, t_range, visits);
+                let max_t = left.as_ref().map_or(t_range.max, |(_, hit)| hit.t);
+
+                self.hit_node(right, objects, r, t_range.until(max_t), visits)
+                    .or(left)
+            }
+        }
+    }
+
+    /// Whether the ray `r` hits any of the `objects` within `t_range`,
+    /// stopping at the first hit found.
+    pub fn hit_any(&self, objects: &[Object], r: &Ray, t_range: Interval) -> bool {
+        !self.nodes.is_empty() && self.hit_any_node(0, objects, r, t_range)
+    }
+
+    fn hit_any_node(&self, node: usize, objects: &[Object], r: &Ray, t_range: Interval) -> bool {
+        stats::count(|s| s.bvh_node_visits += 1);
+
+        match self.nodes[node] {
+            Node::Leaf { object, id } => {
+                stats::count(|s| s.intersection_tests += 1);
+                stats::count_object(id, |s| s.intersection_tests += 1);
+                objects[object].hit_any(r, t_range)
+            }
+            Node::Branch { bounds, right } => {
+                bounds.hit(r, t_range)
+                    && (self.hit_any_node(node + 1, objects, r, t_range)
+                        || self.hit_any_node(right, objects, r, t_range))
+            }
+        }
+    }
+}
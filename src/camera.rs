@@ -1,17 +1,85 @@
 use std::{
     ops::Range,
-    sync::atomic::{self, AtomicU32},
+    sync::{
+        atomic::{self, AtomicU32, AtomicU64},
+        Arc, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
+use indicatif::{HumanCount, ProgressBar, ProgressStyle};
 use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    affinity,
+    buffer::ImageBuffer,
+    film::{FilterSampler, PixelFilter, Sample},
+    pause::Pause,
     ray::Ray,
-    vec3::{Color, Vec3},
+    vec3::{Float, Vec3},
 };
 
+/// Rectangular region of the image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Split a `width`x`height` image in square tiles of `size` pixels,
+    /// the tiles on the right and bottom edges may be smaller.
+    pub fn split(width: u32, height: u32, size: u32) -> Vec<Self> {
+        (0..height)
+            .step_by(size as usize)
+            .flat_map(|y| {
+                (0..width).step_by(size as usize).map(move |x| Self {
+                    x,
+                    y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parameters from which the camera is built, which is serialized
+/// as its settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CameraSettings {
+    pub aspect_ratio: Float,
+    pub width: u32,
+    /// Vertical field of view in degrees.
+    pub v_fov: Float,
+    pub look_from: Vec3,
+    pub look_at: Vec3,
+    /// Aperture of the defocus blur cone in degrees.
+    pub defocus_angle: Float,
+    pub focus_dist: Float,
+    /// Direction pointing up in the image.
+    #[cfg_attr(feature = "serde", serde(default = "default_up"))]
+    pub vup: Vec3,
+}
+
+fn default_up() -> Vec3 {
+    Vec3(0., 1., 0.)
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "CameraSettings", from = "CameraSettings")
+)]
 pub struct Camera {
+    settings: CameraSettings,
     width: u32,
     height: u32,
     defocus_disk_u: Vec3,
@@ -20,28 +88,35 @@ pub struct Camera {
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
     pixel_00: Vec3,
+    quiet: bool,
+    threads: u32,
+    /// Each thread stays on its own core.
+    pin_threads: bool,
+    pause: Option<Arc<Pause>>,
+    filter: FilterSampler,
 }
 
-impl Camera {
-    pub fn new(
-        aspect_ratio: f32,
-        width: u32,
-        v_fov: f32,
-        look_from: Vec3,
-        look_at: Vec3,
-        defocus_angle: f32,
-        focus_dist: f32,
-    ) -> Self {
-        let vup = Vec3(0., 1., 0.);
+impl From<CameraSettings> for Camera {
+    fn from(settings: CameraSettings) -> Self {
+        let CameraSettings {
+            aspect_ratio,
+            width,
+            v_fov,
+            look_from,
+            look_at,
+            defocus_angle,
+            focus_dist,
+            vup,
+        } = settings;
         let camera_center = look_from;
 
-        let height = (width as f32 / aspect_ratio) as u32;
+        let height = (width as Float / aspect_ratio) as u32;
 
         // Determine viewport dimensions.
         let theta = v_fov.to_radians();
-        let h = f32::tan(theta / 2.);
+        let h = Float::tan(theta / 2.);
         let viewport_h = 2. * h * focus_dist;
-        let viewport_w = viewport_h * (width as f32 / height as f32);
+        let viewport_w = viewport_h * (width as Float / height as Float);
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
         let w = Vec3::unit(look_from - look_at);
@@ -53,8 +128,8 @@ impl Camera {
         let viewport_v = viewport_h * -v;
 
         // Calculate the horizontal and vertical delta vectors from pixel to pixel.
-        let pixel_delta_u = (1.0 / width as f32) * viewport_u;
-        let pixel_delta_v = (1.0 / height as f32) * viewport_v;
+        let pixel_delta_u = (1.0 / width as Float) * viewport_u;
+        let pixel_delta_v = (1.0 / height as Float) * viewport_v;
 
         // Calculate the location of the upper left pixel.
         let viewport_upper_left =
@@ -62,11 +137,12 @@ impl Camera {
         let pixel_00 = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
 
         // Calculate the camera defocus disk basis vectors.
-        let defocus_radius = focus_dist * f32::tan((defocus_angle / 2.).to_radians());
+        let defocus_radius = focus_dist * Float::tan((defocus_angle / 2.).to_radians());
         let defocus_disk_u = u * defocus_radius;
         let defocus_disk_v = v * defocus_radius;
 
         Self {
+            settings,
             width,
             height,
             defocus_disk_u,
@@ -75,77 +151,361 @@ impl Camera {
             pixel_delta_u,
             pixel_delta_v,
             pixel_00,
+            quiet: false,
+            // Without threads, as in WebAssembly, the rays are mapped
+            // on the calling thread.
+            threads: thread::available_parallelism().map_or(1, |n| n.get() as u32),
+            pin_threads: false,
+            pause: None,
+            filter: FilterSampler::default(),
+        }
+    }
+}
+
+impl From<Camera> for CameraSettings {
+    fn from(camera: Camera) -> Self {
+        camera.settings
+    }
+}
+
+impl Camera {
+    pub fn new(
+        aspect_ratio: Float,
+        width: u32,
+        v_fov: Float,
+        look_from: Vec3,
+        look_at: Vec3,
+        defocus_angle: Float,
+        focus_dist: Float,
+    ) -> Self {
+        Self::from(CameraSettings {
+            aspect_ratio,
+            width,
+            v_fov,
+            look_from,
+            look_at,
+            defocus_angle,
+            focus_dist,
+            vup: default_up(),
+        })
+    }
+
+    /// Orient the camera so that `vup` points up in the image.
+    pub fn up(self, vup: Vec3) -> Self {
+        Self {
+            quiet: self.quiet,
+            threads: self.threads,
+            pin_threads: self.pin_threads,
+            pause: self.pause,
+            filter: self.filter,
+            ..Self::from(CameraSettings {
+                vup,
+                ..self.settings
+            })
         }
     }
 
-    pub fn ray_map<F: Fn(&[Ray]) -> Color + Sync>(&self, samples: u32, f: F) -> Vec<Vec<Color>> {
+    pub fn settings(&self) -> CameraSettings {
+        self.settings
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Width covered by a pixel at `distance` from the camera, in the
+    /// units of the scene.
+    pub fn pixel_size(&self, distance: Float) -> Float {
+        Vec3::norm(self.pixel_delta_u) * distance / self.settings.focus_dist
+    }
+
+    /// Coordinates in pixels of the point `p` projected on the image,
+    /// the center of the top left pixel is at the origin. Returns `None`
+    /// for points behind the camera.
+    pub fn project(&self, p: Vec3) -> Option<(Float, Float)> {
+        let normal = Vec3::cross(self.pixel_delta_v, self.pixel_delta_u);
+        let dir = p - self.camera_center;
+
+        let t = Vec3::dot(self.pixel_00 - self.camera_center, normal) / Vec3::dot(dir, normal);
+        if t.is_nan() || t <= 0. {
+            return None;
+        }
+
+        let q = self.camera_center + t * dir - self.pixel_00;
+        let x =
+            Vec3::dot(q, self.pixel_delta_u) / Vec3::dot(self.pixel_delta_u, self.pixel_delta_u);
+        let y =
+            Vec3::dot(q, self.pixel_delta_v) / Vec3::dot(self.pixel_delta_v, self.pixel_delta_v);
+
+        Some((x, y))
+    }
+
+    /// Map the rays with the given number of `threads`, when missing
+    /// one thread is used for each core. With a single thread the rays
+    /// are mapped on the calling thread, unless it is pinned, without
+    /// logging the progress.
+    pub fn threads(mut self, threads: Option<u32>) -> Self {
+        if let Some(threads) = threads {
+            self.threads = threads;
+        }
+        self
+    }
+
+    /// Pin each of the threads mapping the rays to a different core, when
+    /// `pin` is set and the platform supports it.
+    pub fn pin_threads(mut self, pin: bool) -> Self {
+        self.pin_threads = pin;
+        self
+    }
+
+    /// Don't log the progress while mapping the rays.
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Shoot the rays of each pixel within the reach of the `filter`,
+    /// weighted by it, instead of uniformly over the pixel.
+    pub fn filter(mut self, filter: PixelFilter) -> Self {
+        self.filter = FilterSampler::new(filter);
+        self
+    }
+
+    /// Stop mapping the rays while the `pause` is on.
+    pub fn pausable(mut self, pause: Arc<Pause>) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Map the `samples` rays of each pixel with `f`, the results of each
+    /// row are passed to `on_row` as soon as the row is completed.
+    pub fn ray_map<T, F, R>(&self, samples: u32, f: F, on_row: R) -> ImageBuffer<T>
+    where
+        T: Send,
+        F: Fn(&[Sample]) -> T + Sync,
+        R: Fn(u32, &[T]) + Sync,
+    {
         let f = &f;
-        let progress = AtomicU32::new(0);
+        let on_row = &on_row;
+        let progress = Progress::new(self.height, "rows", self.threads);
         let progress = &progress;
 
-        thread::scope(|s| {
-            let tot_t = thread::available_parallelism().unwrap().get() as u32;
-
-            let mut handles = Vec::<_>::with_capacity(tot_t as usize);
-
-            for curr_t in 0..tot_t {
-                let h = s.spawn(move || {
-                    self.ray_map_inner(
-                        samples,
-                        Self::thread_partition(self.height, curr_t, tot_t),
-                        progress,
-                        f,
-                    )
-                });
+        // The partitions are consecutive rows, so that they are joined in
+        // the order of the threads.
+        let parts = Mutex::new((0..self.threads).map(|_| Vec::new()).collect::<Vec<_>>());
+        let worker = |thread: usize| {
+            let pixels = self.ray_map_inner(
+                samples,
+                Self::thread_partition(self.height, thread as u32, self.threads),
+                progress,
+                thread,
+                f,
+                on_row,
+            );
+            parts.lock().unwrap()[thread] = pixels;
+        };
 
-                handles.push(h);
+        self.run_workers(worker, samples, progress);
+
+        let pixels = parts.into_inner().unwrap().into_iter().flatten().collect();
+        ImageBuffer::new(self.width, self.height, pixels)
+    }
+
+    /// Map the `samples` rays of each pixel with `f` like `ray_map`, but
+    /// only pass the results of each row to `on_row` without keeping them.
+    /// The threads take the rows in order, so that they are completed
+    /// roughly from top to bottom.
+    pub fn ray_stream<T, F, R>(&self, samples: u32, f: F, on_row: R)
+    where
+        F: Fn(&[Sample]) -> T + Sync,
+        R: Fn(u32, &[T]) + Sync,
+    {
+        let next_row = AtomicU32::new(0);
+        let progress = Progress::new(self.height, "rows", self.threads);
+        let (f, on_row, next_row, progress) = (&f, &on_row, &next_row, &progress);
+
+        let worker = move |thread| {
+            let mut rays = Self::sample_buffer(samples);
+            let mut row = Vec::with_capacity(self.width as usize);
+
+            loop {
+                self.wait_resumed();
+                let h = next_row.fetch_add(1, atomic::Ordering::Relaxed);
+                if h >= self.height {
+                    break;
+                }
+
+                row.clear();
+                self.map_row(h, 0..self.width, &mut rays, f, &mut row);
+                on_row(h, &row);
+                progress.complete(thread, self.width);
             }
+        };
 
-            s.spawn(move || Self::progress_logger(self.height, progress));
+        self.run_workers(worker, samples, progress);
+    }
 
-            handles
-                .into_iter()
-                .map(|h| h.join().unwrap())
-                .collect::<Vec<_>>()
-        })
+    /// Map the `samples` rays of each pixel with `f` like `ray_stream`,
+    /// but divide the image in square tiles of `tile_size` pixels, the
+    /// results of each tile are passed to `on_tile` in row-major order.
+    pub fn ray_tiles<T, F, R>(&self, samples: u32, tile_size: u32, f: F, on_tile: R)
+    where
+        F: Fn(&[Sample]) -> T + Sync,
+        R: Fn(Tile, &[T]) + Sync,
+    {
+        let tiles = Tile::split(self.width, self.height, tile_size);
+        let next_tile = AtomicU32::new(0);
+        let progress = Progress::new(tiles.len() as u32, "tiles", self.threads);
+        let (f, on_tile, tiles, next_tile, progress) =
+            (&f, &on_tile, &tiles, &next_tile, &progress);
+
+        let worker = move |thread| {
+            let mut rays = Self::sample_buffer(samples);
+            let mut pixels = Vec::with_capacity((tile_size * tile_size) as usize);
+
+            while let Some(tile) =
+                tiles.get(next_tile.fetch_add(1, atomic::Ordering::Relaxed) as usize)
+            {
+                self.wait_resumed();
+                let _span = tracing::trace_span!("tile", x = tile.x, y = tile.y).entered();
+                pixels.clear();
+                for h in tile.y..tile.y + tile.height {
+                    self.map_row(h, tile.x..tile.x + tile.width, &mut rays, f, &mut pixels);
+                }
+
+                on_tile(*tile, &pixels);
+                progress.complete(thread, tile.width * tile.height);
+            }
+        };
+
+        self.run_workers(worker, samples, progress);
     }
 
-    fn ray_map_inner<F: Fn(&[Ray]) -> Color + Sync>(
+    /// Map the `samples` rays of each pixel of the row `h` with `f` on
+    /// the calling thread, for callers which schedule the rows on their
+    /// own, for example on the web workers of a browser.
+    pub fn ray_row<T, F>(&self, h: u32, samples: u32, f: F) -> Vec<T>
+    where
+        F: Fn(&[Sample]) -> T,
+    {
+        let mut rays = Self::sample_buffer(samples);
+        let mut row = Vec::with_capacity(self.width as usize);
+        self.map_row(h, 0..self.width, &mut rays, &f, &mut row);
+
+        row
+    }
+
+    /// The `samples` rays of the pixel at column `x` and row `y`, drawn
+    /// as when rendering the image.
+    pub fn pixel_samples(&self, x: u32, y: u32, samples: u32) -> Vec<Sample> {
+        let mut rays = Self::sample_buffer(samples);
+        let mut pixel = Vec::with_capacity(1);
+        self.map_row(
+            y,
+            x..x + 1,
+            &mut rays,
+            &|r: &[Sample]| r.to_vec(),
+            &mut pixel,
+        );
+
+        pixel.pop().unwrap_or_default()
+    }
+
+    /// Run the `worker` on each thread, passing it the index of the
+    /// thread, or only on the calling one with a single thread. The
+    /// `progress` of the `samples` rays of each pixel is shown unless quiet
+    /// or with a single thread.
+    fn run_workers<W: Fn(usize) + Sync>(&self, worker: W, samples: u32, progress: &Progress) {
+        // A single pinned thread is still spawned, so that the calling
+        // thread isn't left pinned after the render.
+        if self.threads == 1 && !self.pin_threads {
+            return worker(0);
+        }
+
+        thread::scope(|s| {
+            let worker = &worker;
+            for thread in 0..self.threads as usize {
+                s.spawn(move || {
+                    if self.pin_threads && !affinity::pin_current_thread(thread) && thread == 0 {
+                        tracing::warn!("failed to pin the threads to the cores");
+                    }
+                    worker(thread)
+                });
+            }
+
+            if !self.quiet && self.threads > 1 {
+                s.spawn(move || self.progress_logger(samples, progress));
+            }
+        });
+    }
+
+    fn ray_map_inner<T, F, R>(
         &self,
         samples: u32,
         h_range: Range<u32>,
-        progress: &AtomicU32,
+        progress: &Progress,
+        thread: usize,
         f: &F,
-    ) -> Vec<Color> {
-        let mut rays = (0..samples)
-            .map(|_| Ray::new(Vec3::ZERO, Vec3::ZERO))
-            .take(samples as usize)
-            .collect::<Vec<_>>();
+        on_row: &R,
+    ) -> Vec<T>
+    where
+        F: Fn(&[Sample]) -> T + Sync,
+        R: Fn(u32, &[T]) + Sync,
+    {
+        let mut rays = Self::sample_buffer(samples);
 
         let mut colors = Vec::with_capacity(self.width as usize * h_range.len());
 
         for h in h_range {
-            progress.fetch_add(1, atomic::Ordering::Relaxed);
-            for w in 0..self.width {
-                let pixel_center = self.pixel_00
-                    + (w as f32 * self.pixel_delta_u)
-                    + (h as f32 * self.pixel_delta_v);
-
-                for ray in &mut rays {
-                    let viewport_pos = pixel_center + Self::pixel_sample_offset(self);
-                    let ray_origin = self.defocus_disk_sample();
-                    let dir = viewport_pos - ray_origin;
-
-                    *ray = Ray::new(ray_origin, dir);
-                }
+            self.wait_resumed();
+            self.map_row(h, 0..self.width, &mut rays, f, &mut colors);
 
-                colors.push(f(&rays));
-            }
+            on_row(h, &colors[colors.len() - self.width as usize..]);
+            progress.complete(thread, self.width);
         }
 
         colors
     }
 
+    /// Map the rays of each pixel in the columns `w_range` of the row `h`
+    /// with `f`, pushing the results to `out`.
+    fn map_row<T, F>(
+        &self,
+        h: u32,
+        w_range: Range<u32>,
+        rays: &mut [Sample],
+        f: &F,
+        out: &mut Vec<T>,
+    ) where
+        F: Fn(&[Sample]) -> T,
+    {
+        let _span = tracing::trace_span!("row", y = h).entered();
+
+        for w in w_range {
+            let pixel_center = self.pixel_00
+                + (w as Float * self.pixel_delta_u)
+                + (h as Float * self.pixel_delta_v);
+
+            for sample in rays.iter_mut() {
+                let (offset, weight) = self.pixel_sample_offset();
+                let viewport_pos = pixel_center + offset;
+                let ray_origin = self.defocus_disk_sample();
+                let dir = viewport_pos - ray_origin;
+
+                *sample = Sample {
+                    ray: Ray::new(ray_origin, dir),
+                    weight,
+                };
+            }
+
+            out.push(f(rays));
+        }
+    }
+
     #[inline]
     fn defocus_disk_sample(&self) -> Vec3 {
         let p = Vec3::rand_in_unit_disk();
@@ -153,12 +513,31 @@ impl Camera {
         self.camera_center + p.0 * self.defocus_disk_u + p.1 * self.defocus_disk_v
     }
 
+    /// Random offset from the center of a pixel within the reach of the
+    /// filter, together with the weight of the sample.
     #[inline]
-    fn pixel_sample_offset(&self) -> Vec3 {
-        let x = rand::thread_rng().gen::<f32>() - 0.5;
-        let y = rand::thread_rng().gen::<f32>() - 0.5;
+    fn pixel_sample_offset(&self) -> (Vec3, Float) {
+        let mut rng = rand::thread_rng();
+        let (x, y, weight) = self.filter.sample((rng.gen(), rng.gen()));
+
+        ((x * self.pixel_delta_u) + (y * self.pixel_delta_v), weight)
+    }
+
+    /// Buffer of the `samples` of a pixel, reused for all the pixels
+    /// mapped by a thread.
+    fn sample_buffer(samples: u32) -> Vec<Sample> {
+        let sample = Sample {
+            ray: Ray::new(Vec3::ZERO, Vec3::ZERO),
+            weight: 0.,
+        };
+
+        vec![sample; samples as usize]
+    }
 
-        (x * self.pixel_delta_u) + (y * self.pixel_delta_v)
+    fn wait_resumed(&self) {
+        if let Some(pause) = &self.pause {
+            pause.wait();
+        }
     }
 
     fn thread_partition(max: u32, curr_thread: u32, tot_threads: u32) -> Range<u32> {
@@ -170,28 +549,88 @@ impl Camera {
         }
     }
 
-    fn progress_logger(max: u32, progress: &AtomicU32) {
-        let progress_len = 30;
+    /// Show the `progress` of the threads until all the work is
+    /// completed, then the rate of the samples of each thread.
+    fn progress_logger(&self, samples: u32, progress: &Progress) {
+        let pixels = self.width as u64 * self.height as u64;
+        let style = ProgressStyle::with_template(
+            "[{bar:30}] {percent:>3}% {human_pos}/{human_len} samples, {msg}, ETA {eta}",
+        )
+        .unwrap()
+        .progress_chars("#> ");
+        let bar = ProgressBar::new(pixels * samples as u64).with_style(style);
+        let start = Instant::now();
 
         loop {
-            let progress = progress.load(atomic::Ordering::Relaxed);
-
-            eprint!(
-                "\r[{}]",
-                (0..progress_len)
-                    .map(|i| if i < progress_len * progress / max {
-                        '#'
-                    } else {
-                        ' '
-                    })
-                    .collect::<String>()
-            );
-
-            if progress == max {
+            let units = progress.units.load(atomic::Ordering::Relaxed);
+            let paused = self.pause.as_ref().is_some_and(|pause| pause.is_paused());
+
+            let done = progress.pixels() * samples as u64;
+            bar.set_position(done);
+            bar.set_message(format!(
+                "{}/s ({}/{} {}{})",
+                HumanCount((done as f64 / start.elapsed().as_secs_f64()) as u64),
+                units,
+                progress.total,
+                progress.unit,
+                if paused { ", paused" } else { "" }
+            ));
+
+            if units == progress.total {
                 break;
             }
+            thread::sleep(Duration::from_millis(100));
+        }
+        bar.finish_and_clear();
+
+        let elapsed = start.elapsed().as_secs_f64();
+        let rates = progress
+            .per_thread
+            .iter()
+            .map(|pixels| {
+                (pixels.load(atomic::Ordering::Relaxed) * samples as u64) as f64 / elapsed
+            })
+            .collect::<Vec<_>>();
+        let min = rates.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = rates.iter().copied().fold(0., f64::max);
+        tracing::info!(
+            "{} threads, from {} to {} samples/s each",
+            rates.len(),
+            HumanCount(min as u64),
+            HumanCount(max as u64)
+        );
+    }
+}
 
-            thread::sleep(std::time::Duration::from_secs(2));
+/// Work completed by the threads mapping the rays, in rows or tiles.
+struct Progress {
+    units: AtomicU32,
+    total: u32,
+    unit: &'static str,
+    /// Pixels completed by each thread.
+    per_thread: Vec<AtomicU64>,
+}
+
+impl Progress {
+    fn new(total: u32, unit: &'static str, threads: u32) -> Self {
+        Self {
+            units: AtomicU32::new(0),
+            total,
+            unit,
+            per_thread: (0..threads).map(|_| AtomicU64::new(0)).collect(),
         }
     }
+
+    /// Record a row or tile of `pixels` completed by the `thread`.
+    fn complete(&self, thread: usize, pixels: u32) {
+        self.per_thread[thread].fetch_add(pixels as u64, atomic::Ordering::Relaxed);
+        self.units.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    fn pixels(&self) -> u64 {
+        self.per_thread
+            .iter()
+            .map(|pixels| pixels.load(atomic::Ordering::Relaxed))
+            .sum()
+    }
 }
@@ -0,0 +1,55 @@
+use crate::vec3::{Color, Float, Vec3};
+
+/// Linear RGB color spaces where the light is computed. The colors of
+/// the scenes are given in Rec. 709, the primaries of sRGB, and the
+/// images are saved in it.
+///
+/// The products of the colors, at each bounce of the light, depend on
+/// the primaries: the wider gamut of ACEScg gives more realistic
+/// results for saturated colors bouncing many times.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorSpace {
+    #[default]
+    Rec709,
+    /// The AP1 primaries of ACES with the D60 white point.
+    AcesCg,
+}
+
+/// From linear Rec. 709 to ACEScg, adapting D65 to D60 with Bradford.
+const REC709_TO_ACESCG: [[Float; 3]; 3] = [
+    [0.6130974, 0.3395231, 0.0473795],
+    [0.07019372, 0.9163539, 0.01345236],
+    [0.02061559, 0.1095698, 0.8698146],
+];
+
+const ACESCG_TO_REC709: [[Float; 3]; 3] = [
+    [1.705051, -0.6217921, -0.08325887],
+    [-0.1302564, 1.140805, -0.01054827],
+    [-0.02400334, -0.128969, 1.152972],
+];
+
+impl ColorSpace {
+    pub const NAMES: [&'static str; 2] = ["rec709", "acescg"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rec709" => Some(Self::Rec709),
+            "acescg" => Some(Self::AcesCg),
+            _ => None,
+        }
+    }
+
+    /// The color `c` of this space in the space `to`.
+    pub fn convert(self, c: Color, to: Self) -> Color {
+        let matrix = match (self, to) {
+            (Self::Rec709, Self::AcesCg) => REC709_TO_ACESCG,
+            (Self::AcesCg, Self::Rec709) => ACESCG_TO_REC709,
+            _ => return c,
+        };
+        let row = |[r, g, b]: [Float; 3]| Vec3::dot(Vec3(r, g, b), c.rgb);
+
+        Color {
+            rgb: Vec3(row(matrix[0]), row(matrix[1]), row(matrix[2])),
+        }
+    }
+}
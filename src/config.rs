@@ -0,0 +1,116 @@
+use std::{fs, path::Path, path::PathBuf};
+
+use serde::Deserialize;
+
+use raycasting_rs::colorspace::ColorSpace;
+use raycasting_rs::film::PixelFilter;
+use raycasting_rs::tonemap::ToneMap;
+use raycasting_rs::vec3::Float;
+
+/// Default configuration file, loaded from the working directory.
+pub const DEFAULT_PATH: &str = "render.toml";
+
+/// Render settings loaded from a TOML file, for example:
+///
+/// ```toml
+/// width = 1920
+/// aspect_ratio = 1.7778
+/// samples = 500
+/// max_depth = 50
+/// threads = 16
+/// pin_threads = true
+/// output = "render.exr"
+/// tone_map = "aces"
+/// lut = "grade.cube"
+/// color_space = "acescg"
+/// exposure = 0.5
+/// filter = "gaussian"
+/// ```
+///
+/// Every setting is optional, the ones given on the command line
+/// take precedence while the config takes precedence over the scene.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderConfig {
+    pub width: Option<u32>,
+    pub aspect_ratio: Option<Float>,
+    pub samples: Option<u32>,
+    pub max_depth: Option<u32>,
+    pub threads: Option<u32>,
+    pub pin_threads: Option<bool>,
+    pub output: Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_tone_map")]
+    pub tone_map: Option<ToneMap>,
+    pub lut: Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_color_space")]
+    pub color_space: Option<ColorSpace>,
+    pub exposure: Option<Float>,
+    #[serde(deserialize_with = "deserialize_filter")]
+    pub filter: Option<PixelFilter>,
+}
+
+fn deserialize_tone_map<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ToneMap>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+
+    ToneMap::from_name(&name).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "tone map must be one of: {}",
+            ToneMap::NAMES.join(", ")
+        ))
+    })
+}
+
+fn deserialize_color_space<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ColorSpace>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+
+    ColorSpace::from_name(&name).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "color space must be one of: {}",
+            ColorSpace::NAMES.join(", ")
+        ))
+    })
+}
+
+fn deserialize_filter<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PixelFilter>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+
+    PixelFilter::from_name(&name).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "filter must be one of: {}",
+            PixelFilter::NAMES.join(", ")
+        ))
+    })
+}
+
+impl RenderConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+        let config: Self = toml::from_str(&text)
+            .map_err(|e| format!("invalid config `{}`: {}", path.display(), e))?;
+
+        let counts = [
+            config.width,
+            config.samples,
+            config.max_depth,
+            config.threads,
+        ];
+        let valid = counts.iter().flatten().all(|n| *n > 0)
+            && config.aspect_ratio.is_none_or(|r| r > 0.)
+            && config.exposure.is_none_or(Float::is_finite);
+
+        match valid {
+            true => Ok(config),
+            false => Err(format!(
+                "invalid config `{}`: the settings must be positive, apart from the exposure",
+                path.display()
+            )),
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Thin curves swept along cubic Bézier splines, for hair, fur or grass,
+//! intersected after Nakamaru and Ohno, "Ray Tracing For Curves
+//! Primitive", like in pbrt.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    onb::Onb,
+    ray::Ray,
+    vec3::{Float, Vec3},
+};
+
+/// Splits of the curves at most before they are taken as straight.
+const MAX_DEPTH: u32 = 10;
+
+/// Shape of the cross-section of the [`Curves`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CurveShape {
+    /// Ribbon facing the ray, shaded flat, which suits the blades of
+    /// grass and the far hair.
+    Flat,
+    /// Ribbon facing the ray with the normals of a tube, which looks
+    /// round like the close hair.
+    #[default]
+    Round,
+}
+
+/// Cubic Bézier spline from the first control point to the last, as
+/// wide as `widths` at its ends and in between along it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Curve {
+    pub points: [Vec3; 4],
+    /// Width at the root and at the tip.
+    pub widths: (Float, Float),
+}
+
+impl Curve {
+    fn max_width(&self) -> Float {
+        self.widths.0.max(self.widths.1)
+    }
+
+    fn bounds(&self) -> Aabb {
+        // The spline is within the hull of its control points.
+        let half = 0.5 * self.max_width();
+        let extent = Vec3(half, half, half);
+
+        Aabb::from_points(self.points.iter().flat_map(|p| [*p - extent, *p + extent]))
+    }
+}
+
+/// Curves of the same `shape` and `material`. Each object tests all its
+/// curves, so the large grooms are [`split`](Self::split) for the BVH.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Curves {
+    curves: Vec<Curve>,
+    pub shape: CurveShape,
+    pub material: Material,
+    bounds: Aabb,
+}
+
+impl Curves {
+    /// Curves of the `shape` made of the `material`, which must not be
+    /// empty.
+    pub fn new(curves: Vec<Curve>, shape: CurveShape, material: Material) -> Self {
+        let bounds = Aabb::from_points(curves.iter().flat_map(|curve| {
+            let bounds = curve.bounds();
+            [bounds.min, bounds.max]
+        }));
+
+        Self {
+            curves,
+            shape,
+            material,
+            bounds,
+        }
+    }
+
+    pub fn curves(&self) -> &[Curve] {
+        &self.curves
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, curve) in self.curves.iter().enumerate() {
+            if !curve.points.iter().all(|p| p.is_finite()) {
+                return Err(format!("curve {}: the points must be finite", i));
+            }
+            let (root, tip) = curve.widths;
+            let valid = |width: Float| width >= 0. && width.is_finite();
+            if !(valid(root) && valid(tip) && curve.max_width() > 0.) {
+                return Err(format!(
+                    "curve {}: the widths must be positive, found ({}, {})",
+                    i, root, tip
+                ));
+            }
+        }
+
+        self.material.validate()
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        for curve in &mut self.curves {
+            for point in &mut curve.points {
+                *point += offset;
+            }
+        }
+        self.bounds.translate(offset);
+    }
+
+    /// Split the curves into groups of `size` curves at most, halving
+    /// them along the widest axis of their roots so that each group is
+    /// compact.
+    pub fn split(self, size: usize) -> Vec<Curves> {
+        let mut groups = Vec::new();
+        let mut parts = vec![self.curves];
+
+        while let Some(mut curves) = parts.pop() {
+            if curves.len() <= size.max(1) {
+                groups.push(Self::new(curves, self.shape, self.material));
+                continue;
+            }
+
+            let bounds = Aabb::from_points(curves.iter().map(|c| c.points[0]));
+            let Vec3(x, y, z) = bounds.max - bounds.min;
+            let key = |c: &Curve| match (x >= y && x >= z, y >= z) {
+                (true, _) => c.points[0].0,
+                (false, true) => c.points[0].1,
+                (false, false) => c.points[0].2,
+            };
+            let half = curves.len() / 2;
+            curves.select_nth_unstable_by(half, |a, b| key(a).total_cmp(&key(b)));
+
+            let right = curves.split_off(half);
+            parts.push(curves);
+            parts.push(right);
+        }
+
+        groups
+    }
+
+    /// Closest hit of the ray `r` with the `curve` within `t_range`, as
+    /// the distance and the normal there.
+    fn intersect(&self, curve: &Curve, r: &Ray, t_range: Interval) -> Option<(Float, Vec3)> {
+        let length = Vec3::norm(r.dir);
+        if length.is_nan() || length == 0. {
+            return None;
+        }
+
+        // The control points seen from the ray, which runs along z from
+        // the origin, so that the curve is hit where it covers (0, 0).
+        let frame = Onb::new(r.dir);
+        let points = curve.points.map(|p| {
+            let d = p - r.start;
+            Vec3(
+                Vec3::dot(d, frame.u),
+                Vec3::dot(d, frame.v),
+                Vec3::dot(d, frame.w),
+            )
+        });
+
+        // Splits after which the pieces of the spline are straight within
+        // a twentieth of its width, from the bound of its second
+        // derivative.
+        let bend = (0..2)
+            .map(|i| {
+                let d = points[i] - 2. * points[i + 1] + points[i + 2];
+                d.0.abs().max(d.1.abs()).max(d.2.abs())
+            })
+            .fold(0., Float::max);
+        let epsilon = 0.05 * curve.max_width();
+        let depth = match bend > 0. {
+            true => {
+                let depth = (Float::sqrt(2.) * 6. * bend / (8. * epsilon)).log2() / 2.;
+                depth.clamp(0., MAX_DEPTH as Float) as u32
+            }
+            false => 0,
+        };
+
+        let z_range = Interval::new(t_range.min * length, t_range.max * length);
+        let hit = find(curve, points, (0., 1.), depth, z_range)?;
+
+        let toward = (-1. / length) * r.dir;
+        let normal = match self.shape {
+            CurveShape::Flat => toward,
+            CurveShape::Round => {
+                // Across the ribbon, the normal turns around the tangent
+                // from one side of a tube to the other.
+                let tangent = Vec3::unit(frame.local(hit.tangent));
+                let across = |v: Vec3| v - Vec3::dot(v, tangent) * tangent;
+                let side = across(frame.local(Vec3(-hit.point.0, -hit.point.1, 0.)));
+                let s = (Vec3::norm(side) / (0.5 * hit.width)).min(1.);
+
+                match s > 0. && tangent.is_finite() {
+                    true => (1. - s * s).sqrt() * Vec3::unit(across(toward)) + s * Vec3::unit(side),
+                    false => toward,
+                }
+            }
+        };
+
+        Some((hit.point.2 / length, normal))
+    }
+}
+
+/// Hit of a ray with a curve, seen from the ray.
+struct CurveHit {
+    /// Point of the spline nearest to the ray, whose z is the distance
+    /// of the hit along it.
+    point: Vec3,
+    /// Derivative of the spline there.
+    tangent: Vec3,
+    width: Float,
+}
+
+/// Closest hit of the ray along the z axis with the piece of the `curve`
+/// between the parameters `u`, whose control points seen from the ray
+/// are `points`, within the distances `z_range`. The piece is split in
+/// halves `depth` times, skipping the halves whose bounds miss the ray,
+/// before being taken as a straight segment.
+fn find(
+    curve: &Curve,
+    points: [Vec3; 4],
+    u: (Float, Float),
+    depth: u32,
+    z_range: Interval,
+) -> Option<CurveHit> {
+    if depth == 0 {
+        return segment(curve, points, u, z_range);
+    }
+
+    let [p0, p1, p2, p3] = points;
+    let p01 = 0.5 * (p0 + p1);
+    let p12 = 0.5 * (p1 + p2);
+    let p23 = 0.5 * (p2 + p3);
+    let p012 = 0.5 * (p01 + p12);
+    let p123 = 0.5 * (p12 + p23);
+    let mid = 0.5 * (p012 + p123);
+    let u_mid = 0.5 * (u.0 + u.1);
+
+    let half = 0.5 * curve.max_width();
+    let mut closest: Option<CurveHit> = None;
+    for (points, u) in [
+        ([p0, p01, p012, mid], (u.0, u_mid)),
+        ([mid, p123, p23, p3], (u_mid, u.1)),
+    ] {
+        let z_range = z_range.until(closest.as_ref().map_or(z_range.max, |hit| hit.point.2));
+        let bounds = Aabb::from_points(points);
+        if bounds.min.0 - half > 0.
+            || bounds.max.0 + half < 0.
+            || bounds.min.1 - half > 0.
+            || bounds.max.1 + half < 0.
+            || bounds.min.2 - half > z_range.max
+            || bounds.max.2 + half < z_range.min
+        {
+            continue;
+        }
+
+        if let Some(hit) = find(curve, points, u, depth - 1, z_range) {
+            closest = Some(hit);
+        }
+    }
+
+    closest
+}
+
+/// Hit of the ray along the z axis with the piece of the `curve` like
+/// in [`find`], taken as the segment between its ends.
+fn segment(
+    curve: &Curve,
+    points: [Vec3; 4],
+    u: (Float, Float),
+    z_range: Interval,
+) -> Option<CurveHit> {
+    let [p0, p1, p2, p3] = points;
+
+    // The ray must pass between the lines through the ends of the piece,
+    // perpendicular to it, so that the pieces don't overlap.
+    if (p1.1 - p0.1) * -p0.1 + p0.0 * (p0.0 - p1.0) < 0.
+        || (p2.1 - p3.1) * -p3.1 + p3.0 * (p3.0 - p2.0) < 0.
+    {
+        return None;
+    }
+
+    // Parameter of the point of the segment nearest to the ray.
+    let (dx, dy) = (p3.0 - p0.0, p3.1 - p0.1);
+    let denominator = dx * dx + dy * dy;
+    if denominator == 0. {
+        return None;
+    }
+    let w = ((-p0.0 * dx - p0.1 * dy) / denominator).clamp(0., 1.);
+
+    let t = u.0 + w * (u.1 - u.0);
+    let width = curve.widths.0 + t * (curve.widths.1 - curve.widths.0);
+    let (point, tangent) = bezier(points, w);
+
+    let distance2 = point.0 * point.0 + point.1 * point.1;
+    let half2 = 0.25 * width * width;
+    // The rays leaving the curve start on it, where they would hit it
+    // again as it turns to face them.
+    if distance2 > half2 || !z_range.surrounds(point.2) || distance2 + point.2 * point.2 <= half2 {
+        return None;
+    }
+
+    Some(CurveHit {
+        point,
+        tangent,
+        width,
+    })
+}
+
+/// Point of the spline of the control `points` at the parameter `t`,
+/// with the derivative there.
+fn bezier(points: [Vec3; 4], t: Float) -> (Vec3, Vec3) {
+    let [p0, p1, p2, p3] = points;
+    let lerp = |a: Vec3, b: Vec3| a + t * (b - a);
+    let (a, b, c) = (lerp(p0, p1), lerp(p1, p2), lerp(p2, p3));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+
+    // The derivative vanishes at the ends folded on their neighbors.
+    let tangent = match e == d {
+        true => p3 - p0,
+        false => 3. * (e - d),
+    };
+
+    (lerp(d, e), tangent)
+}
+
+impl Hit<Ray> for Curves {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        if !self.bounds.hit(r, t_range) {
+            return None;
+        }
+
+        let mut closest = None;
+        let mut max_t = t_range.max;
+
+        for curve in &self.curves {
+            if let Some((t, normal)) = self.intersect(curve, r, t_range.until(max_t)) {
+                max_t = t;
+                closest = Some((t, normal));
+            }
+        }
+
+        closest.map(|(t, normal)| HitRecord::new(r.at(t), normal, t, &self.material, r))
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.bounds.hit(r, t_range)
+            && self
+                .curves
+                .iter()
+                .any(|curve| self.intersect(curve, r, t_range).is_some())
+    }
+}
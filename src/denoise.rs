@@ -0,0 +1,161 @@
+use crate::{
+    aov::Aov,
+    vec3::{Color, Float, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Denoiser {
+    /// Edge-avoiding à-trous wavelet filter, guided by the
+    /// normals and albedo of the first hits.
+    Atrous,
+    /// Intel Open Image Denoise, available with the `oidn` feature.
+    Oidn,
+}
+
+impl Denoiser {
+    pub const NAMES: [&'static str; 2] = ["atrous", "oidn"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "atrous" => Some(Self::Atrous),
+            "oidn" => Some(Self::Oidn),
+            _ => None,
+        }
+    }
+
+    /// Whether the denoiser was compiled in.
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Atrous => true,
+            Self::Oidn => cfg!(feature = "oidn"),
+        }
+    }
+
+    /// Denoise the linear `colors`, in row-major order, guided by
+    /// the `aovs` of the same pixels.
+    pub fn denoise(
+        self,
+        width: u32,
+        height: u32,
+        colors: &mut [Color],
+        aovs: &[Aov],
+    ) -> Result<(), String> {
+        match self {
+            Self::Atrous => {
+                atrous(width, height, colors, aovs);
+                Ok(())
+            }
+            Self::Oidn => oidn(width, height, colors, aovs),
+        }
+    }
+}
+
+/// Edge-avoiding à-trous wavelet transform, from "Edge-Avoiding À-Trous
+/// Wavelet Transform for fast Global Illumination Filtering" (Dammertz et al.).
+fn atrous(width: u32, height: u32, colors: &mut [Color], aovs: &[Aov]) {
+    const KERNEL: [Float; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+    const ITERATIONS: i32 = 5;
+    const SIGMA_COLOR: Float = 0.6;
+    const SIGMA_NORMAL: Float = 0.3;
+    const SIGMA_ALBEDO: Float = 0.1;
+    const MIN_ALBEDO: Float = 1e-3;
+
+    let (w, h) = (width as i32, height as i32);
+    let weight = |d: Vec3, sigma: Float| Float::exp(-Vec3::dot(d, d) / (sigma * sigma));
+
+    // Filter the irradiance instead of the radiance, so that the
+    // details of the surfaces are not blurred away.
+    let albedo = aovs
+        .iter()
+        .map(|a| a.albedo.rgb)
+        .map(|a| {
+            Vec3(
+                a.0.max(MIN_ALBEDO),
+                a.1.max(MIN_ALBEDO),
+                a.2.max(MIN_ALBEDO),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut irradiance = colors
+        .iter()
+        .zip(&albedo)
+        .map(|(c, a)| c.rgb / *a)
+        .collect::<Vec<_>>();
+
+    for i in 0..ITERATIONS {
+        let step = 1 << i;
+        let sigma_color = SIGMA_COLOR / (1 << i) as Float;
+
+        irradiance = (0..w * h)
+            .map(|p| {
+                let (x, y) = (p % w, p / w);
+                let p = p as usize;
+
+                let mut sum = Vec3::ZERO;
+                let mut weight_sum = 0.;
+
+                for (ky, hy) in KERNEL.iter().enumerate() {
+                    for (kx, hx) in KERNEL.iter().enumerate() {
+                        let qx = x + (kx as i32 - 2) * step;
+                        let qy = y + (ky as i32 - 2) * step;
+                        if !(0..w).contains(&qx) || !(0..h).contains(&qy) {
+                            continue;
+                        }
+
+                        let q = (qy * w + qx) as usize;
+                        let wq = hx
+                            * hy
+                            * weight(irradiance[p] - irradiance[q], sigma_color)
+                            * weight(aovs[p].normal - aovs[q].normal, SIGMA_NORMAL)
+                            * weight(albedo[p] - albedo[q], SIGMA_ALBEDO);
+
+                        sum += wq * irradiance[q];
+                        weight_sum += wq;
+                    }
+                }
+
+                sum / weight_sum
+            })
+            .collect();
+    }
+
+    for ((c, e), a) in colors.iter_mut().zip(irradiance).zip(albedo) {
+        c.rgb = e * a;
+    }
+}
+
+#[cfg(feature = "oidn")]
+fn oidn(width: u32, height: u32, colors: &mut [Color], aovs: &[Aov]) -> Result<(), String> {
+    let mut color = colors.iter().flat_map(|c| c.to_f32()).collect::<Vec<_>>();
+    let albedo = aovs
+        .iter()
+        .flat_map(|a| a.albedo.to_f32())
+        .collect::<Vec<_>>();
+    let normal = aovs
+        .iter()
+        .flat_map(|a| a.normal.to_f32())
+        .collect::<Vec<_>>();
+
+    let device = oidn::Device::new();
+    oidn::RayTracing::new(&device)
+        .hdr(true)
+        .image_dimensions(width as usize, height as usize)
+        .albedo_normal(&albedo, &normal)
+        .filter_in_place(&mut color)
+        .map_err(|e| format!("invalid denoiser configuration: {:?}", e))?;
+
+    device
+        .get_error()
+        .map_err(|(_, msg)| format!("denoising failed: {}", msg))?;
+
+    for (c, rgb) in colors.iter_mut().zip(color.chunks_exact(3)) {
+        *c = Color::from_f32([rgb[0], rgb[1], rgb[2]]);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "oidn"))]
+fn oidn(_: u32, _: u32, _: &mut [Color], _: &[Aov]) -> Result<(), String> {
+    Err("denoising with OIDN requires building with `--features oidn`".into())
+}
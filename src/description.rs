@@ -0,0 +1,810 @@
+use std::{collections::BTreeMap, ops::Range, path::PathBuf};
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "serde")]
+use crate::{metaballs, object, script};
+#[cfg(feature = "serde")]
+use ron::extensions::Extensions;
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    animation::{Animation, CameraPath, Keyframes},
+    camera::CameraSettings,
+    curve::{Curve, CurveShape, Curves},
+    fractal::{Fractal, FractalKind},
+    import::{obj, ply::PlyMesh},
+    material::{Material, MaterialType},
+    mesh::Mesh,
+    metaballs::{Ball, Metaballs},
+    object::Object,
+    plugin,
+    pointcloud::{PointCloud, Splat},
+    post::PostEffect,
+    sphere::Sphere,
+    subdivision::{self, Cage},
+    text,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Scene described in a RON file, for example:
+///
+/// ```ron
+/// (
+///     camera: (look_from: (13, 2, 3), look_at: (0, 0, 0), fov: 20),
+///     materials: {
+///         "ground": Lambertian(color: (0.5, 0.5, 0.5)),
+///         "brick": Lambertian(color: "#b5543c"),
+///         "glass": Dielectric(refraction_index: 1.5),
+///     },
+///     objects: [
+///         Sphere(center: (0, -1000, 0), radius: 1000, material: "ground"),
+///         Sphere(center: (0, 1, 0), radius: 1, material: "glass"),
+///     ],
+///     render: (width: 400, samples: 100),
+/// )
+/// ```
+///
+/// Every setting of the camera and of the render is optional, and can
+/// be overridden from the command line.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SceneDescription {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub camera: CameraDescription,
+    /// Materials referenced by name from the objects.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    pub objects: Vec<ObjectDescription>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub render: RenderSettings,
+    /// Rhai script adding objects to the scene once loaded, see
+    /// [`script`](crate::script).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub script: Option<String>,
+    /// Seed of the random numbers of the script.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub seed: Option<u64>,
+    /// Motion of the camera and of the named objects when rendering
+    /// several frames, see [`AnimationDescription`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub animation: Option<AnimationDescription>,
+}
+
+/// Position of the camera and of the objects during an animation,
+/// for example:
+///
+/// ```ron
+/// animation: (
+///     camera: Turntable(period: 4),
+///     objects: {
+///         "ball": [(0, (0, 0, 0)), (1, (0, 1, 0)), (2, (0, 0, 0))],
+///     },
+/// ),
+/// ```
+///
+/// The keyframes are `(time, position)` pairs with the time in seconds,
+/// the positions in between are interpolated linearly.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct AnimationDescription {
+    /// Path of the camera, it stays in place when missing.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub camera: Option<CameraMotion>,
+    /// Translations of the [`Named`](ObjectDescription::Named) objects,
+    /// from their position in the scene.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub objects: BTreeMap<String, Positions>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub enum CameraMotion {
+    /// Orbit around the vertical axis through the point looked at, a
+    /// full turn every `period` seconds.
+    Turntable { period: Float },
+    /// Positions of the camera, which keeps looking at the same point.
+    Keyframes(Positions),
+}
+
+/// Keyframes of a position, as `(time, position)` pairs.
+pub type Positions = Vec<(Float, (Float, Float, Float))>;
+
+impl AnimationDescription {
+    /// Animate the objects built from the scene, `named` gives the
+    /// range of the objects built from each named one.
+    fn animation(&self, named: &BTreeMap<&str, Range<usize>>) -> Result<Animation, String> {
+        let keyframes = |keys: &Positions| match keys.is_empty() {
+            true => Err("there are no keyframes".to_string()),
+            false => Ok(Keyframes::new(
+                keys.iter().map(|(t, v)| (*t, Vec3::new(*v))).collect(),
+            )),
+        };
+
+        let camera = match &self.camera {
+            Some(CameraMotion::Turntable { period }) if *period <= 0. || period.is_nan() => {
+                return Err(format!("the camera: the period {} isn't positive", period));
+            }
+            Some(CameraMotion::Turntable { period }) => {
+                Some(CameraPath::Turntable { period: *period })
+            }
+            Some(CameraMotion::Keyframes(keys)) => Some(CameraPath::Keyframes(
+                keyframes(keys).map_err(|e| format!("the camera: {}", e))?,
+            )),
+            None => None,
+        };
+
+        let objects = self
+            .objects
+            .iter()
+            .map(|(name, keys)| {
+                let range = named
+                    .get(name.as_str())
+                    .ok_or_else(|| format!("the object `{}` is not defined", name))?;
+                let keyframes = keyframes(keys).map_err(|e| format!("`{}`: {}", name, e))?;
+
+                Ok((range.clone(), keyframes))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Animation { camera, objects })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct CameraDescription {
+    pub look_from: Option<(Float, Float, Float)>,
+    pub look_at: Option<(Float, Float, Float)>,
+    /// Direction pointing up in the image.
+    pub up: Option<(Float, Float, Float)>,
+    /// Vertical field of view in degrees.
+    pub fov: Option<Float>,
+    /// Aperture of the defocus blur cone in degrees.
+    pub defocus_angle: Option<Float>,
+    pub focus_dist: Option<Float>,
+}
+
+impl CameraDescription {
+    /// Settings of the camera rendering `width` pixels wide images, the
+    /// missing ones default to looking at the origin from (13, 2, 3).
+    pub fn settings(&self, width: u32, aspect_ratio: Float) -> CameraSettings {
+        CameraSettings {
+            aspect_ratio,
+            width,
+            v_fov: self.fov.unwrap_or(20.),
+            look_from: self.look_from.map_or(Vec3(13., 2., 3.), Vec3::new),
+            look_at: self.look_at.map_or(Vec3::ZERO, Vec3::new),
+            defocus_angle: self.defocus_angle.unwrap_or(0.6),
+            focus_dist: self.focus_dist.unwrap_or(10.),
+            vup: self.up.map_or(Vec3(0., 1., 0.), Vec3::new),
+        }
+    }
+}
+
+/// Ratio between the width and the height of the images when missing.
+pub const DEFAULT_ASPECT_RATIO: Float = 19. / 9.;
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct RenderSettings {
+    pub width: Option<u32>,
+    pub aspect_ratio: Option<Float>,
+    pub samples: Option<u32>,
+    pub max_depth: Option<u32>,
+    /// Effects applied to the image once rendered, see [`PostEffect`].
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub post: Vec<PostEffect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub enum MaterialDescription {
+    Lambertian {
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "color"))]
+        color: (Float, Float, Float),
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        fuzz: Option<Float>,
+    },
+    Metal {
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "color"))]
+        color: (Float, Float, Float),
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        fuzz: Option<Float>,
+    },
+    Dielectric {
+        refraction_index: Float,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        fuzz: Option<Float>,
+    },
+    /// Light source, its color can be brighter than white.
+    Emissive {
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "color"))]
+        color: (Float, Float, Float),
+    },
+}
+
+/// Read a color given either by its linear components or by a hex
+/// string like `"#aabbcc"`, see [`Color::from_hex`].
+#[cfg(feature = "serde")]
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(Float, Float, Float), D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Rgb(Float, Float, Float),
+        Hex(String),
+    }
+
+    match Repr::deserialize(deserializer)? {
+        Repr::Rgb(r, g, b) => Ok((r, g, b)),
+        Repr::Hex(hex) => Color::from_hex(&hex)
+            .map(|c| c.rgb.into())
+            .map_err(D::Error::custom),
+    }
+}
+
+impl From<&MaterialDescription> for Material {
+    fn from(material: &MaterialDescription) -> Self {
+        match *material {
+            MaterialDescription::Lambertian { color, fuzz } => {
+                Material::lambertian(Color::new(color), fuzz)
+            }
+            MaterialDescription::Metal { color, fuzz } => Material::metal(Color::new(color), fuzz),
+            MaterialDescription::Dielectric {
+                refraction_index,
+                fuzz,
+            } => Material::dielectric(refraction_index, fuzz),
+            MaterialDescription::Emissive { color } => Material::emissive(Color::new(color)),
+        }
+    }
+}
+
+impl From<&Material> for MaterialDescription {
+    fn from(material: &Material) -> Self {
+        let color = material.solid_color.rgb.into();
+        let fuzz = material.fuzz;
+
+        match material.material {
+            MaterialType::Lambertian => Self::Lambertian { color, fuzz },
+            MaterialType::Metal => Self::Metal { color, fuzz },
+            MaterialType::Dielectric => Self::Dielectric {
+                refraction_index: material.refraction_index,
+                fuzz,
+            },
+            MaterialType::Emissive => Self::Emissive { color },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub enum ObjectDescription {
+    Sphere {
+        center: (Float, Float, Float),
+        radius: Float,
+        /// Name of the material.
+        material: String,
+        /// Whether the rays hitting the inside of the sphere pass
+        /// through, so that a camera inside it sees out.
+        #[cfg_attr(feature = "serde", serde(default))]
+        cull_backfaces: bool,
+    },
+    /// Balls merging smoothly, see [`Metaballs`]:
+    ///
+    /// ```ron
+    /// Metaballs(
+    ///     balls: [(center: (0, 1, 0), radius: 1), (center: (1, 1, 0), radius: 0.8)],
+    ///     material: "blue",
+    /// )
+    /// ```
+    Metaballs {
+        balls: Vec<Ball>,
+        #[cfg_attr(feature = "serde", serde(default = "default_threshold"))]
+        threshold: Float,
+        material: String,
+    },
+    /// Fractal of the `kind`, see [`Fractal`]:
+    ///
+    /// ```ron
+    /// Fractal(kind: Mandelbulb(power: 8), center: (0, 1, 0), scale: 1, material: "gold")
+    /// ```
+    Fractal {
+        kind: FractalKind,
+        center: (Float, Float, Float),
+        scale: Float,
+        material: String,
+    },
+    /// Surface of revolution around the vertical axis through the
+    /// `center`, see [`Mesh::lathe`]:
+    ///
+    /// ```ron
+    /// Lathe(profile: [(0, 0), (0.5, 0), (0.3, 1), (0.4, 1.5)], center: (0, 0, 0), material: "glass")
+    /// ```
+    Lathe {
+        profile: Vec<(Float, Float)>,
+        center: (Float, Float, Float),
+        #[cfg_attr(feature = "serde", serde(default = "default_segments"))]
+        segments: u32,
+        material: String,
+        /// See [`Mesh::with_backface_culling`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        cull_backfaces: bool,
+    },
+    /// Polygon extruded upwards from the `center`, see [`Mesh::extrude`]:
+    ///
+    /// ```ron
+    /// Extrusion(polygon: [(0, 0), (4, 0), (4, 0.2), (0, 0.2)], height: 2.5, center: (0, 0, 0), material: "wall")
+    /// ```
+    Extrusion {
+        polygon: Vec<(Float, Float)>,
+        height: Float,
+        center: (Float, Float, Float),
+        material: String,
+        /// See [`Mesh::with_backface_culling`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        cull_backfaces: bool,
+    },
+    /// Solid letters written with the TrueType or OpenType `font`, one
+    /// mesh for each, standing on the `center` and facing the z axis,
+    /// see [`text::text`]:
+    ///
+    /// ```ron
+    /// Text(text: "Hello", font: "fonts/DejaVuSans.ttf", size: 1, depth: 0.2, center: (0, 0, 0), material: "gold")
+    /// ```
+    Text {
+        text: String,
+        /// Path of the font, relative to the working directory.
+        font: PathBuf,
+        size: Float,
+        depth: Float,
+        center: (Float, Float, Float),
+        material: String,
+        /// See [`Mesh::with_backface_culling`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        cull_backfaces: bool,
+    },
+    /// Points of the PLY file at `path` drawn as splats of the `radius`,
+    /// with the colors of the file when it has some, see [`PointCloud`]:
+    ///
+    /// ```ron
+    /// PointCloud(path: "scans/statue.ply", radius: 0.01, splat: Disc, center: (0, 0, 0), material: "plaster")
+    /// ```
+    PointCloud {
+        /// Path of the file, relative to the working directory.
+        path: PathBuf,
+        radius: Float,
+        #[cfg_attr(feature = "serde", serde(default))]
+        splat: Splat,
+        center: (Float, Float, Float),
+        material: String,
+    },
+    /// Polygons of the Wavefront OBJ file at `path` smoothed into a
+    /// surface by subdividing them `levels` times, see [`Cage`]:
+    ///
+    /// ```ron
+    /// Subdivision(path: "models/cage.obj", levels: 3, center: (0, 1, 0), material: "clay")
+    /// ```
+    Subdivision {
+        /// Path of the file, relative to the working directory.
+        path: PathBuf,
+        #[cfg_attr(feature = "serde", serde(default = "default_levels"))]
+        levels: u32,
+        center: (Float, Float, Float),
+        material: String,
+        /// See [`Mesh::with_backface_culling`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        cull_backfaces: bool,
+    },
+    /// Bézier splines swept with their widths, for hair or grass, see
+    /// [`Curves`]:
+    ///
+    /// ```ron
+    /// Curves(
+    ///     curves: [(points: ((0, 0, 0), (0, 0.5, 0), (0.1, 1, 0), (0.3, 1.3, 0)), widths: (0.02, 0.005))],
+    ///     shape: Flat,
+    ///     material: "grass",
+    /// )
+    /// ```
+    Curves {
+        curves: Vec<Curve>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        shape: CurveShape,
+        material: String,
+    },
+    /// Shape registered with the [`plugin`](crate::plugin) API.
+    Custom {
+        shape: String,
+        #[cfg_attr(feature = "serde", serde(default))]
+        params: BTreeMap<String, Float>,
+        material: String,
+    },
+    /// Object given a name, so that the animation can move it:
+    ///
+    /// ```ron
+    /// Named(name: "ball", object: Sphere(center: (0, 2.3, 0), radius: 0.3, material: "blue"))
+    /// ```
+    Named {
+        name: String,
+        object: Box<ObjectDescription>,
+    },
+}
+
+/// Triangles of the meshes, points of the clouds or curves built from a
+/// description at most, so that the BVH skips the parts of the large
+/// ones far from a ray.
+pub(crate) const CHUNK: usize = 32;
+
+#[cfg(feature = "serde")]
+fn default_threshold() -> Float {
+    metaballs::DEFAULT_THRESHOLD
+}
+
+#[cfg(feature = "serde")]
+fn default_segments() -> u32 {
+    64
+}
+
+#[cfg(feature = "serde")]
+fn default_levels() -> u32 {
+    2
+}
+
+impl SceneDescription {
+    /// Load the scene from the RON file at `path`, checking that
+    /// every material used by the objects is defined.
+    #[cfg(feature = "serde")]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+
+        Self::parse(&text).map_err(|e| format!("invalid scene `{}`: {}", path.display(), e))
+    }
+
+    /// Parse the scene from the RON `text`, checking it like [`Self::load`].
+    /// The objects of the script are added to the ones of the scene.
+    #[cfg(feature = "serde")]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // Optional values don't need to be wrapped in `Some(..)`.
+        let mut scene: Self = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(text)
+            .map_err(|e| e.to_string())?;
+
+        if let Some(script) = scene.script.take() {
+            let seed = scene.seed.take().unwrap_or_default();
+            let objects = script::run(&script, seed).map_err(|e| format!("the script: {}", e))?;
+
+            scene.objects.extend(objects);
+        }
+
+        for (name, material) in &scene.materials {
+            Material::from(material)
+                .validate()
+                .map_err(|e| format!("the material `{}`: {}", name, e))?;
+        }
+
+        let (objects, _) = scene.try_build()?;
+        object::validate(&objects)?;
+
+        Ok(scene)
+    }
+
+    /// Build the `object`, failing when its material or its shape
+    /// is missing. The large meshes are split into several objects.
+    fn objects(&self, object: &ObjectDescription) -> Result<Vec<Object>, String> {
+        let material = |name: &String| match self.materials.get(name) {
+            Some(material) => Ok(Material::from(material)),
+            None => Err(format!("the material `{}` is not defined", name)),
+        };
+
+        let object = match object {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material: name,
+                cull_backfaces,
+            } => Ok(Object::Sphere(
+                Sphere::new(Vec3::new(*center), *radius, material(name)?)
+                    .with_backface_culling(*cull_backfaces),
+            )),
+            ObjectDescription::Metaballs {
+                balls,
+                threshold,
+                material: name,
+            } => Ok(Object::Metaballs(Metaballs::new(
+                balls.clone(),
+                *threshold,
+                material(name)?,
+            ))),
+            ObjectDescription::Fractal {
+                kind,
+                center,
+                scale,
+                material: name,
+            } => Ok(Object::Fractal(Fractal::new(
+                *kind,
+                Vec3::new(*center),
+                *scale,
+                material(name)?,
+            ))),
+            ObjectDescription::Lathe {
+                profile,
+                center,
+                segments,
+                material: name,
+                cull_backfaces,
+            } => {
+                let mut mesh = Mesh::lathe(profile, *segments, material(name)?)
+                    .map_err(|e| format!("the lathe: {}", e))?
+                    .with_backface_culling(*cull_backfaces);
+                mesh.translate(Vec3::new(*center));
+
+                return Ok(mesh.split(CHUNK).into_iter().map(Object::Mesh).collect());
+            }
+            ObjectDescription::Extrusion {
+                polygon,
+                height,
+                center,
+                material: name,
+                cull_backfaces,
+            } => {
+                let mut mesh = Mesh::extrude(polygon, *height, material(name)?)
+                    .map_err(|e| format!("the extrusion: {}", e))?
+                    .with_backface_culling(*cull_backfaces);
+                mesh.translate(Vec3::new(*center));
+
+                return Ok(mesh.split(CHUNK).into_iter().map(Object::Mesh).collect());
+            }
+            ObjectDescription::Text {
+                text,
+                font,
+                size,
+                depth,
+                center,
+                material: name,
+                cull_backfaces,
+            } => {
+                let meshes = text::text(font, text, *size, *depth, material(name)?)
+                    .map_err(|e| format!("the text `{}`: {}", text, e))?;
+
+                return Ok(meshes
+                    .into_iter()
+                    .flat_map(|mesh| {
+                        let mut mesh = mesh.with_backface_culling(*cull_backfaces);
+                        mesh.translate(Vec3::new(*center));
+                        mesh.split(CHUNK)
+                    })
+                    .map(Object::Mesh)
+                    .collect());
+            }
+            ObjectDescription::PointCloud {
+                path,
+                radius,
+                splat,
+                center,
+                material: name,
+            } => {
+                let points = PlyMesh::read_points(path)?.points();
+                let mut cloud = PointCloud::new(points, *radius, *splat, material(name)?);
+                cloud.translate(Vec3::new(*center));
+
+                return Ok(cloud
+                    .split(CHUNK)
+                    .into_iter()
+                    .map(Object::PointCloud)
+                    .collect());
+            }
+            ObjectDescription::Subdivision {
+                path,
+                levels,
+                center,
+                material: name,
+                cull_backfaces,
+            } => {
+                let (vertices, faces) = obj::load_polygons(path)?;
+                let mut cage = Cage::new(vertices, faces);
+                cage.validate()
+                    .map_err(|e| format!("the cage `{}`: {}", path.display(), e))?;
+                if *levels > subdivision::MAX_LEVELS {
+                    return Err(format!(
+                        "the levels of subdivision must be at most {}, found {}",
+                        subdivision::MAX_LEVELS,
+                        levels
+                    ));
+                }
+
+                for _ in 0..*levels {
+                    cage = cage.subdivide();
+                }
+                let mut mesh = cage
+                    .to_mesh(material(name)?)
+                    .with_backface_culling(*cull_backfaces);
+                mesh.translate(Vec3::new(*center));
+
+                return Ok(mesh.split(CHUNK).into_iter().map(Object::Mesh).collect());
+            }
+            ObjectDescription::Curves {
+                curves,
+                shape,
+                material: name,
+            } => {
+                if curves.is_empty() {
+                    return Err("the curves: there are none".into());
+                }
+                let curves = Curves::new(curves.clone(), *shape, material(name)?);
+
+                return Ok(curves
+                    .split(CHUNK)
+                    .into_iter()
+                    .map(Object::Curves)
+                    .collect());
+            }
+            ObjectDescription::Custom {
+                shape,
+                params,
+                material: name,
+            } => plugin::custom(shape, params.clone(), material(name)?).map(Object::Custom),
+            ObjectDescription::Named { name, object } => {
+                if let ObjectDescription::Named { .. } = **object {
+                    return Err(format!("`{}`: the object is already named", name));
+                }
+
+                return self.objects(object);
+            }
+        }?;
+
+        Ok(vec![object])
+    }
+
+    /// Describe the `objects`, for example the generated ones, so that
+    /// they can be saved and rendered again. The materials are named
+    /// in the order they are first used, and meshes and point clouds
+    /// can't be described.
+    pub fn from_objects(
+        camera: CameraDescription,
+        objects: &[Object],
+        render: RenderSettings,
+    ) -> Result<Self, String> {
+        let mut materials: Vec<MaterialDescription> = vec![];
+        let mut name = |material: &Material| {
+            let material = MaterialDescription::from(material);
+            let index = match materials.iter().position(|m| *m == material) {
+                Some(index) => index,
+                None => {
+                    materials.push(material);
+                    materials.len() - 1
+                }
+            };
+
+            format!("material{}", index)
+        };
+
+        let objects = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| match object {
+                Object::Sphere(sphere) => Ok(ObjectDescription::Sphere {
+                    center: sphere.center.into(),
+                    radius: sphere.radius,
+                    material: name(&sphere.material),
+                    cull_backfaces: sphere.cull_backfaces,
+                }),
+                Object::Metaballs(metaballs) => Ok(ObjectDescription::Metaballs {
+                    balls: metaballs.balls.clone(),
+                    threshold: metaballs.threshold,
+                    material: name(&metaballs.material),
+                }),
+                Object::Fractal(fractal) => Ok(ObjectDescription::Fractal {
+                    kind: fractal.kind,
+                    center: fractal.center.into(),
+                    scale: fractal.scale,
+                    material: name(&fractal.material),
+                }),
+                Object::Curves(curves) => Ok(ObjectDescription::Curves {
+                    curves: curves.curves().to_vec(),
+                    shape: curves.shape,
+                    material: name(&curves.material),
+                }),
+                Object::Custom(custom) if custom.params().offset == Vec3::ZERO => {
+                    let params = custom.params();
+
+                    Ok(ObjectDescription::Custom {
+                        shape: params.shape.clone(),
+                        params: params.params.clone(),
+                        material: name(&params.material),
+                    })
+                }
+                Object::Mesh(_) | Object::PointCloud(_) | Object::Custom(_) => Err(format!(
+                    "object {} is a mesh, a point cloud or a moved shape, which can only be \
+                     saved as JSON",
+                    i
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            camera,
+            materials: materials
+                .into_iter()
+                .enumerate()
+                .map(|(i, material)| (format!("material{}", i), material))
+                .collect(),
+            objects,
+            render,
+            script: None,
+            seed: None,
+            animation: None,
+        })
+    }
+
+    /// Save the scene to the RON file at `path`, which [`Self::load`]
+    /// reads back.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let config = ron::ser::PrettyConfig::default().extensions(Extensions::IMPLICIT_SOME);
+        let text = ron::ser::to_string_pretty(self, config).unwrap();
+
+        fs::write(path, text + "\n")
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))
+    }
+
+    pub fn build(&self) -> Vec<Object> {
+        self.build_animated().0
+    }
+
+    /// Build the objects of the scene together with their animation.
+    pub fn build_animated(&self) -> (Vec<Object>, Animation) {
+        self.try_build()
+            .expect("the objects and the animation are checked when parsed")
+    }
+
+    fn try_build(&self) -> Result<(Vec<Object>, Animation), String> {
+        let mut objects = vec![];
+        let mut named = BTreeMap::new();
+        for object in &self.objects {
+            let start = objects.len();
+            objects.extend(self.objects(object)?);
+
+            if let ObjectDescription::Named { name, .. } = object {
+                if named.insert(name.as_str(), start..objects.len()).is_some() {
+                    return Err(format!("the name `{}` is given to several objects", name));
+                }
+            }
+        }
+
+        let animation = match &self.animation {
+            Some(animation) => animation
+                .animation(&named)
+                .map_err(|e| format!("the animation: {}", e))?,
+            None => Animation::default(),
+        };
+
+        Ok((objects, animation))
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::{Duration, Instant};
+
+use raycasting_rs::aov::Aov;
+use raycasting_rs::camera::Camera;
+use raycasting_rs::object::Object;
+use raycasting_rs::render::Tracer;
+use raycasting_rs::triangle::Triangle;
+use raycasting_rs::vec3::Color;
+use raycasting_rs::world::World;
+
+use crate::options::Options;
+
+/// Width of the probe image as a fraction of the one of the image.
+const PROBE_SCALE: u32 = 8;
+
+/// Samples of the probe image, when the image has more.
+const PROBE_SAMPLES: u32 = 4;
+
+/// Build the scene of the `options` and report its size and the
+/// memory it takes, then render a small probe image to estimate
+/// how long the whole render would take.
+pub fn dry_run(options: &Options) -> Result<(), String> {
+    let objects = options.scene.build();
+    let triangles = objects
+        .iter()
+        .map(|object| match object {
+            Object::Mesh(mesh) => mesh.triangles().len(),
+            _ => 0,
+        })
+        .sum::<usize>();
+    let spheres = objects
+        .iter()
+        .filter(|object| matches!(object, Object::Sphere(_)))
+        .count();
+
+    let world = World::from(objects);
+    let bvh = world.bvh_stats();
+
+    println!(
+        "Objects: {} ({} spheres, {} triangles in {} meshes)",
+        world.objects().len(),
+        spheres,
+        triangles,
+        world
+            .objects()
+            .iter()
+            .filter(|object| matches!(object, Object::Mesh(_)))
+            .count()
+    );
+    println!(
+        "BVH: {} nodes, {} leaves, depth {}",
+        bvh.nodes, bvh.leaves, bvh.depth
+    );
+
+    let new_camera = |width: u32| {
+        Camera::new(
+            options.aspect_ratio,
+            width,
+            options.v_fov,
+            options.look_from,
+            options.look_at,
+            options.defocus_angle,
+            options.focus_dist,
+        )
+        .up(options.up)
+        .threads(options.threads)
+        .quiet()
+    };
+    let camera = new_camera(options.width);
+    let (width, height) = (camera.width(), camera.height());
+
+    let scene_bytes = size_of_val(world.objects()) + triangles * size_of::<Triangle>() + bvh.bytes;
+    let image_bytes = (width * height) as usize * size_of::<(Color, Aov)>();
+    println!(
+        "Memory: {} for the scene, {} for each image",
+        format_bytes(scene_bytes),
+        format_bytes(image_bytes)
+    );
+
+    let probe = new_camera((width / PROBE_SCALE).max(1));
+    let probe_samples = options.samples.min(PROBE_SAMPLES);
+    let tracer = Tracer {
+        max_depth: options.max_depth,
+        transparent: options.transparent,
+        firefly: options.firefly,
+    };
+
+    let time = Instant::now();
+    probe.ray_map(probe_samples, |r| tracer.pixel(r, &world), |_, _| {});
+    let elapsed = time.elapsed();
+    println!(
+        "Probe: {}x{} pixels with {} samples in {}",
+        probe.width(),
+        probe.height(),
+        probe_samples,
+        format_duration(elapsed)
+    );
+
+    let rays = |camera: &Camera, samples: u32| {
+        camera.width() as f64 * camera.height() as f64 * samples as f64
+    };
+    let images = options.frames * if options.anaglyph { 2 } else { 1 };
+    let estimate = elapsed.as_secs_f64() * rays(&camera, options.samples)
+        / rays(&probe, probe_samples)
+        * images as f64;
+    let estimate = Duration::from_secs_f64(estimate);
+
+    print!(
+        "Estimated render time: {} for {} image{} of {}x{} pixels with {} samples",
+        format_duration(estimate),
+        images,
+        if images == 1 { "" } else { "s" },
+        width,
+        height,
+        options.samples
+    );
+    match options.time_limit {
+        Some(limit) if limit * images < estimate => {
+            println!(", limited to {}", format_duration(limit * images))
+        }
+        _ => println!(),
+    }
+
+    Ok(())
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b < 1 << 10 => format!("{} B", b),
+        b if b < 1 << 20 => format!("{:.1} KiB", b as f64 / (1 << 10) as f64),
+        b if b < 1 << 30 => format!("{:.1} MiB", b as f64 / (1 << 20) as f64),
+        b => format!("{:.1} GiB", b as f64 / (1 << 30) as f64),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match secs {
+        0..60 => format!("{:.2}s", duration.as_secs_f32()),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
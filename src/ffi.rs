@@ -0,0 +1,297 @@
+//! C API of the renderer, declared in `include/raycasting_rs.h`.
+//!
+//! The functions returning an `int` return 0 on success and -1 on
+//! failure, in which case `rt_last_error` describes the error.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CString},
+    slice,
+};
+
+use crate::{
+    camera::Camera,
+    description::CameraDescription,
+    material::Material,
+    mesh::Mesh,
+    object::{self, Object},
+    render::Tracer,
+    sphere::Sphere,
+    triangle::Triangle,
+    vec3::{Color, Float, Vec3},
+    world::World,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+// The C API is in single precision whatever the precision of the renderer.
+#[allow(clippy::unnecessary_cast)]
+fn float(x: Float) -> f32 {
+    x as f32
+}
+
+fn fail(error: impl Into<String>) -> c_int {
+    let error = CString::new(error.into()).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = error);
+    -1
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub enum RtMaterialKind {
+    RtLambertian,
+    RtMetal,
+    RtDielectric,
+    /// Light source, its color can be brighter than white.
+    RtEmissive,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RtMaterial {
+    pub kind: RtMaterialKind,
+    pub color: [f32; 3],
+    /// Blur of the reflections and refractions, 0 disables it.
+    pub fuzz: f32,
+    /// Used only by the dielectrics.
+    pub refraction_index: f32,
+}
+
+impl From<RtMaterial> for Material {
+    fn from(m: RtMaterial) -> Self {
+        let color = Color::from_f32(m.color);
+        let fuzz = Some(m.fuzz as Float).filter(|f| *f != 0.);
+
+        match m.kind {
+            RtMaterialKind::RtLambertian => Material::lambertian(color, fuzz),
+            RtMaterialKind::RtMetal => Material::metal(color, fuzz),
+            RtMaterialKind::RtDielectric => Material::dielectric(m.refraction_index as Float, fuzz),
+            RtMaterialKind::RtEmissive => Material::emissive(color),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RtCamera {
+    pub look_from: [f32; 3],
+    pub look_at: [f32; 3],
+    /// Direction pointing up in the image.
+    pub up: [f32; 3],
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    /// Aperture of the defocus blur cone in degrees.
+    pub defocus_angle: f32,
+    pub focus_dist: f32,
+}
+
+/// Objects and camera of a scene, created with `rt_scene_new`.
+pub struct RtScene {
+    objects: Vec<Object>,
+    camera: RtCamera,
+}
+
+/// Camera with the defaults of the command line.
+#[no_mangle]
+pub extern "C" fn rt_camera_default() -> RtCamera {
+    let settings = CameraDescription::default().settings(1, 1.);
+
+    RtCamera {
+        look_from: settings.look_from.to_f32(),
+        look_at: settings.look_at.to_f32(),
+        up: settings.vup.to_f32(),
+        fov: float(settings.v_fov),
+        defocus_angle: float(settings.defocus_angle),
+        focus_dist: float(settings.focus_dist),
+    }
+}
+
+/// Create an empty scene seen by the default camera, to be released
+/// with `rt_scene_free`.
+#[no_mangle]
+pub extern "C" fn rt_scene_new() -> *mut RtScene {
+    Box::into_raw(Box::new(RtScene {
+        objects: vec![],
+        camera: rt_camera_default(),
+    }))
+}
+
+/// # Safety
+///
+/// `scene` must be null or returned by `rt_scene_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_free(scene: *mut RtScene) {
+    if !scene.is_null() {
+        drop(Box::from_raw(scene));
+    }
+}
+
+/// # Safety
+///
+/// `scene` must be null or a live scene, and `camera` null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_set_camera(
+    scene: *mut RtScene,
+    camera: *const RtCamera,
+) -> c_int {
+    let (Some(scene), Some(camera)) = (scene.as_mut(), camera.as_ref()) else {
+        return fail("the scene and the camera can't be null");
+    };
+
+    scene.camera = *camera;
+    0
+}
+
+/// # Safety
+///
+/// `scene` must be null or a live scene, and `center` null or pointing
+/// to 3 floats.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_sphere(
+    scene: *mut RtScene,
+    center: *const f32,
+    radius: f32,
+    material: RtMaterial,
+) -> c_int {
+    let Some(scene) = scene.as_mut().filter(|_| !center.is_null()) else {
+        return fail("the scene and the center can't be null");
+    };
+
+    let center = slice::from_raw_parts(center, 3);
+    let sphere = Sphere::new(
+        Vec3::from_f32([center[0], center[1], center[2]]),
+        radius as Float,
+        material.into(),
+    );
+    if let Err(e) = sphere.validate() {
+        return fail(format!("invalid sphere: {}", e));
+    }
+
+    scene.objects.push(Object::Sphere(sphere));
+    0
+}
+
+/// Add the mesh of the triangles whose corners are the `vertices`, given
+/// as `x, y, z` floats, at the `indices`, three for each triangle.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene, `vertices` must point to
+/// `3 * vertex_count` floats and `indices` to `index_count` integers.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_mesh(
+    scene: *mut RtScene,
+    vertices: *const f32,
+    vertex_count: usize,
+    indices: *const u32,
+    index_count: usize,
+    material: RtMaterial,
+) -> c_int {
+    let Some(scene) = scene
+        .as_mut()
+        .filter(|_| !vertices.is_null() && !indices.is_null())
+    else {
+        return fail("the scene, the vertices and the indices can't be null");
+    };
+    if index_count == 0 || !index_count.is_multiple_of(3) {
+        return fail("the indices must be a positive multiple of 3");
+    }
+
+    let vertices = slice::from_raw_parts(vertices, 3 * vertex_count)
+        .chunks_exact(3)
+        .map(|v| Vec3::from_f32([v[0], v[1], v[2]]))
+        .collect::<Vec<_>>();
+    let indices = slice::from_raw_parts(indices, index_count);
+    if indices.iter().any(|i| *i as usize >= vertex_count) {
+        return fail("the indices are out of range");
+    }
+
+    let material = material.into();
+    let mesh = Object::Mesh(Mesh::new(
+        indices
+            .chunks_exact(3)
+            .map(|t| Triangle::new(std::array::from_fn(|i| vertices[t[i] as usize]), material))
+            .collect(),
+    ));
+    if let Err(e) = mesh.validate() {
+        return fail(format!("invalid mesh: {}", e));
+    }
+
+    scene.objects.push(mesh);
+    0
+}
+
+/// Render the `scene` in the caller's `pixels`, `width * height * 3`
+/// floats holding the linear RGB color of each pixel row by row.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene, and `pixels` null or pointing
+/// to `width * height * 3` floats.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    scene: *const RtScene,
+    width: u32,
+    height: u32,
+    samples: u32,
+    max_depth: u32,
+    pixels: *mut f32,
+) -> c_int {
+    let Some(scene) = scene.as_ref().filter(|_| !pixels.is_null()) else {
+        return fail("the scene and the pixels can't be null");
+    };
+    if width == 0 || height == 0 || samples == 0 || max_depth == 0 {
+        return fail("the size, the samples and the maximum depth must be positive");
+    }
+    if let Err(e) = object::validate(&scene.objects) {
+        return fail(e);
+    }
+
+    let RtCamera {
+        look_from,
+        look_at,
+        up,
+        fov,
+        defocus_angle,
+        focus_dist,
+    } = scene.camera;
+    // The height of the camera is truncated from the aspect ratio,
+    // which is aimed at the middle of the last row.
+    let aspect_ratio = width as Float / (height as Float + 0.5);
+    let camera = Camera::from(
+        CameraDescription {
+            look_from: Some(Vec3::from_f32(look_from).into()),
+            look_at: Some(Vec3::from_f32(look_at).into()),
+            up: Some(Vec3::from_f32(up).into()),
+            fov: Some(fov as Float),
+            defocus_angle: Some(defocus_angle as Float),
+            focus_dist: Some(focus_dist as Float),
+        }
+        .settings(width, aspect_ratio),
+    )
+    .quiet();
+    if camera.height() != height {
+        return fail(format!(
+            "can't render an image of {}x{} pixels",
+            width, height
+        ));
+    }
+
+    let world = World::from(scene.objects.clone());
+    let colors = Tracer::new(max_depth).render(&camera, &world, samples);
+    let pixels = slice::from_raw_parts_mut(pixels, 3 * colors.len());
+    for (pixel, color) in pixels.chunks_exact_mut(3).zip(colors) {
+        pixel.copy_from_slice(&color.to_f32());
+    }
+
+    0
+}
+
+/// Message of the last error of the calling thread, valid until the
+/// next call failing on the same thread.
+#[no_mangle]
+pub extern "C" fn rt_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
@@ -0,0 +1,172 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{ray::Ray, vec3::Float};
+
+/// Reconstruction filters weighting the samples of a pixel by their
+/// distance from its center, in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PixelFilter {
+    /// Equal weight over the pixel, the samples don't reach the
+    /// neighbouring pixels.
+    #[default]
+    Box,
+    /// Weight falling linearly to zero one pixel away.
+    Tent,
+    /// Gaussian with a standard deviation of half a pixel, cut off
+    /// a pixel and a half away.
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3, two pixels wide.
+    /// It is sharper than the others, its negative lobes can ring
+    /// around the edges.
+    Mitchell,
+}
+
+impl PixelFilter {
+    pub const NAMES: [&'static str; 4] = ["box", "tent", "gaussian", "mitchell"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => Some(Self::Box),
+            "tent" => Some(Self::Tent),
+            "gaussian" => Some(Self::Gaussian),
+            "mitchell" => Some(Self::Mitchell),
+            _ => None,
+        }
+    }
+
+    /// Distance from the center of the pixel, on each axis, beyond
+    /// which the weight is zero.
+    pub fn radius(self) -> Float {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.,
+        }
+    }
+
+    /// Weight of a sample at `(x, y)` pixels from the center.
+    pub fn eval(self, x: Float, y: Float) -> Float {
+        self.eval_1d(x) * self.eval_1d(y)
+    }
+
+    fn eval_1d(self, x: Float) -> Float {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.;
+        }
+
+        match self {
+            Self::Box => 1.,
+            Self::Tent => 1. - x,
+            Self::Gaussian => {
+                const SIGMA: Float = 0.5;
+                let gaussian = |x: Float| Float::exp(-x * x / (2. * SIGMA * SIGMA));
+
+                // Shifted so that it reaches zero at the radius.
+                (gaussian(x) - gaussian(self.radius())).max(0.)
+            }
+            Self::Mitchell => {
+                const B: Float = 1. / 3.;
+                const C: Float = 1. / 3.;
+
+                // The cubic is defined over [0, 2].
+                let weight = match x < 1. {
+                    true => {
+                        (12. - 9. * B - 6. * C) * x * x * x
+                            + (-18. + 12. * B + 6. * C) * x * x
+                            + (6. - 2. * B)
+                    }
+                    false => {
+                        (-B - 6. * C) * x * x * x
+                            + (6. * B + 30. * C) * x * x
+                            + (-12. * B - 48. * C) * x
+                            + (8. * B + 24. * C)
+                    }
+                };
+
+                weight / 6.
+            }
+        }
+    }
+}
+
+/// Draws the offsets of the samples from the center of a pixel with
+/// a density following the magnitude of the filter, so that the samples
+/// gather where the weight is high. Their weights are then close to one,
+/// negative on the negative lobes, and a wider filter doesn't add noise.
+#[derive(Debug, Clone)]
+pub struct FilterSampler {
+    filter: PixelFilter,
+    /// Magnitude of the filter at the center of each bin along an axis.
+    bins: Vec<Float>,
+    /// Cumulative distribution of the bins, from 0 to 1.
+    cdf: Vec<Float>,
+}
+
+impl FilterSampler {
+    const BINS: usize = 64;
+
+    pub fn new(filter: PixelFilter) -> Self {
+        let width = 2. * filter.radius() / Self::BINS as Float;
+        let bins = (0..Self::BINS)
+            .map(|i| {
+                filter
+                    .eval_1d(-filter.radius() + (i as Float + 0.5) * width)
+                    .abs()
+            })
+            .collect::<Vec<_>>();
+
+        let total = bins.iter().sum::<Float>();
+        let cdf = std::iter::once(0.)
+            .chain(bins.iter().scan(0., |sum, bin| {
+                *sum += bin / total;
+                Some(*sum)
+            }))
+            .collect();
+
+        Self { filter, bins, cdf }
+    }
+
+    pub fn filter(&self) -> PixelFilter {
+        self.filter
+    }
+
+    /// Offset `(x, y)` from the center of the pixel, in pixels, drawn
+    /// from the uniform random numbers `u` in `[0, 1)`, together with the
+    /// weight of the sample.
+    pub fn sample(&self, u: (Float, Float)) -> (Float, Float, Float) {
+        let (x, wx) = self.sample_1d(u.0);
+        let (y, wy) = self.sample_1d(u.1);
+
+        (x, y, wx * wy)
+    }
+
+    fn sample_1d(&self, u: Float) -> (Float, Float) {
+        // The bins where the filter is zero are never picked, as their
+        // cumulative distribution doesn't grow.
+        let bin = (self.cdf.partition_point(|c| *c <= u) - 1).min(Self::BINS - 1);
+        let t = (u - self.cdf[bin]) / (self.cdf[bin + 1] - self.cdf[bin]);
+
+        let width = 2. * self.filter.radius() / Self::BINS as Float;
+        let x = -self.filter.radius() + (bin as Float + t.clamp(0., 1.)) * width;
+
+        (x, self.filter.eval_1d(x) / self.bins[bin])
+    }
+}
+
+impl Default for FilterSampler {
+    fn default() -> Self {
+        Self::new(PixelFilter::default())
+    }
+}
+
+/// Camera ray of a pixel with the weight of the pixel filter at the
+/// point of the image it was shot through.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub ray: Ray,
+    pub weight: Float,
+}
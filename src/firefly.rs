@@ -0,0 +1,55 @@
+use crate::vec3::{Color, Float};
+
+/// Reduce the fireflies, single blown-out pixels produced by rare
+/// high energy paths, when averaging the samples of a pixel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FireflyFilter {
+    /// Samples brighter than this luminance are scaled down to it.
+    pub max_luminance: Option<Float>,
+    /// Samples whose luminance is more than `outlier_sigma` standard
+    /// deviations above the mean of the pixel are discarded.
+    pub outlier_sigma: Option<Float>,
+}
+
+impl FireflyFilter {
+    /// Average the `samples` of a pixel, each one with the weight of
+    /// the pixel filter.
+    pub fn resolve(&self, samples: &[(Color, Float)]) -> Color {
+        let clamped = samples.iter().map(|&(c, w)| match self.max_luminance {
+            Some(max) if c.luminance() > max => ((max / c.luminance()) * c, w),
+            _ => (c, w),
+        });
+
+        let Some(sigma) = self.outlier_sigma else {
+            return Self::weighted_average(clamped);
+        };
+
+        let n = samples.len() as Float;
+        let mean = clamped.clone().map(|(c, _)| c.luminance()).sum::<Float>() / n;
+        let variance = clamped
+            .clone()
+            .map(|(c, _)| Float::powi(c.luminance() - mean, 2))
+            .sum::<Float>()
+            / n;
+        let threshold = mean + sigma * variance.sqrt();
+
+        // At least the samples below the mean are always kept.
+        Self::weighted_average(clamped.filter(|(c, _)| c.luminance() <= threshold))
+    }
+
+    /// The negative lobes of a filter can cancel out the weights of a few
+    /// samples, which are then averaged with equal weights.
+    fn weighted_average(samples: impl Iterator<Item = (Color, Float)> + Clone) -> Color {
+        let (sum, total) = samples
+            .clone()
+            .fold((Color::BLACK, 0.), |(sum, total), (c, w)| {
+                (sum + w * c, total + w)
+            });
+        if total > Float::EPSILON {
+            return (1. / total) * sum;
+        }
+
+        let (sum, n) = samples.fold((Color::BLACK, 0), |(sum, n), (c, _)| (sum + c, n + 1));
+        (1. / n as Float) * sum
+    }
+}
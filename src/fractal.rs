@@ -0,0 +1,279 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Float, Vec3},
+};
+
+/// Steps of the sphere tracing at most, past which the ray is taken as
+/// missing the fractal.
+const MAX_STEPS: u32 = 300;
+
+/// Distance from the surface at which it is hit, relative to the scale
+/// of the fractal.
+const EPSILON: Float = 2e-4;
+
+/// Fractals drawn from their distance estimator, about as large as the
+/// cube from -1 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub enum FractalKind {
+    /// Three dimensional Mandelbrot set, from the powers of the points
+    /// in spherical coordinates, standing along the y axis.
+    Mandelbulb {
+        /// Power of the iterated function, 8 for the classic bulb.
+        #[cfg_attr(feature = "serde", serde(default = "default_power"))]
+        power: Float,
+        /// More iterations carve finer details.
+        #[cfg_attr(feature = "serde", serde(default = "default_iterations"))]
+        iterations: u32,
+    },
+    /// Cube with the central cross of its 27 sub-cubes removed, again
+    /// in each of the others for each iteration.
+    Menger {
+        #[cfg_attr(feature = "serde", serde(default = "default_menger_iterations"))]
+        iterations: u32,
+    },
+}
+
+#[cfg(feature = "serde")]
+fn default_power() -> Float {
+    8.
+}
+
+#[cfg(feature = "serde")]
+fn default_iterations() -> u32 {
+    10
+}
+
+#[cfg(feature = "serde")]
+fn default_menger_iterations() -> u32 {
+    4
+}
+
+impl FractalKind {
+    /// Radius of the sphere around the origin enclosing the fractal.
+    fn radius(&self) -> Float {
+        match self {
+            // The points further than 2 escape whatever the power.
+            Self::Mandelbulb { .. } => 2.,
+            Self::Menger { .. } => Float::sqrt(3.),
+        }
+    }
+
+    /// Lower bound of the distance of the point `p` from the fractal.
+    fn distance(&self, p: Vec3) -> Float {
+        match *self {
+            Self::Mandelbulb { power, iterations } => mandelbulb(p, power, iterations),
+            Self::Menger { iterations } => menger(p, iterations),
+        }
+    }
+}
+
+/// Fractal object, rendered by sphere tracing its distance estimator:
+/// the rays advance by the distance to the surface the estimator
+/// guarantees, until they get close enough. The fractals are only hit
+/// from outside, so they suit opaque materials.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fractal {
+    pub kind: FractalKind,
+    pub center: Vec3,
+    /// Half the size of the fractal, the side of the sponge.
+    pub scale: Float,
+    pub material: Material,
+}
+
+impl Fractal {
+    pub fn new(kind: FractalKind, center: Vec3, scale: Float, material: Material) -> Self {
+        Self {
+            kind,
+            center,
+            scale,
+            material,
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let radius = self.scale * self.kind.radius();
+        let extent = Vec3(radius, radius, radius);
+
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.center.is_finite() {
+            return Err(format!("the center must be finite, found {}", self.center));
+        }
+        if !(self.scale > 0. && self.scale.is_finite()) {
+            return Err(format!("the scale must be positive, found {}", self.scale));
+        }
+        match self.kind {
+            FractalKind::Mandelbulb { power, iterations } => {
+                if !(2. ..=16.).contains(&power) {
+                    return Err(format!(
+                        "the power of the mandelbulb must be between 2 and 16, found {}",
+                        power
+                    ));
+                }
+                if !(1..=64).contains(&iterations) {
+                    return Err(format!(
+                        "the iterations of the mandelbulb must be between 1 and 64, found {}",
+                        iterations
+                    ));
+                }
+            }
+            FractalKind::Menger { iterations } => {
+                if iterations > 10 {
+                    return Err(format!(
+                        "the iterations of the Menger sponge must be at most 10, found {}",
+                        iterations
+                    ));
+                }
+            }
+        }
+
+        self.material.validate()
+    }
+
+    /// Distance of the point `p` from the fractal, at least.
+    fn distance(&self, p: Vec3) -> Float {
+        self.scale * self.kind.distance((1. / self.scale) * (p - self.center))
+    }
+
+    /// Distance of the hit of the ray `r` within `t_range`.
+    fn intersect(&self, r: &Ray, t_range: Interval) -> Option<Float> {
+        // Only march within the sphere enclosing the fractal.
+        let oc = r.start - self.center;
+        let radius = self.scale * self.kind.radius();
+        let a = Vec3::dot(r.dir, r.dir);
+        let half_b = Vec3::dot(r.dir, oc);
+        let c = Vec3::dot(oc, oc) - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if !(discriminant > 0. && a > 0.) {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        let span = Interval::new(
+            t_range.min.max((-half_b - sqrtd) / a),
+            t_range.max.min((-half_b + sqrtd) / a),
+        );
+
+        let speed = a.sqrt();
+        let epsilon = EPSILON * self.scale;
+        let mut t = span.min;
+
+        // The rays bouncing off the fractal start on its surface, where
+        // they would hit it again right away: they first leave it.
+        let mut steps = 0;
+        while self.distance(r.at(t)) < 2. * epsilon {
+            t += epsilon / speed;
+            steps += 1;
+            if steps > 16 || t > span.max {
+                return None;
+            }
+        }
+
+        for _ in 0..MAX_STEPS {
+            if t > span.max {
+                return None;
+            }
+
+            let d = self.distance(r.at(t));
+            if d < epsilon {
+                return Some(t);
+            }
+            t += d / speed;
+        }
+
+        None
+    }
+
+    /// Normal of the surface near the point `p`, from the gradient of
+    /// the distance.
+    fn normal(&self, p: Vec3) -> Vec3 {
+        let h = EPSILON * self.scale;
+        let dx = Vec3(h, 0., 0.);
+        let dy = Vec3(0., h, 0.);
+        let dz = Vec3(0., 0., h);
+
+        Vec3::unit(Vec3(
+            self.distance(p + dx) - self.distance(p - dx),
+            self.distance(p + dy) - self.distance(p - dy),
+            self.distance(p + dz) - self.distance(p - dz),
+        ))
+    }
+}
+
+impl Hit<Ray> for Fractal {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let t = self.intersect(r, t_range)?;
+        let p = r.at(t);
+
+        Some(HitRecord::new(p, self.normal(p), t, &self.material, r))
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.intersect(r, t_range).is_some()
+    }
+}
+
+/// Distance estimator of the mandelbulb, from the derivative of the
+/// iterated function tracked along with it.
+fn mandelbulb(p: Vec3, power: Float, iterations: u32) -> Float {
+    // The bulb turns around the z axis of its formula, the y axis here.
+    let c = Vec3(p.0, p.2, p.1);
+    let mut z = c;
+    let mut dr = 1.;
+    let mut r = Vec3::norm(z);
+
+    for _ in 0..iterations {
+        if r > 2. {
+            break;
+        }
+
+        let theta = (z.2 / r).acos() * power;
+        let phi = z.1.atan2(z.0) * power;
+        dr = power * r.powf(power - 1.) * dr + 1.;
+
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        z = r.powf(power) * Vec3(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta) + c;
+        r = Vec3::norm(z);
+    }
+
+    // The center, where the logarithm fails, is inside the bulb.
+    match r > 0. {
+        true => 0.5 * r.ln() * r / dr,
+        false => 0.,
+    }
+}
+
+/// Distance estimator of the Menger sponge: the distance from the cube
+/// pushed away by the crosses carved out of it at each scale.
+fn menger(p: Vec3, iterations: u32) -> Float {
+    let q = Vec3(p.0.abs() - 1., p.1.abs() - 1., p.2.abs() - 1.);
+    let outside = Vec3::norm(Vec3(q.0.max(0.), q.1.max(0.), q.2.max(0.)));
+    let mut d = outside + q.0.max(q.1).max(q.2).min(0.);
+
+    let mut s = 1.;
+    for _ in 0..iterations {
+        // Position within the cell of the current scale, folded so that
+        // the cross carved out of its middle is where two coordinates
+        // are below 1.
+        let r = |x: Float| (1. - 3. * ((x * s).rem_euclid(2.) - 1.).abs()).abs();
+        let (rx, ry, rz) = (r(p.0), r(p.1), r(p.2));
+        s *= 3.;
+
+        let cross = (rx.max(ry).min(ry.max(rz)).min(rz.max(rx)) - 1.) / s;
+        d = d.max(cross);
+    }
+
+    d
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use raycasting_rs::camera::Camera;
+use raycasting_rs::material::{Material, MaterialType};
+use raycasting_rs::render::Tracer;
+use raycasting_rs::vec3::{Color, Float};
+use raycasting_rs::world::{Background, World};
+
+use crate::options::Options;
+
+/// Bounces of the rays at least, as the paths cut short are black and
+/// would look like energy lost by the materials.
+const MIN_DEPTH: u32 = 100;
+
+/// Relative deviation from white tolerated for a material.
+const TOLERANCE: f64 = 0.01;
+
+/// Luminance of the pixels showing the same material first.
+#[derive(Debug, Clone, Copy, Default)]
+struct Group {
+    pixels: usize,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl Group {
+    // The sums are in `f64` whatever the `Float`, not to lose the
+    // small deviations over many pixels.
+    #[allow(clippy::unnecessary_cast)]
+    fn add(&mut self, luminance: Float) {
+        let luminance = luminance as f64;
+        self.pixels += 1;
+        self.sum += luminance;
+        self.sum_squares += luminance * luminance;
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.pixels as f64
+    }
+
+    /// Standard error of the mean, the noise expected from the samples.
+    fn error(&self) -> f64 {
+        let mean = self.mean();
+        let variance = (self.sum_squares / self.pixels as f64 - mean * mean).max(0.);
+
+        (variance / self.pixels as f64).sqrt()
+    }
+
+    /// Whether the mean is further from white than both the tolerance
+    /// and the noise explain.
+    fn fails(&self) -> bool {
+        let deviation = (self.mean() - 1.).abs();
+
+        deviation > TOLERANCE && deviation > 3. * self.error()
+    }
+}
+
+/// White furnace test: render the scene of the `options` with all the
+/// materials turned white under a uniform white sky. A material that
+/// neither absorbs nor emits light must then look exactly as white as
+/// the sky, so any deviation of the pixels showing it is energy gained
+/// or lost by its implementation.
+///
+/// The pixels are grouped by the material they show first, but the
+/// light bouncing off the other materials counts too, so a material
+/// losing energy darkens its neighbours a little as well. The lights
+/// become white lambertian surfaces, so that they are tested too, and
+/// the scene must be open for the rays to reach the sky.
+pub fn furnace(options: &Options) -> Result<(), String> {
+    let mut objects = options.scene.build();
+    let mut materials = BTreeMap::new();
+    for object in &mut objects {
+        object.map_materials(|material| {
+            *material = whiten(*material);
+            materials.insert(material.id(), *material);
+        })?;
+    }
+
+    let world = World::builder()
+        .add_many(objects)
+        .with_background(Background::Color(Color::WHITE))
+        .build()?;
+    let camera = Camera::new(
+        options.aspect_ratio,
+        options.width,
+        options.v_fov,
+        options.look_from,
+        options.look_at,
+        options.defocus_angle,
+        options.focus_dist,
+    )
+    .up(options.up)
+    .threads(options.threads)
+    .quiet();
+    let tracer = Tracer::new(options.max_depth.max(MIN_DEPTH));
+
+    let pixels = camera.ray_map(options.samples, |r| tracer.pixel(r, &world), |_, _| {});
+
+    let mut total = Group::default();
+    let mut groups = BTreeMap::<_, Group>::new();
+    for (color, aov) in &pixels {
+        let luminance = color.luminance();
+        total.add(luminance);
+        groups.entry(aov.material).or_default().add(luminance);
+    }
+
+    println!(
+        "Furnace: {}x{} pixels with {} samples, mean {:.4} ({:+.2}%)",
+        camera.width(),
+        camera.height(),
+        options.samples,
+        total.mean(),
+        100. * (total.mean() - 1.)
+    );
+
+    // The materials seen the most first, whose means are the most
+    // reliable.
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort_by_key(|(_, group)| std::cmp::Reverse(group.pixels));
+
+    let mut failed = 0;
+    for (material, group) in &groups {
+        let name = match material {
+            None => "sky".into(),
+            Some(id) => describe(&materials[id]),
+        };
+        let verdict = match group.fails() {
+            true => {
+                failed += 1;
+                "FAIL"
+            }
+            false => "ok",
+        };
+
+        println!(
+            "  {:<28} {:>8} pixels, mean {:.4} ({:+.2}% ± {:.2}%) {}",
+            name,
+            group.pixels,
+            group.mean(),
+            100. * (group.mean() - 1.),
+            300. * group.error(),
+            verdict
+        );
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!(
+            "{} material{} of the scene {} not conserve energy",
+            n,
+            if n == 1 { "" } else { "s" },
+            if n == 1 { "does" } else { "do" }
+        )),
+    }
+}
+
+/// The `material` with a white color, which reflects or transmits all
+/// the light it receives.
+fn whiten(material: Material) -> Material {
+    match material.material {
+        MaterialType::Emissive => Material::lambertian(Color::WHITE, None),
+        _ => Material {
+            solid_color: Color::WHITE,
+            ..material
+        },
+    }
+}
+
+fn describe(material: &Material) -> String {
+    let mut name = match material.material {
+        MaterialType::Metal => "metal".to_string(),
+        MaterialType::Lambertian => "lambertian".to_string(),
+        MaterialType::Dielectric => format!("dielectric {}", material.refraction_index),
+        MaterialType::Emissive => "emissive".to_string(),
+    };
+    if let Some(fuzz) = material.fuzz {
+        name += &format!(" fuzz {}", fuzz);
+    }
+
+    name
+}
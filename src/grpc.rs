@@ -0,0 +1,363 @@
+//! gRPC service of the render jobs described by `proto/render_jobs.proto`,
+//! through which other processes submit scenes, follow their progress
+//! and collect the finished tiles and frames.
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+};
+
+use tonic::{Request, Response, Status};
+
+use crate::{
+    camera::Camera,
+    description::DEFAULT_ASPECT_RATIO,
+    output::{self, BitDepth, ImageFormat},
+    render::Tracer,
+    scene::Scene,
+    tonemap::ToneMap,
+    vec3::Color,
+    world::World,
+};
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/raycasting.RenderJobs.rs"));
+}
+
+pub use generated::{render_jobs_client, render_jobs_server};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JobRequest {
+    /// Scene as a JSON world or described in RON.
+    #[prost(string, tag = "1")]
+    pub scene: String,
+    /// The settings left to 0 are taken from the scene, or the defaults.
+    #[prost(uint32, tag = "2")]
+    pub width: u32,
+    #[prost(uint32, tag = "3")]
+    pub samples: u32,
+    #[prost(uint32, tag = "4")]
+    pub max_depth: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JobId {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, prost::Enumeration)]
+#[repr(i32)]
+pub enum JobState {
+    Queued = 0,
+    Running = 1,
+    Done = 2,
+    Failed = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct JobProgress {
+    #[prost(enumeration = "JobState", tag = "1")]
+    pub state: i32,
+    #[prost(uint32, tag = "2")]
+    pub rows_done: u32,
+    #[prost(uint32, tag = "3")]
+    pub rows: u32,
+    /// Why the job failed.
+    #[prost(string, tag = "4")]
+    pub error: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TileRequest {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(uint32, tag = "2")]
+    pub x: u32,
+    #[prost(uint32, tag = "3")]
+    pub y: u32,
+    #[prost(uint32, tag = "4")]
+    pub width: u32,
+    #[prost(uint32, tag = "5")]
+    pub height: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Tile {
+    #[prost(uint32, tag = "1")]
+    pub x: u32,
+    #[prost(uint32, tag = "2")]
+    pub y: u32,
+    #[prost(uint32, tag = "3")]
+    pub width: u32,
+    #[prost(uint32, tag = "4")]
+    pub height: u32,
+    /// Linear RGB of each pixel, row by row.
+    #[prost(float, repeated, tag = "5")]
+    pub pixels: Vec<f32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Frame {
+    #[prost(uint32, tag = "1")]
+    pub width: u32,
+    #[prost(uint32, tag = "2")]
+    pub height: u32,
+    /// Tone mapped image encoded as PNG.
+    #[prost(bytes = "vec", tag = "3")]
+    pub png: Vec<u8>,
+}
+
+/// A submitted job and the rows rendered so far.
+struct Job {
+    state: JobState,
+    error: String,
+    width: u32,
+    height: u32,
+    /// Linear color of each pixel, row by row.
+    pixels: Vec<Color>,
+    done: Vec<bool>,
+}
+
+type Jobs = Arc<Mutex<HashMap<u64, Job>>>;
+
+/// Lock the `jobs`, which stay consistent even when a render panics
+/// while holding the lock.
+fn lock(jobs: &Jobs) -> MutexGuard<'_, HashMap<u64, Job>> {
+    jobs.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Render of a job waiting in the queue.
+struct Queued {
+    id: u64,
+    scene: Scene,
+    camera: Camera,
+    tracer: Tracer,
+    samples: u32,
+}
+
+impl Queued {
+    /// Render the job, storing each row in its `Job` as it's completed.
+    fn render(self, jobs: &Jobs) {
+        let world = World::from(self.scene.build());
+
+        self.camera.ray_map(
+            self.samples,
+            |r| self.tracer.pixel(r, &world).0,
+            |y, row| {
+                let mut jobs = lock(jobs);
+                let job = jobs.get_mut(&self.id).unwrap();
+                let start = (y * job.width) as usize;
+
+                job.pixels[start..start + row.len()].copy_from_slice(row);
+                job.done[y as usize] = true;
+            },
+        );
+    }
+}
+
+/// Queue of the render jobs, rendered one at a time in the order
+/// they are submitted.
+pub struct JobQueue {
+    jobs: Jobs,
+    queue: mpsc::Sender<Queued>,
+    next_id: AtomicU64,
+    /// Number of threads rendering each job, one for each core by default.
+    threads: Option<u32>,
+}
+
+impl JobQueue {
+    pub fn new(threads: Option<u32>) -> Self {
+        let jobs = Jobs::default();
+        let (queue, pending) = mpsc::channel::<Queued>();
+
+        let worker_jobs = jobs.clone();
+        thread::spawn(move || {
+            for queued in pending {
+                let id = queued.id;
+                let set_state = |state, error: &str| {
+                    let mut jobs = lock(&worker_jobs);
+                    let job = jobs.get_mut(&id).unwrap();
+                    job.state = state;
+                    job.error = error.into();
+                };
+
+                set_state(JobState::Running, "");
+                match panic::catch_unwind(AssertUnwindSafe(|| queued.render(&worker_jobs))) {
+                    Ok(()) => set_state(JobState::Done, ""),
+                    Err(_) => set_state(JobState::Failed, "the render panicked"),
+                }
+            }
+        });
+
+        Self {
+            jobs,
+            queue,
+            next_id: AtomicU64::new(0),
+            threads,
+        }
+    }
+
+    /// Run `f` on the job `id`, failing when it doesn't exist.
+    fn with_job<T>(&self, id: u64, f: impl FnOnce(&Job) -> Result<T, Status>) -> Result<T, Status> {
+        match lock(&self.jobs).get(&id) {
+            Some(job) => f(job),
+            None => Err(Status::not_found(format!("there is no job {}", id))),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl render_jobs_server::RenderJobs for JobQueue {
+    async fn submit(&self, request: Request<JobRequest>) -> Result<Response<JobId>, Status> {
+        let request = request.into_inner();
+        let scene = Scene::parse(&request.scene).map_err(Status::invalid_argument)?;
+
+        let (camera, render) = scene.settings();
+        let nonzero = |x: u32| Some(x).filter(|x| *x > 0);
+        let width = nonzero(request.width).or(render.width).unwrap_or(800);
+        let aspect_ratio = render.aspect_ratio.unwrap_or(DEFAULT_ASPECT_RATIO);
+        let samples = nonzero(request.samples).or(render.samples).unwrap_or(50);
+        let max_depth = nonzero(request.max_depth)
+            .or(render.max_depth)
+            .unwrap_or(20);
+
+        let camera = Camera::from(camera.settings(width, aspect_ratio))
+            .threads(self.threads)
+            .quiet();
+        let (width, height) = (camera.width(), camera.height());
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        lock(&self.jobs).insert(
+            id,
+            Job {
+                state: JobState::Queued,
+                error: String::new(),
+                width,
+                height,
+                pixels: vec![Color::BLACK; (width * height) as usize],
+                done: vec![false; height as usize],
+            },
+        );
+        let queued = Queued {
+            id,
+            scene,
+            camera,
+            tracer: Tracer::new(max_depth),
+            samples,
+        };
+        self.queue
+            .send(queued)
+            .map_err(|_| Status::unavailable("the render thread has stopped"))?;
+
+        Ok(Response::new(JobId { id }))
+    }
+
+    async fn progress(&self, request: Request<JobId>) -> Result<Response<JobProgress>, Status> {
+        self.with_job(request.into_inner().id, |job| {
+            Ok(Response::new(JobProgress {
+                state: job.state.into(),
+                rows_done: job.done.iter().filter(|done| **done).count() as u32,
+                rows: job.height,
+                error: job.error.clone(),
+            }))
+        })
+    }
+
+    async fn fetch_tile(&self, request: Request<TileRequest>) -> Result<Response<Tile>, Status> {
+        let TileRequest {
+            id,
+            x,
+            y,
+            width,
+            height,
+        } = request.into_inner();
+
+        self.with_job(id, |job| {
+            if x.checked_add(width).is_none_or(|end| end > job.width)
+                || y.checked_add(height).is_none_or(|end| end > job.height)
+            {
+                return Err(Status::out_of_range(format!(
+                    "the tile is outside of the {}x{} image",
+                    job.width, job.height
+                )));
+            }
+
+            let rows = y as usize..(y + height) as usize;
+            if !job.done[rows.clone()].iter().all(|done| *done) {
+                return Err(Status::failed_precondition("the tile is not rendered yet"));
+            }
+
+            let pixels = rows
+                .flat_map(|row| {
+                    let start = row * job.width as usize + x as usize;
+                    &job.pixels[start..start + width as usize]
+                })
+                .flat_map(|c| c.to_f32())
+                .collect();
+
+            Ok(Response::new(Tile {
+                x,
+                y,
+                width,
+                height,
+                pixels,
+            }))
+        })
+    }
+
+    async fn fetch_frame(&self, request: Request<JobId>) -> Result<Response<Frame>, Status> {
+        self.with_job(request.into_inner().id, |job| {
+            if job.state != JobState::Done {
+                return Err(Status::failed_precondition("the job is not done yet"));
+            }
+
+            let pixels = job
+                .pixels
+                .iter()
+                .map(|c| ToneMap::default().apply(*c).to_gamma())
+                .collect::<Vec<_>>();
+            let format = ImageFormat::Png {
+                depth: BitDepth::Eight,
+            };
+
+            let mut png = Cursor::new(vec![]);
+            output::write(&mut png, format, job.width, job.height, &pixels, None)
+                .map_err(|e| Status::internal(format!("failed to encode the image: {}", e)))?;
+
+            Ok(Response::new(Frame {
+                width: job.width,
+                height: job.height,
+                png: png.into_inner(),
+            }))
+        })
+    }
+}
+
+/// Serve the render jobs on `addr` until the process is stopped,
+/// rendering each one with `threads` threads.
+pub fn serve(addr: &str, threads: Option<u32>) -> Result<(), String> {
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("invalid address `{}`: {}", addr, e))?;
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("failed to start the runtime: {}", e))?;
+
+    tracing::info!("Serving the render jobs on {}", addr);
+    runtime
+        .block_on(
+            tonic::transport::Server::builder()
+                .add_service(render_jobs_server::RenderJobsServer::new(JobQueue::new(
+                    threads,
+                )))
+                .serve(addr),
+        )
+        .map_err(|e| format!("failed to serve on `{}`: {}", addr, e))
+}
@@ -1,30 +1,76 @@
-use std::ops::Range;
-
-use crate::{material::Material, ray::Ray, vec3::Vec3};
+use crate::{
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Color, Float, Vec3},
+};
 
 pub trait Hit<F> {
-    fn hit(&self, f: &F, t_range: Range<f32>) -> Option<HitRecord>;
+    fn hit(&self, f: &F, t_range: Interval) -> Option<HitRecord<'_>>;
+
+    /// Whether anything is hit within `t_range`, like the shadow rays
+    /// need, which can stop at the first hit and skip its record.
+    fn hit_any(&self, f: &F, t_range: Interval) -> bool {
+        self.hit(f, t_range).is_some()
+    }
 }
 
+/// Hit of a ray, which borrows the material of the object instead of
+/// copying it for each hit replaced by a closer one.
 #[derive(Debug, Clone, Copy)]
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub p: Vec3,
+    /// Normal shading the surface.
     pub normal: Vec3,
-    pub t: f32,
+    /// Normal of the surface itself, which the shading `normal` can
+    /// differ from, like the normals interpolated across a triangle. The
+    /// rays leave the surface along it.
+    pub geometric_normal: Vec3,
+    pub t: Float,
     pub front_face: bool,
-    pub material: Material,
+    pub material: &'a Material,
+    /// Color replacing the one of the material, like the interpolated
+    /// color of the vertices of a triangle.
+    pub color: Option<Color>,
 }
 
-impl HitRecord {
-    pub fn new(p: Vec3, normal: Vec3, t: f32, material: Material, ray: &Ray) -> Self {
+impl<'a> HitRecord<'a> {
+    pub fn new(p: Vec3, normal: Vec3, t: Float, material: &'a Material, ray: &Ray) -> Self {
         let front_face = Vec3::dot(ray.dir, normal) < 0.0;
 
         Self {
             p,
             normal,
+            geometric_normal: normal,
             t,
             front_face,
             material,
+            color: None,
         }
     }
+
+    /// Shade the hit with the `normal` instead of the geometric one,
+    /// turned to the same side of the surface.
+    pub fn with_shading_normal(mut self, normal: Vec3) -> Self {
+        self.normal = match Vec3::dot(normal, self.geometric_normal) < 0. {
+            true => -normal,
+            false => normal,
+        };
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// The material at the hit point, resolved only for the closest hit.
+    pub fn surface(&self) -> Material {
+        let mut material = *self.material;
+        if let Some(color) = self.color {
+            material.solid_color = color;
+        }
+
+        material
+    }
 }
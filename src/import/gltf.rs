@@ -0,0 +1,194 @@
+use std::{collections::BTreeSet, path::Path};
+
+use ::gltf::{buffer, camera::Projection, mesh::Mode, Gltf, Node};
+
+use super::{framing_camera, ImportedScene};
+use crate::{
+    aabb::Aabb,
+    description::{CameraDescription, RenderSettings},
+    material::Material,
+    matrix::Matrix,
+    mesh::Mesh,
+    object::Object,
+    triangle::Triangle,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Load the default scene of a glTF 2.0 file, either `.gltf` or `.glb`,
+/// with its triangle meshes, the first perspective camera and the
+/// metallic-roughness materials approximated by the ones available here.
+///
+/// The emissive materials become area lights, the textures are replaced
+/// by their factors and the punctual lights are skipped with a warning.
+/// Scenes without a camera are framed from the front.
+pub fn load(path: &Path) -> Result<ImportedScene, String> {
+    let invalid = |e: ::gltf::Error| format!("invalid scene `{}`: {}", path.display(), e);
+    let Gltf { document, blob } = Gltf::open(path).map_err(invalid)?;
+    let buffers = ::gltf::import_buffers(&document, path.parent(), blob).map_err(invalid)?;
+
+    let scene = document
+        .default_scene()
+        .or(document.scenes().next())
+        .ok_or(format!(
+            "invalid scene `{}`: there are no scenes",
+            path.display()
+        ))?;
+
+    let mut importer = Importer {
+        buffers: &buffers,
+        camera: None,
+        bounds: None,
+        objects: vec![],
+        warnings: BTreeSet::new(),
+    };
+    for node in scene.nodes() {
+        importer.node(node, Matrix::IDENTITY)?;
+    }
+
+    for warning in &importer.warnings {
+        tracing::warn!("`{}`: {}", path.display(), warning);
+    }
+
+    Ok(importer.finish())
+}
+
+struct Importer<'a> {
+    buffers: &'a [buffer::Data],
+    /// Camera to world transform, vertical field of view in degrees and
+    /// aspect ratio of the first camera.
+    camera: Option<(Matrix, Float, Option<Float>)>,
+    bounds: Option<Aabb>,
+    objects: Vec<Object>,
+    warnings: BTreeSet<String>,
+}
+
+impl Importer<'_> {
+    fn warn(&mut self, warning: &str) {
+        self.warnings.insert(warning.into());
+    }
+
+    /// Import the `node` and its children, placed by the `parent` transform.
+    fn node(&mut self, node: Node, parent: Matrix) -> Result<(), String> {
+        let columns = node.transform().matrix();
+        let columns = columns.as_flattened().iter().map(|x| *x as Float);
+        let transform = parent * Matrix::from_columns(&columns.collect::<Vec<_>>())?;
+
+        if let Some(camera) = node.camera() {
+            match camera.projection() {
+                Projection::Perspective(p) if self.camera.is_none() => {
+                    let fov = p.yfov().to_degrees() as Float;
+                    let aspect_ratio = p.aspect_ratio().map(|r| r as Float);
+                    self.camera = Some((transform, fov, aspect_ratio));
+                }
+                Projection::Orthographic(_) => {
+                    self.warn("orthographic cameras are not supported");
+                }
+                Projection::Perspective(_) => {}
+            }
+        }
+
+        if node.light().is_some() {
+            self.warn("punctual lights are not supported, only emissive materials are");
+        }
+
+        for primitive in node.mesh().iter().flat_map(|m| m.primitives()) {
+            if primitive.mode() != Mode::Triangles {
+                self.warn("only primitives made of triangles are supported");
+                continue;
+            }
+
+            let buffers = self.buffers;
+            let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let vertices = positions
+                .map(|p| transform.point(Vec3::from_f32(p)))
+                .collect::<Vec<_>>();
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                None => (0..vertices.len()).collect::<Vec<_>>(),
+            };
+            if indices.iter().any(|i| *i >= vertices.len()) {
+                return Err("a primitive has indices out of range".into());
+            }
+            if indices.len() < 3 {
+                continue;
+            }
+
+            let material = self.material(primitive.material());
+            let mesh = Mesh::new(
+                indices
+                    .chunks_exact(3)
+                    .map(|t| {
+                        Triangle::new([vertices[t[0]], vertices[t[1]], vertices[t[2]]], material)
+                    })
+                    .collect(),
+            );
+
+            let bounds = mesh.bounds();
+            self.bounds = Some(match self.bounds {
+                Some(b) => Aabb::from_points([b.min, b.max, bounds.min, bounds.max]),
+                None => bounds,
+            });
+            self.objects.push(Object::Mesh(mesh));
+        }
+
+        for child in node.children() {
+            self.node(child, transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn material(&mut self, material: ::gltf::Material) -> Material {
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base_color = Color::from_f32([r, g, b]);
+        let fuzz = Some(pbr.roughness_factor() as Float).filter(|r| *r > 0.);
+
+        if pbr.base_color_texture().is_some() || pbr.metallic_roughness_texture().is_some() {
+            self.warn("textures are not supported, their factors are used instead");
+        }
+
+        let strength = material.emissive_strength().unwrap_or(1.) as Float;
+        let emission = strength * Vec3::from_f32(material.emissive_factor());
+        let transmission = material
+            .transmission()
+            .map_or(0., |t| t.transmission_factor());
+
+        if emission != Vec3::ZERO {
+            Material::emissive(Color { rgb: emission })
+        } else if transmission > 0.5 {
+            Material::dielectric(material.ior().unwrap_or(1.5) as Float, fuzz)
+        } else if pbr.metallic_factor() > 0.5 {
+            Material::metal(base_color, fuzz)
+        } else {
+            Material::lambertian(base_color, None)
+        }
+    }
+
+    fn finish(self) -> ImportedScene {
+        let camera = match self.camera {
+            // The cameras of glTF look towards -z with the y axis up.
+            Some((transform, fov, _)) => CameraDescription {
+                look_from: Some(transform.point(Vec3::ZERO).into()),
+                look_at: Some(transform.point(Vec3(0., 0., -1.)).into()),
+                up: Some(transform.vector(Vec3(0., 1., 0.)).into()),
+                fov: Some(fov),
+                defocus_angle: Some(0.),
+                focus_dist: None,
+            },
+            None => framing_camera(self.bounds.unwrap_or(Aabb::new(Vec3::ZERO, Vec3::ZERO))),
+        };
+
+        ImportedScene {
+            camera,
+            render: RenderSettings {
+                aspect_ratio: self.camera.and_then(|(_, _, aspect)| aspect),
+                ..Default::default()
+            },
+            objects: self.objects,
+        }
+    }
+}
@@ -0,0 +1,611 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node};
+
+use super::{obj, ply::PlyMesh, vertical_fov, FovAxis, ImportedScene};
+use crate::{
+    description::{CameraDescription, RenderSettings},
+    material::Material,
+    matrix::Matrix,
+    mesh::{Mesh, CREASE_ANGLE},
+    object::Object,
+    sphere::Sphere,
+    triangle::Triangle,
+    vec3::{consts::PI, Color, Float, Vec3},
+};
+
+/// Segments of the polygons approximating the disks.
+const DISK_SEGMENTS: usize = 32;
+
+/// Load a scene in the XML format of Mitsuba 0.6 or 3, keeping the
+/// perspective sensor, the spheres, the simple shapes and the OBJ or PLY meshes,
+/// the BSDFs approximated by the materials available here and the area
+/// emitters.
+///
+/// Like for PBRT, what can't be rendered is skipped with a warning.
+pub fn load(path: &Path) -> Result<ImportedScene, String> {
+    let mut importer = Importer {
+        dir: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        ..Default::default()
+    };
+    importer
+        .file(path)
+        .map_err(|e| format!("invalid scene `{}`: {}", path.display(), e))?;
+
+    for warning in &importer.warnings {
+        tracing::warn!("`{}`: {}", path.display(), warning);
+    }
+
+    Ok(importer.finish())
+}
+
+/// Numbers separated by commas or spaces.
+fn numbers(s: &str) -> Result<Vec<Float>, String> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().map_err(|_| format!("`{}` is not a number", n)))
+        .collect()
+}
+
+/// Refraction index of the named materials of Mitsuba.
+fn named_ior(name: &str) -> Option<Float> {
+    match name {
+        "vacuum" => Some(1.),
+        "air" => Some(1.000277),
+        "water" => Some(1.333),
+        "acrylic glass" | "polypropylene" => Some(1.49),
+        "fused quartz" => Some(1.458),
+        "bk7" => Some(1.5046),
+        "sapphire" => Some(1.77),
+        "diamond" => Some(2.419),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Importer {
+    /// Directory of the scene, the files are relative to it.
+    dir: PathBuf,
+    /// Values of the `$name` parameters.
+    defaults: HashMap<String, String>,
+    bsdfs: HashMap<String, Option<Material>>,
+    camera_to_world: Option<Matrix>,
+    fov: Option<(Float, FovAxis)>,
+    lens: Option<(Float, Float)>,
+    resolution: Option<(u32, u32)>,
+    samples: Option<u32>,
+    max_depth: Option<u32>,
+    /// The sensor of Mitsuba has the x axis pointing left, mirroring
+    /// cameras are converted by mirroring the z axis of the world.
+    mirror: bool,
+    objects: Vec<Object>,
+    warnings: BTreeSet<String>,
+}
+
+impl Importer {
+    fn file(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+        let doc = Document::parse(&text).map_err(|e| e.to_string())?;
+
+        let scene = doc.root_element();
+        if scene.tag_name().name() != "scene" {
+            return Err("the root element must be a `scene`".into());
+        }
+
+        // The sensor is needed first, to know if the world is mirrored.
+        let (sensors, elements): (Vec<_>, Vec<_>) = scene
+            .children()
+            .filter(Node::is_element)
+            .partition(|n| n.tag_name().name() == "sensor");
+
+        for node in elements.iter().filter(|n| n.tag_name().name() == "default") {
+            self.element(*node)
+                .map_err(|e| format!("line {}: {}", doc.text_pos_at(node.range().start).row, e))?;
+        }
+        for node in sensors.iter().chain(&elements) {
+            self.element(*node)
+                .map_err(|e| format!("line {}: {}", doc.text_pos_at(node.range().start).row, e))?;
+        }
+
+        Ok(())
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.warnings.insert(warning);
+    }
+
+    /// Attribute `name` of the `node`, with the `$name` parameters replaced.
+    fn attr(&self, node: Node, name: &str) -> Option<String> {
+        let value = node.attribute(name)?;
+
+        Some(match value.strip_prefix('$') {
+            Some(param) => self.defaults.get(param).cloned().unwrap_or(value.into()),
+            None => value.into(),
+        })
+    }
+
+    /// Child property of the `node` called with one of the `names`, the
+    /// ones of Mitsuba 3 and the camel case ones of Mitsuba 0.6.
+    fn property<'a, 'i>(node: Node<'a, 'i>, names: &[&str]) -> Option<Node<'a, 'i>> {
+        node.children()
+            .find(|c| c.attribute("name").is_some_and(|n| names.contains(&n)))
+    }
+
+    fn float(&self, node: Node, names: &[&str]) -> Result<Option<Float>, String> {
+        let Some(property) = Self::property(node, names) else {
+            return Ok(None);
+        };
+        let value = self
+            .attr(property, "value")
+            .ok_or(format!("`{}` has no value", names[0]))?;
+
+        value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("`{}` is not a number", value))
+    }
+
+    fn string(&self, node: Node, names: &[&str]) -> Option<String> {
+        self.attr(Self::property(node, names)?, "value")
+    }
+
+    /// Vector given either as `value` or as `x`, `y` and `z` attributes,
+    /// the missing coordinates are `default`.
+    fn vector(&self, node: Node, default: Float) -> Result<Vec3, String> {
+        if let Some(value) = self.attr(node, "value") {
+            return match numbers(&value)?[..] {
+                [v] => Ok(Vec3(v, v, v)),
+                [x, y, z] => Ok(Vec3(x, y, z)),
+                _ => Err(format!("`{}` is not a vector", value)),
+            };
+        }
+
+        let coordinate = |axis| {
+            self.attr(node, axis).map_or(Ok(default), |v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a number", v))
+            })
+        };
+
+        Ok(Vec3(coordinate("x")?, coordinate("y")?, coordinate("z")?))
+    }
+
+    /// Color of the first of the properties `names` which is given.
+    fn color(&mut self, node: Node, names: &[&str]) -> Result<Option<Color>, String> {
+        let Some(property) = Self::property(node, names) else {
+            return Ok(None);
+        };
+        let value = self.attr(property, "value").unwrap_or_default();
+
+        match (property.tag_name().name(), numbers(&value)) {
+            ("rgb" | "srgb" | "spectrum" | "float", Ok(v)) if v.len() == 1 => {
+                Ok(Some(Color::new((v[0], v[0], v[0]))))
+            }
+            ("rgb" | "srgb", Ok(v)) if v.len() == 3 => Ok(Some(Color::new((v[0], v[1], v[2])))),
+            ("blackbody", _) => {
+                self.warn("blackbody emission is approximated by white".into());
+                Ok(Some(Color::WHITE))
+            }
+            (kind, _) => {
+                self.warn(format!(
+                    "`{}` properties are not supported, the default color is used",
+                    kind
+                ));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Transform of a `transform` element, whose children are applied in order.
+    fn transform(&self, node: Node) -> Result<Matrix, String> {
+        let mut m = Matrix::IDENTITY;
+
+        for op in node.children().filter(Node::is_element) {
+            let step = match op.tag_name().name() {
+                "translate" => Matrix::translate(self.vector(op, 0.)?),
+                "scale" => Matrix::scale(self.vector(op, 1.)?),
+                "rotate" => {
+                    let angle = self
+                        .attr(op, "angle")
+                        .and_then(|a| a.trim().parse().ok())
+                        .ok_or("`rotate` needs an angle")?;
+                    Matrix::rotate(angle, self.vector(op, 0.)?)
+                }
+                "matrix" => Matrix::from_rows(&numbers(
+                    &self.attr(op, "value").ok_or("`matrix` needs a value")?,
+                )?)?,
+                "lookat" | "lookAt" => {
+                    let vector = |name| {
+                        self.attr(op, name)
+                            .ok_or(format!("`lookat` needs the `{}`", name))
+                            .and_then(|v| match numbers(&v)?[..] {
+                                [x, y, z] => Ok(Vec3(x, y, z)),
+                                _ => Err(format!("`{}` is not a vector", v)),
+                            })
+                    };
+                    let origin = vector("origin")?;
+                    let dir = Vec3::unit(vector("target")? - origin);
+                    let up = self
+                        .attr(op, "up")
+                        .map_or(Ok(Vec3(0., 1., 0.)), |_| vector("up"))?;
+
+                    let left = Vec3::unit(Vec3::cross(up, dir));
+                    if left.0.is_nan() {
+                        return Err(
+                            "the up vector of `lookat` is parallel to the view direction".into(),
+                        );
+                    }
+
+                    Matrix::from_frame(left, Vec3::cross(dir, left), dir, origin)
+                }
+                op => return Err(format!("unknown transform `{}`", op)),
+            };
+
+            m = step * m;
+        }
+
+        Ok(m)
+    }
+
+    fn element(&mut self, node: Node) -> Result<(), String> {
+        match node.tag_name().name() {
+            "default" => {
+                let (Some(name), Some(value)) = (node.attribute("name"), node.attribute("value"))
+                else {
+                    return Err("`default` needs a name and a value".into());
+                };
+                // The parameters given earlier take precedence.
+                self.defaults
+                    .entry(name.into())
+                    .or_insert_with(|| value.into());
+            }
+            "include" => {
+                let filename = self
+                    .attr(node, "filename")
+                    .ok_or("`include` needs a filename")?;
+                let path = self.dir.join(filename);
+                self.file(&path)
+                    .map_err(|e| format!("in `{}`, {}", path.display(), e))?;
+            }
+            "integrator" => {
+                self.max_depth = self
+                    .float(node, &["max_depth", "maxDepth"])?
+                    .filter(|n| *n >= 1.)
+                    .map(|n| n as u32);
+            }
+            "sensor" => self.sensor(node)?,
+            "bsdf" => {
+                let id = self.attr(node, "id").ok_or("top level bsdfs need an id")?;
+                let material = self.bsdf(node)?;
+                self.bsdfs.insert(id, material);
+            }
+            "shape" => self.shape(node)?,
+            "emitter" => {
+                let kind = self.attr(node, "type").unwrap_or_default();
+                self.warn(match kind.as_str() {
+                    "constant" | "envmap" | "sky" | "sunsky" => {
+                        format!("the `{}` emitter is replaced by the sky", kind)
+                    }
+                    kind => format!(
+                        "the `{}` emitter is not supported, only area emitters are",
+                        kind
+                    ),
+                });
+            }
+            "texture" | "medium" | "phase" => {
+                let kind = node.tag_name().name();
+                self.warn(format!("top level `{}` elements are not supported", kind));
+            }
+            element => return Err(format!("unknown element `{}`", element)),
+        }
+
+        Ok(())
+    }
+
+    fn sensor(&mut self, node: Node) -> Result<(), String> {
+        let kind = self.attr(node, "type").unwrap_or_default();
+        if kind != "perspective" && kind != "thinlens" {
+            self.warn(format!(
+                "the `{}` sensor is replaced by a perspective one",
+                kind
+            ));
+        }
+
+        let to_world = match Self::property(node, &["to_world", "toWorld"]) {
+            Some(transform) => self.transform(transform)?,
+            None => Matrix::IDENTITY,
+        };
+        self.camera_to_world = Some(to_world);
+        self.mirror = to_world.determinant() < 0.;
+
+        let axis = match self.string(node, &["fov_axis", "fovAxis"]).as_deref() {
+            None | Some("x") => FovAxis::Horizontal,
+            Some("y") => FovAxis::Vertical,
+            Some("diagonal") => FovAxis::Diagonal,
+            Some("smaller") => FovAxis::Smaller,
+            Some("larger") => FovAxis::Larger,
+            Some(axis) => return Err(format!("unknown fov axis `{}`", axis)),
+        };
+        // A 50mm lens on a 35mm film, the default of Mitsuba.
+        let fov = self.float(node, &["fov"])?.unwrap_or(39.6);
+        self.fov = Some((fov, axis));
+
+        let radius = self.float(node, &["aperture_radius", "apertureRadius"])?;
+        let focus = self.float(node, &["focus_distance", "focusDistance"])?;
+        self.lens = radius
+            .filter(|r| *r > 0.)
+            .map(|r| (r, focus.unwrap_or(10.)));
+
+        for child in node.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                "film" => {
+                    let width = self.float(child, &["width"])?.unwrap_or(768.);
+                    let height = self.float(child, &["height"])?.unwrap_or(576.);
+                    if !(width >= 1. && height >= 1.) {
+                        return Err("the film size must be positive".into());
+                    }
+                    self.resolution = Some((width as u32, height as u32));
+                }
+                "sampler" => {
+                    self.samples = self
+                        .float(child, &["sample_count", "sampleCount"])?
+                        .filter(|n| *n >= 1.)
+                        .map(|n| n as u32);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Approximate the BSDF of the `node`, `None` for invisible ones.
+    fn bsdf(&mut self, node: Node) -> Result<Option<Material>, String> {
+        let kind = self.attr(node, "type").unwrap_or_default();
+        let gray = |v| Color::new((v, v, v));
+        let rough = kind.starts_with("rough");
+        let fuzz = match self.float(node, &["alpha"])? {
+            Some(alpha) if alpha > 0. => Some(alpha.min(1.)),
+            Some(_) => None,
+            None => rough.then_some(0.1),
+        };
+
+        let material = match kind.as_str() {
+            "null" => return Ok(None),
+            // Wrappers of another BSDF.
+            "twosided" | "mask" | "bumpmap" | "normalmap" => {
+                return match node.children().find(|c| c.has_tag_name("bsdf")) {
+                    Some(inner) => self.bsdf(inner),
+                    None => match node.children().find(|c| c.has_tag_name("ref")) {
+                        Some(r) => self.reference(r),
+                        None => Err(format!("the `{}` bsdf has no nested bsdf", kind)),
+                    },
+                };
+            }
+            "diffuse" | "roughdiffuse" => {
+                let color = self.color(node, &["reflectance"])?;
+                Material::lambertian(color.unwrap_or(gray(0.5)), None)
+            }
+            "plastic" | "roughplastic" => {
+                let color = self.color(node, &["diffuse_reflectance", "diffuseReflectance"])?;
+                Material::lambertian(color.unwrap_or(gray(0.5)), None)
+            }
+            "conductor" | "roughconductor" => {
+                let color = self.color(node, &["specular_reflectance", "specularReflectance"])?;
+                Material::metal(color.unwrap_or(Color::WHITE), fuzz)
+            }
+            "dielectric" | "roughdielectric" | "thindielectric" => {
+                let ior = |importer: &Self, names: &[&str], default| -> Result<Float, String> {
+                    match importer.string(node, names) {
+                        Some(value) => named_ior(&value)
+                            .or(value.trim().parse().ok())
+                            .ok_or(format!("unknown refraction index `{}`", value)),
+                        None => Ok(default),
+                    }
+                };
+                let interior = ior(self, &["int_ior", "intIOR"], 1.5046)?;
+                let exterior = ior(self, &["ext_ior", "extIOR"], 1.000277)?;
+                Material::dielectric(interior / exterior, fuzz)
+            }
+            "principled" => {
+                let color = self.color(node, &["base_color"])?.unwrap_or(gray(0.5));
+                match self.float(node, &["metallic"])?.unwrap_or(0.) > 0.5 {
+                    true => {
+                        let roughness = self.float(node, &["roughness"])?.unwrap_or(0.5);
+                        Material::metal(color, Some(roughness).filter(|r| *r > 0.))
+                    }
+                    false => Material::lambertian(color, None),
+                }
+            }
+            kind => {
+                self.warn(format!("the `{}` bsdf is approximated as diffuse", kind));
+                Material::lambertian(gray(0.5), None)
+            }
+        };
+
+        Ok(Some(material))
+    }
+
+    fn reference(&mut self, node: Node) -> Result<Option<Material>, String> {
+        let id = self.attr(node, "id").ok_or("`ref` needs an id")?;
+
+        self.bsdfs
+            .get(&id)
+            .copied()
+            .ok_or(format!("the bsdf `{}` is not defined", id))
+    }
+
+    fn shape(&mut self, node: Node) -> Result<(), String> {
+        let kind = self.attr(node, "type").unwrap_or_default();
+
+        // Mitsuba uses a diffuse BSDF by default.
+        let mut material = Some(Material::lambertian(Color::new((0.5, 0.5, 0.5)), None));
+        let mut to_world = Matrix::IDENTITY;
+
+        for child in node.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                "bsdf" => material = self.bsdf(child)?,
+                "ref" => material = self.reference(child)?,
+                "transform" => to_world = self.transform(child)?,
+                _ => {}
+            }
+        }
+
+        // Area emitters replace the BSDF, wherever they are declared.
+        if let Some(emitter) = node.children().find(|c| c.has_tag_name("emitter")) {
+            match self.attr(emitter, "type").unwrap_or_default().as_str() {
+                "area" => {
+                    let radiance = self.color(emitter, &["radiance"])?;
+                    material = Some(Material::emissive(radiance.unwrap_or(Color::WHITE)));
+                }
+                kind => self.warn(format!(
+                    "the `{}` emitter of a shape is not supported",
+                    kind
+                )),
+            }
+        }
+
+        let Some(material) = material else {
+            return Ok(());
+        };
+
+        let triangles = match kind.as_str() {
+            "sphere" => {
+                let center = match Self::property(node, &["center"]) {
+                    Some(center) => self.vector(center, 0.)?,
+                    None => Vec3::ZERO,
+                };
+                let radius = self.float(node, &["radius"])?.unwrap_or(1.);
+                // Non-uniform scales are approximated by their mean.
+                let scale = to_world.determinant().abs().cbrt();
+
+                self.objects.push(Object::Sphere(Sphere::new(
+                    self.to_world(to_world.point(center)),
+                    scale * radius,
+                    material,
+                )));
+                return Ok(());
+            }
+            "rectangle" => {
+                let [a, b, c, d] =
+                    [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)].map(|(x, y)| Vec3(x, y, 0.));
+                vec![[a, b, c], [a, c, d]]
+            }
+            "cube" => {
+                let corner = |i: usize| {
+                    let side = |bit| if i & bit != 0 { 1. } else { -1. };
+                    Vec3(side(1), side(2), side(4))
+                };
+                // Two triangles for each face, as the corners of quads.
+                [
+                    [0, 2, 3, 1],
+                    [4, 5, 7, 6],
+                    [0, 1, 5, 4],
+                    [2, 6, 7, 3],
+                    [0, 4, 6, 2],
+                    [1, 3, 7, 5],
+                ]
+                .iter()
+                .flat_map(|q| {
+                    let [a, b, c, d] = q.map(corner);
+                    [[a, b, c], [a, c, d]]
+                })
+                .collect()
+            }
+            "disk" => {
+                let rim = |i: usize| {
+                    let angle = 2. * PI * i as Float / DISK_SEGMENTS as Float;
+                    Vec3(angle.cos(), angle.sin(), 0.)
+                };
+                (0..DISK_SEGMENTS)
+                    .map(|i| [Vec3::ZERO, rim(i), rim(i + 1)])
+                    .collect()
+            }
+            "obj" => {
+                let filename = self
+                    .string(node, &["filename"])
+                    .ok_or("the obj shape needs a filename")?;
+                obj::load(&self.dir.join(filename))?
+            }
+            "ply" => {
+                let filename = self
+                    .string(node, &["filename"])
+                    .ok_or("the ply shape needs a filename")?;
+                let mut mesh = PlyMesh::read(&self.dir.join(filename))?;
+                mesh.transform(
+                    |p| self.to_world(to_world.point(p)),
+                    |n| self.to_world(to_world.normal(n)),
+                );
+                let mut mesh = Mesh::new(mesh.triangles(material));
+                mesh.smooth(CREASE_ANGLE);
+                self.objects.push(Object::Mesh(mesh));
+                return Ok(());
+            }
+            kind => {
+                self.warn(format!("the `{}` shape is not supported", kind));
+                return Ok(());
+            }
+        };
+
+        // Like Mitsuba, the meshes without normals are smoothed.
+        let mut mesh = Mesh::new(
+            triangles
+                .into_iter()
+                .map(|t| Triangle::new(t.map(|p| self.to_world(to_world.point(p))), material))
+                .collect(),
+        );
+        mesh.smooth(CREASE_ANGLE);
+        self.objects.push(Object::Mesh(mesh));
+
+        Ok(())
+    }
+
+    /// Convert the point `p` of the world of Mitsuba.
+    fn to_world(&self, p: Vec3) -> Vec3 {
+        match self.mirror {
+            true => Vec3(p.0, p.1, -p.2),
+            false => p,
+        }
+    }
+
+    fn finish(self) -> ImportedScene {
+        let camera_to_world = self.camera_to_world.unwrap_or(Matrix::IDENTITY);
+        let look_from = self.to_world(camera_to_world.point(Vec3::ZERO));
+        let look_at = self.to_world(camera_to_world.point(Vec3(0., 0., 1.)));
+        let up = self.to_world(camera_to_world.vector(Vec3(0., 1., 0.)));
+
+        let (width, height) = self.resolution.unwrap_or((768, 576));
+        let aspect_ratio = width as Float / height as Float;
+        let (fov, axis) = self.fov.unwrap_or((39.6, FovAxis::Horizontal));
+        let (defocus_angle, focus_dist) = match self.lens {
+            Some((radius, dist)) => (2. * (radius / dist).atan().to_degrees(), Some(dist)),
+            None => (0., None),
+        };
+
+        ImportedScene {
+            camera: CameraDescription {
+                look_from: Some(look_from.into()),
+                look_at: Some(look_at.into()),
+                up: Some(up.into()),
+                fov: Some(vertical_fov(fov, axis, aspect_ratio)),
+                defocus_angle: Some(defocus_angle),
+                focus_dist,
+            },
+            render: RenderSettings {
+                width: Some(width),
+                aspect_ratio: Some(aspect_ratio),
+                samples: self.samples,
+                max_depth: self.max_depth,
+                ..Default::default()
+            },
+            objects: self.objects,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    aabb::Aabb,
+    description::{CameraDescription, RenderSettings},
+    object::Object,
+    vec3::{Float, Vec3},
+};
+
+pub mod gltf;
+pub mod mitsuba;
+pub mod obj;
+pub mod pbrt;
+pub mod ply;
+pub mod stl;
+
+/// Scene imported from the file format of another renderer, keeping
+/// only what can be rendered here.
+#[derive(Debug, Clone)]
+pub struct ImportedScene {
+    pub camera: CameraDescription,
+    pub render: RenderSettings,
+    pub objects: Vec<Object>,
+}
+
+/// Image axis along which a field of view is measured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FovAxis {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Smaller,
+    Larger,
+}
+
+/// Vertical field of view in degrees of an image with the `aspect_ratio`,
+/// from the `fov` measured along the `axis`.
+pub fn vertical_fov(fov: Float, axis: FovAxis, aspect_ratio: Float) -> Float {
+    let half = (fov / 2.).to_radians().tan();
+    let half = match axis {
+        FovAxis::Horizontal => half / aspect_ratio,
+        FovAxis::Vertical => half,
+        FovAxis::Diagonal => half / Float::sqrt(1. + aspect_ratio * aspect_ratio),
+        FovAxis::Smaller if aspect_ratio < 1. => half / aspect_ratio,
+        FovAxis::Larger if aspect_ratio > 1. => half / aspect_ratio,
+        FovAxis::Smaller | FovAxis::Larger => half,
+    };
+
+    2. * half.atan().to_degrees()
+}
+
+/// Field of view of the cameras framing the scenes without one.
+const FRAMING_FOV: Float = 40.;
+
+/// Camera looking at the `bounds` from the front and slightly above,
+/// far enough to see all of them, for the formats without cameras.
+pub fn framing_camera(bounds: Aabb) -> CameraDescription {
+    let radius = 0.5 * Vec3::norm(bounds.max - bounds.min);
+    let distance = match radius > 0. {
+        true => radius / (FRAMING_FOV / 2.).to_radians().sin(),
+        false => 1.,
+    };
+    let center = bounds.center();
+    let direction = Vec3::unit(Vec3(0.5, 0.5, 1.));
+
+    CameraDescription {
+        look_from: Some((center + distance * direction).into()),
+        look_at: Some(center.into()),
+        up: Some((0., 1., 0.)),
+        fov: Some(FRAMING_FOV),
+        defocus_angle: Some(0.),
+        focus_dist: None,
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+
+use crate::vec3::{Float, Vec3};
+
+/// Load the triangles of the Wavefront OBJ file at `path`, only the
+/// positions of the vertices are read and the faces with more vertices
+/// are split in fans of triangles.
+pub fn load(path: &Path) -> Result<Vec<[Vec3; 3]>, String> {
+    let (vertices, faces) = load_polygons(path)?;
+
+    Ok(faces
+        .iter()
+        .flat_map(|face| {
+            (1..face.len() - 1).map(|j| [face[0], face[j], face[j + 1]].map(|i| vertices[i]))
+        })
+        .collect())
+}
+
+/// Load the vertices of the Wavefront OBJ file at `path` and its faces,
+/// as the indices of their vertices, keeping the faces with more than
+/// three vertices whole.
+pub fn load_polygons(path: &Path) -> Result<(Vec<Vec3>, Vec<Vec<usize>>), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+    let invalid =
+        |line: usize, e: &str| format!("invalid mesh `{}`: line {}: {}", path.display(), line, e);
+
+    let mut vertices = vec![];
+    let mut faces = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("v") => {
+                let p = words
+                    .take(3)
+                    .map(|w| w.parse::<Float>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid(i + 1, "invalid vertex"))?;
+                match p[..] {
+                    [x, y, z] => vertices.push(Vec3(x, y, z)),
+                    _ => return Err(invalid(i + 1, "a vertex needs 3 coordinates")),
+                }
+            }
+            Some("f") => {
+                // Indices start from 1, negative ones count from the last vertex.
+                let face = words
+                    .map(|w| match w.split('/').next().unwrap().parse::<isize>() {
+                        Ok(n) if n > 0 && n as usize <= vertices.len() => Ok(n as usize - 1),
+                        Ok(n) if n < 0 && n.unsigned_abs() <= vertices.len() => {
+                            Ok(vertices.len() - n.unsigned_abs())
+                        }
+                        _ => Err(invalid(i + 1, "invalid face index")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if face.len() < 3 {
+                    return Err(invalid(i + 1, "a face needs at least 3 vertices"));
+                }
+
+                faces.push(face);
+            }
+            _ => {}
+        }
+    }
+
+    match faces.is_empty() {
+        true => Err(format!(
+            "invalid mesh `{}`: there are no faces",
+            path.display()
+        )),
+        false => Ok((vertices, faces)),
+    }
+}
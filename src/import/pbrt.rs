@@ -0,0 +1,666 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use super::{ply::PlyMesh, vertical_fov, FovAxis, ImportedScene};
+use crate::{
+    description::{CameraDescription, RenderSettings},
+    material::Material,
+    matrix::Matrix,
+    mesh::Mesh,
+    object::Object,
+    sphere::Sphere,
+    triangle::Triangle,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Reflectance of the default conductor of pbrt, copper.
+const COPPER: Color = Color {
+    rgb: Vec3(0.95, 0.64, 0.54),
+};
+
+/// Load a scene in the pbrt-v3 or pbrt-v4 format, keeping the perspective
+/// camera, the spheres and the triangle or PLY meshes, the materials approximated
+/// by the ones available here and the area lights.
+///
+/// What can't be rendered is skipped with a warning, the other lights
+/// included, since there is no light sampling the scenes are lit by the
+/// sky and by the emissive shapes.
+pub fn load(path: &Path) -> Result<ImportedScene, String> {
+    let mut parser = Parser::default();
+    parser
+        .parse_file(path)
+        .map_err(|e| format!("invalid scene `{}`: {}", path.display(), e))?;
+
+    for warning in &parser.warnings {
+        tracing::warn!("`{}`: {}", path.display(), warning);
+    }
+
+    Ok(parser.finish())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Directive(String),
+    Str(String),
+    Num(Float),
+    Bool(bool),
+    Open,
+    Close,
+}
+
+/// Split the `text` in tokens, each one with its line.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            '#' => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '[' => Token::Open,
+            ']' => Token::Close,
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\n') | None => {
+                            return Err(format!("line {}: unterminated string", line))
+                        }
+                        Some(c) => s.push(c),
+                    }
+                }
+                Token::Str(s)
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !matches!(c, '[' | ']' | '"' | '#'))
+                {
+                    word.push(c);
+                }
+
+                match word.as_str() {
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    _ if c.is_ascii_alphabetic() => Token::Directive(word),
+                    _ => Token::Num(
+                        word.parse()
+                            .map_err(|_| format!("line {}: `{}` is not a number", line, word))?,
+                    ),
+                }
+            }
+        };
+
+        tokens.push((token, line));
+    }
+
+    Ok(tokens)
+}
+
+/// Numbers of the arguments of a transform, which may be in brackets.
+fn numbers(args: &[Token]) -> Result<Vec<Float>, String> {
+    args.iter()
+        .filter(|t| !matches!(t, Token::Open | Token::Close))
+        .map(|t| match t {
+            Token::Num(n) => Ok(*n),
+            t => Err(format!("expected a number, found {:?}", t)),
+        })
+        .collect()
+}
+
+/// Value of a parameter, declared as `"type name"` and followed by
+/// either a single value or a list in brackets.
+#[derive(Debug, Clone)]
+struct Param {
+    kind: String,
+    values: Vec<Token>,
+}
+
+#[derive(Debug, Default)]
+struct Params(HashMap<String, Param>);
+
+impl Params {
+    fn kind(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|p| p.kind.as_str())
+    }
+
+    fn floats(&self, name: &str) -> Vec<Float> {
+        self.0.get(name).map_or(vec![], |p| {
+            p.values
+                .iter()
+                .filter_map(|v| match v {
+                    Token::Num(n) => Some(*n),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    fn float(&self, name: &str) -> Option<Float> {
+        self.floats(name).first().copied()
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        self.0.get(name)?.values.iter().find_map(|v| match v {
+            Token::Str(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Split the arguments of a directive in its first `positional` strings
+/// and the parameter list.
+fn split_params(args: &[Token], positional: usize) -> Result<(Vec<String>, Params), String> {
+    let mut args = args.iter();
+    let mut names = vec![];
+
+    for _ in 0..positional {
+        match args.next() {
+            Some(Token::Str(s)) => names.push(s.clone()),
+            _ => return Err(format!("expected {} quoted names", positional)),
+        }
+    }
+
+    let mut params = Params::default();
+    while let Some(decl) = args.next() {
+        let Token::Str(decl) = decl else {
+            return Err(format!("expected a parameter, found {:?}", decl));
+        };
+        let (kind, name) = decl
+            .split_once(char::is_whitespace)
+            .ok_or(format!("parameter `{}` has no type", decl))?;
+
+        let values = match args.next() {
+            Some(Token::Open) => args
+                .by_ref()
+                .take_while(|t| **t != Token::Close)
+                .cloned()
+                .collect(),
+            Some(value @ (Token::Num(_) | Token::Str(_) | Token::Bool(_))) => vec![value.clone()],
+            _ => return Err(format!("parameter `{}` has no value", decl)),
+        };
+
+        params.0.insert(
+            name.trim().to_string(),
+            Param {
+                kind: kind.to_string(),
+                values,
+            },
+        );
+    }
+
+    Ok((names, params))
+}
+
+/// Transform from the world to the space of a camera at `eye`
+/// looking at `target`, with the y axis up and z forward.
+fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Result<Matrix, String> {
+    let dir = Vec3::unit(target - eye);
+    let right = Vec3::cross(Vec3::unit(up), dir);
+    if Vec3::norm(right).is_nan() || Vec3::norm(right) == 0. {
+        return Err("the up vector of `LookAt` is parallel to the view direction".into());
+    }
+    let right = Vec3::unit(right);
+    let up = Vec3::cross(dir, right);
+
+    Matrix::from_frame(right, up, dir, eye)
+        .inverse()
+        .ok_or("`LookAt` has no view direction".into())
+}
+
+/// State saved by `AttributeBegin` and restored by `AttributeEnd`.
+#[derive(Debug, Clone)]
+struct Attributes {
+    /// Current transformation matrix.
+    ctm: Matrix,
+    /// `None` for the `interface` material, whose shapes are invisible.
+    material: Option<Material>,
+    /// Radiance emitted by the shapes, when they are area lights.
+    area_light: Option<Color>,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            ctm: Matrix::IDENTITY,
+            material: Some(Material::lambertian(Color::new((0.5, 0.5, 0.5)), None)),
+            area_light: None,
+        }
+    }
+}
+
+/// Kind of block opened in the attribute stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Block {
+    Attribute,
+    /// Only saves the transform, from pbrt-v3.
+    Transform,
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    attributes: Attributes,
+    stack: Vec<(Block, Attributes)>,
+    named_materials: HashMap<String, Option<Material>>,
+    camera_to_world: Option<Matrix>,
+    /// Camera parameters: field of view of the shorter image axis,
+    /// lens radius and focal distance.
+    fov: Option<Float>,
+    lens: Option<(Float, Float)>,
+    resolution: Option<(u32, u32)>,
+    samples: Option<u32>,
+    max_depth: Option<u32>,
+    /// pbrt uses a left-handed coordinate system, which is converted by
+    /// mirroring the z axis unless the camera already mirrors the image.
+    mirror: bool,
+    /// Within an object definition, whose instances are not supported.
+    in_object: bool,
+    objects: Vec<Object>,
+    warnings: BTreeSet<String>,
+}
+
+impl Parser {
+    fn parse_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+        let tokens = tokenize(&text)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let mut start = 0;
+        while start < tokens.len() {
+            let (Token::Directive(directive), line) = &tokens[start] else {
+                return Err(format!(
+                    "line {}: expected a directive, found {:?}",
+                    tokens[start].1, tokens[start].0
+                ));
+            };
+            let end = tokens[start + 1..]
+                .iter()
+                .position(|(t, _)| matches!(t, Token::Directive(_)))
+                .map_or(tokens.len(), |i| start + 1 + i);
+            let args = tokens[start + 1..end]
+                .iter()
+                .map(|(t, _)| t.clone())
+                .collect::<Vec<_>>();
+
+            self.directive(directive, &args, dir)
+                .map_err(|e| format!("line {}: {}", line, e))?;
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.warnings.insert(warning);
+    }
+
+    fn directive(&mut self, directive: &str, args: &[Token], dir: &Path) -> Result<(), String> {
+        match directive {
+            "Include" | "Import" => {
+                let (names, _) = split_params(args, 1)?;
+                let path = dir.join(&names[0]);
+                self.parse_file(&path)
+                    .map_err(|e| format!("in `{}`, {}", path.display(), e))?;
+            }
+            "Identity" => self.attributes.ctm = Matrix::IDENTITY,
+            "Translate" => match numbers(args)?[..] {
+                [x, y, z] => self.transform(Matrix::translate(Vec3(x, y, z))),
+                _ => return Err("`Translate` needs 3 numbers".into()),
+            },
+            "Scale" => match numbers(args)?[..] {
+                [x, y, z] => self.transform(Matrix::scale(Vec3(x, y, z))),
+                _ => return Err("`Scale` needs 3 numbers".into()),
+            },
+            "Rotate" => match numbers(args)?[..] {
+                [angle, x, y, z] => self.transform(Matrix::rotate(angle, Vec3(x, y, z))),
+                _ => return Err("`Rotate` needs 4 numbers".into()),
+            },
+            "LookAt" => match numbers(args)?[..] {
+                [ex, ey, ez, lx, ly, lz, ux, uy, uz] => self.transform(look_at(
+                    Vec3(ex, ey, ez),
+                    Vec3(lx, ly, lz),
+                    Vec3(ux, uy, uz),
+                )?),
+                _ => return Err("`LookAt` needs 9 numbers".into()),
+            },
+            "Transform" => self.attributes.ctm = Matrix::from_columns(&numbers(args)?)?,
+            "ConcatTransform" => self.transform(Matrix::from_columns(&numbers(args)?)?),
+            "Camera" => {
+                let (names, params) = split_params(args, 1)?;
+                if names[0] != "perspective" {
+                    self.warn(format!(
+                        "the `{}` camera is replaced by a perspective one",
+                        names[0]
+                    ));
+                }
+
+                let ctm = self.attributes.ctm;
+                self.camera_to_world =
+                    Some(ctm.inverse().ok_or("the camera transform is singular")?);
+                self.mirror = ctm.determinant() > 0.;
+                self.fov = params.float("fov");
+                self.lens = params
+                    .float("lensradius")
+                    .filter(|r| *r > 0.)
+                    .map(|r| (r, params.float("focaldistance").unwrap_or(1e6)));
+            }
+            "Film" => {
+                let (_, params) = split_params(args, 1)?;
+                let x = params.float("xresolution").unwrap_or(1280.);
+                let y = params.float("yresolution").unwrap_or(720.);
+                if !(x >= 1. && y >= 1.) {
+                    return Err("the film resolution must be positive".into());
+                }
+                self.resolution = Some((x as u32, y as u32));
+            }
+            "Sampler" => {
+                let (_, params) = split_params(args, 1)?;
+                self.samples = params
+                    .float("pixelsamples")
+                    .filter(|n| *n >= 1.)
+                    .map(|n| n as u32);
+            }
+            "Integrator" => {
+                let (_, params) = split_params(args, 1)?;
+                self.max_depth = params
+                    .float("maxdepth")
+                    .filter(|n| *n >= 1.)
+                    .map(|n| n as u32);
+            }
+            "WorldBegin" => {
+                if self.camera_to_world.is_none() {
+                    self.camera_to_world = self.attributes.ctm.inverse();
+                    self.mirror = self.attributes.ctm.determinant() > 0.;
+                }
+                self.attributes.ctm = Matrix::IDENTITY;
+            }
+            "AttributeBegin" => self.stack.push((Block::Attribute, self.attributes.clone())),
+            "TransformBegin" => self.stack.push((Block::Transform, self.attributes.clone())),
+            "AttributeEnd" | "TransformEnd" => {
+                let (block, attributes) = self
+                    .stack
+                    .pop()
+                    .ok_or(format!("`{}` without a matching begin", directive))?;
+
+                match (block, directive) {
+                    (Block::Attribute, "AttributeEnd") => self.attributes = attributes,
+                    (Block::Transform, "TransformEnd") => self.attributes.ctm = attributes.ctm,
+                    _ => return Err(format!("`{}` closes a different block", directive)),
+                }
+            }
+            "Material" => {
+                let (names, params) = split_params(args, 1)?;
+                self.attributes.material = self.material(&names[0], &params);
+            }
+            "MakeNamedMaterial" => {
+                let (names, params) = split_params(args, 1)?;
+                let kind = params.string("type").unwrap_or("diffuse").to_string();
+                let material = self.material(&kind, &params);
+                self.named_materials.insert(names[0].clone(), material);
+            }
+            "NamedMaterial" => {
+                let (names, _) = split_params(args, 1)?;
+                self.attributes.material = *self
+                    .named_materials
+                    .get(&names[0])
+                    .ok_or(format!("the material `{}` is not defined", names[0]))?;
+            }
+            "AreaLightSource" => {
+                let (names, params) = split_params(args, 1)?;
+                if names[0] != "diffuse" {
+                    self.warn(format!("the `{}` area light is not supported", names[0]));
+                    return Ok(());
+                }
+
+                let radiance = self.color(&params, &["L"]).unwrap_or(Color::WHITE);
+                let scale = params.float("scale").unwrap_or(1.);
+                self.attributes.area_light = Some(Color {
+                    rgb: scale * radiance.rgb,
+                });
+            }
+            "LightSource" => {
+                let (names, _) = split_params(args, 1)?;
+                self.warn(match names[0].as_str() {
+                    "infinite" => "infinite lights are replaced by the sky".into(),
+                    light => format!(
+                        "the `{}` light is not supported, only area lights are",
+                        light
+                    ),
+                });
+            }
+            "Shape" => {
+                let (names, params) = split_params(args, 1)?;
+                self.shape(&names[0], &params, dir)?;
+            }
+            "ObjectBegin" => {
+                self.in_object = true;
+                self.warn("object instances are not supported, their shapes are skipped".into());
+            }
+            "ObjectEnd" => self.in_object = false,
+            "ObjectInstance" => {}
+            "Texture" | "MakeNamedMedium" | "MediumInterface" | "CoordinateSystem"
+            | "CoordSysTransform" | "Attribute" => {
+                self.warn(format!("the `{}` directive is not supported", directive));
+            }
+            // Settings without an effect on the render here.
+            "Option" | "ColorSpace" | "PixelFilter" | "Accelerator" | "ReverseOrientation"
+            | "TransformTimes" | "ActiveTransform" | "WorldEnd" => {}
+            _ => return Err(format!("unknown directive `{}`", directive)),
+        }
+
+        Ok(())
+    }
+
+    fn transform(&mut self, m: Matrix) {
+        self.attributes.ctm = self.attributes.ctm * m;
+    }
+
+    /// Convert the point `p` of the world of pbrt.
+    fn to_world(&self, p: Vec3) -> Vec3 {
+        match self.mirror {
+            true => Vec3(p.0, p.1, -p.2),
+            false => p,
+        }
+    }
+
+    /// Color of the first of the parameters `names` which is given.
+    fn color(&mut self, params: &Params, names: &[&str]) -> Option<Color> {
+        let name = names.iter().find(|n| params.kind(n).is_some())?;
+        let values = params.floats(name);
+
+        match (params.kind(name)?, &values[..]) {
+            ("rgb" | "color", [r, g, b]) => Some(Color::new((*r, *g, *b))),
+            ("float", [v]) => Some(Color::new((*v, *v, *v))),
+            ("blackbody", _) => {
+                self.warn("blackbody emission is approximated by white".into());
+                Some(Color::WHITE)
+            }
+            (kind, _) => {
+                self.warn(format!(
+                    "`{} {}` parameters are not supported, the default color is used",
+                    kind, name
+                ));
+                None
+            }
+        }
+    }
+
+    /// Approximate the material of pbrt `kind`, `None` for invisible ones.
+    fn material(&mut self, kind: &str, params: &Params) -> Option<Material> {
+        let gray = |v| Color::new((v, v, v));
+        let fuzz = params
+            .float("roughness")
+            .or(params.float("uroughness"))
+            .filter(|r| *r > 0.)
+            .map(|r| r.min(1.));
+
+        let material = match kind {
+            "" | "none" | "interface" => return None,
+            "diffuse" | "matte" | "coateddiffuse" | "plastic" | "substrate" | "uber" => {
+                let color = self.color(params, &["reflectance", "Kd"]);
+                Material::lambertian(color.unwrap_or(gray(0.5)), None)
+            }
+            "conductor" | "metal" | "coatedconductor" => {
+                let color = self.color(params, &["reflectance"]);
+                Material::metal(color.unwrap_or(COPPER), fuzz)
+            }
+            "mirror" => {
+                let color = self.color(params, &["Kr"]);
+                Material::metal(color.unwrap_or(gray(0.9)), None)
+            }
+            "dielectric" | "thindielectric" | "glass" => {
+                let eta = params.float("eta").or(params.float("index"));
+                Material::dielectric(eta.unwrap_or(1.5), fuzz)
+            }
+            kind => {
+                self.warn(format!(
+                    "the `{}` material is approximated as diffuse",
+                    kind
+                ));
+                Material::lambertian(gray(0.5), None)
+            }
+        };
+
+        Some(material)
+    }
+
+    fn shape(&mut self, kind: &str, params: &Params, dir: &Path) -> Result<(), String> {
+        if self.in_object {
+            return Ok(());
+        }
+        let material = match self.attributes.area_light {
+            Some(radiance) => Material::emissive(radiance),
+            None => match self.attributes.material {
+                Some(material) => material,
+                None => return Ok(()),
+            },
+        };
+        let ctm = self.attributes.ctm;
+
+        match kind {
+            "sphere" => {
+                let radius = params.float("radius").unwrap_or(1.);
+                let center = self.to_world(ctm.point(Vec3::ZERO));
+                // Non-uniform scales are approximated by their mean.
+                let scale = ctm.determinant().abs().cbrt();
+
+                self.objects.push(Object::Sphere(Sphere::new(
+                    center,
+                    scale * radius,
+                    material,
+                )));
+            }
+            "trianglemesh" | "bilinearmesh" => {
+                let points = params.floats("P");
+                if points.is_empty() || !points.len().is_multiple_of(3) {
+                    return Err(format!("the {} has invalid points `P`", kind));
+                }
+                let vertices = points
+                    .chunks(3)
+                    .map(|p| self.to_world(ctm.point(Vec3(p[0], p[1], p[2]))))
+                    .collect::<Vec<_>>();
+
+                let mut indices = params
+                    .floats("indices")
+                    .into_iter()
+                    .map(|i| i as usize)
+                    .collect::<Vec<_>>();
+                // A single triangle or patch doesn't need the indices.
+                if indices.is_empty() {
+                    indices = (0..vertices.len()).collect();
+                }
+                if indices.iter().any(|i| *i >= vertices.len()) {
+                    return Err(format!("the {} has indices out of range", kind));
+                }
+
+                let triangles = match kind {
+                    "trianglemesh" if indices.len().is_multiple_of(3) => indices
+                        .chunks(3)
+                        .map(|t| [vertices[t[0]], vertices[t[1]], vertices[t[2]]])
+                        .collect::<Vec<_>>(),
+                    // The vertices of each patch are in the order
+                    // (0,0), (1,0), (0,1), (1,1).
+                    "bilinearmesh" if indices.len().is_multiple_of(4) => indices
+                        .chunks(4)
+                        .flat_map(|q| {
+                            let [a, b, c, d] = [q[0], q[1], q[2], q[3]].map(|i| vertices[i]);
+                            [[a, b, d], [a, d, c]]
+                        })
+                        .collect(),
+                    _ => return Err(format!("the {} has an invalid number of indices", kind)),
+                };
+
+                self.objects.push(Object::Mesh(Mesh::new(
+                    triangles
+                        .into_iter()
+                        .map(|vertices| Triangle::new(vertices, material))
+                        .collect(),
+                )));
+            }
+            "plymesh" => {
+                let filename = params
+                    .string("filename")
+                    .ok_or("the plymesh needs a filename")?;
+                let mut mesh = PlyMesh::read(&dir.join(filename))?;
+                mesh.transform(
+                    |p| self.to_world(ctm.point(p)),
+                    |n| self.to_world(ctm.normal(n)),
+                );
+
+                self.objects
+                    .push(Object::Mesh(Mesh::new(mesh.triangles(material))));
+            }
+            kind => self.warn(format!("the `{}` shape is not supported", kind)),
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> ImportedScene {
+        let camera_to_world = self.camera_to_world.unwrap_or(Matrix::IDENTITY);
+        let look_from = self.to_world(camera_to_world.point(Vec3::ZERO));
+        let look_at = self.to_world(camera_to_world.point(Vec3(0., 0., 1.)));
+        let up = self.to_world(camera_to_world.vector(Vec3(0., 1., 0.)));
+
+        let (width, height) = self.resolution.unwrap_or((1280, 720));
+        let aspect_ratio = width as Float / height as Float;
+        // The field of view of pbrt is the one of the shorter axis.
+        let v_fov = vertical_fov(self.fov.unwrap_or(90.), FovAxis::Smaller, aspect_ratio);
+        let (defocus_angle, focus_dist) = match self.lens {
+            Some((radius, dist)) => (2. * (radius / dist).atan().to_degrees(), Some(dist)),
+            None => (0., None),
+        };
+
+        ImportedScene {
+            camera: CameraDescription {
+                look_from: Some(look_from.into()),
+                look_at: Some(look_at.into()),
+                up: Some(up.into()),
+                fov: Some(v_fov),
+                defocus_angle: Some(defocus_angle),
+                focus_dist,
+            },
+            render: RenderSettings {
+                width: Some(width),
+                aspect_ratio: Some(aspect_ratio),
+                samples: self.samples,
+                max_depth: self.max_depth,
+                ..Default::default()
+            },
+            objects: self.objects,
+        }
+    }
+}
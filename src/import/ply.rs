@@ -0,0 +1,422 @@
+use std::{fs, path::Path, str::SplitAsciiWhitespace};
+
+use super::{framing_camera, ImportedScene};
+use crate::{
+    aabb::Aabb,
+    description::{RenderSettings, CHUNK},
+    material::Material,
+    mesh::{Mesh, CREASE_ANGLE},
+    object::Object,
+    pointcloud::{Point, PointCloud, Splat},
+    triangle::Triangle,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Load the mesh of a PLY file, framed by the camera. The colors of the
+/// vertices, when given, replace the color of the material. The files
+/// without faces are point clouds, drawn as discs sized after the
+/// spacing of their points.
+pub fn load(path: &Path) -> Result<ImportedScene, String> {
+    let mesh = PlyMesh::read_points(path)?;
+    let material = Material::lambertian(Color::new((0.7, 0.7, 0.7)), None);
+
+    let (bounds, objects) = match mesh.faces.is_empty() {
+        true => {
+            let bounds = Aabb::from_points(mesh.vertices.iter().copied());
+            // The points of a scanned surface are about the diagonal
+            // over their square root apart, the discs overlap a little
+            // to cover the gaps of the random spacing.
+            let spacing =
+                Vec3::norm(bounds.max - bounds.min) / (mesh.vertices.len() as Float).sqrt();
+            let cloud = PointCloud::new(mesh.points(), spacing, Splat::Disc, material);
+
+            (
+                cloud.bounds(),
+                cloud
+                    .split(CHUNK)
+                    .into_iter()
+                    .map(Object::PointCloud)
+                    .collect(),
+            )
+        }
+        false => {
+            let mut mesh = Mesh::new(mesh.triangles(material));
+            mesh.smooth(CREASE_ANGLE);
+            (mesh.bounds(), vec![Object::Mesh(mesh)])
+        }
+    };
+
+    Ok(ImportedScene {
+        camera: framing_camera(bounds),
+        render: RenderSettings::default(),
+        objects,
+    })
+}
+
+/// Triangles of a PLY file with the optional attributes of the vertices.
+#[derive(Debug, Clone)]
+pub struct PlyMesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub colors: Option<Vec<Color>>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Self::I8),
+            "uchar" | "uint8" => Some(Self::U8),
+            "short" | "int16" => Some(Self::I16),
+            "ushort" | "uint16" => Some(Self::U16),
+            "int" | "int32" => Some(Self::I32),
+            "uint" | "uint32" => Some(Self::U32),
+            "float" | "float32" => Some(Self::F32),
+            "double" | "float64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Value of full intensity of the colors stored with this type.
+    fn color_scale(self) -> f64 {
+        match self {
+            Self::U8 | Self::I8 => 255.,
+            Self::U16 | Self::I16 => 65535.,
+            _ => 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PropertyKind {
+    Scalar(Scalar),
+    /// Type of the length and of the items.
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Body of the file, after the header.
+enum Body<'a> {
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary { data: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        let truncated = || "the file is truncated".to_string();
+
+        match self {
+            Self::Ascii(words) => {
+                let word = words.next().ok_or_else(truncated)?;
+                word.parse()
+                    .map_err(|_| format!("`{}` is not a number", word))
+            }
+            Self::Binary { data, big_endian } => {
+                if data.len() < scalar.size() {
+                    return Err(truncated());
+                }
+                let (bytes, rest) = data.split_at(scalar.size());
+                *data = rest;
+
+                let mut b = [0u8; 8];
+                b[..bytes.len()].copy_from_slice(bytes);
+                if *big_endian {
+                    b[..bytes.len()].reverse();
+                }
+
+                Ok(match scalar {
+                    Scalar::I8 => b[0] as i8 as f64,
+                    Scalar::U8 => b[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+}
+
+/// Names of the attributes of the vertices which are read: the position,
+/// the normal and the color.
+const VERTEX_PROPERTIES: [&[&str]; 9] = [
+    &["x"],
+    &["y"],
+    &["z"],
+    &["nx"],
+    &["ny"],
+    &["nz"],
+    &["red", "r", "diffuse_red"],
+    &["green", "g", "diffuse_green"],
+    &["blue", "b", "diffuse_blue"],
+];
+
+impl PlyMesh {
+    /// Read the ASCII or binary PLY file at `path`, the faces with more
+    /// vertices are split in fans of triangles.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let mesh = Self::read_points(path)?;
+
+        match mesh.faces.is_empty() {
+            true => Err(format!(
+                "invalid mesh `{}`: there are no faces",
+                path.display()
+            )),
+            false => Ok(mesh),
+        }
+    }
+
+    /// Read the PLY file at `path` like [`Self::read`], the faces being
+    /// optional like in the files of point clouds.
+    pub fn read_points(path: &Path) -> Result<Self, String> {
+        let data =
+            fs::read(path).map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+
+        Self::parse(&data).map_err(|e| format!("invalid mesh `{}`: {}", path.display(), e))
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        const END: &[u8] = b"end_header";
+        let end = data
+            .windows(END.len())
+            .position(|w| w == END)
+            .ok_or("the header is not terminated")?;
+        let header = std::str::from_utf8(&data[..end]).map_err(|_| "the header is not text")?;
+        // The body starts on the line after the end of the header.
+        let body_start = data[end..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(data.len(), |i| end + i + 1);
+
+        let mut lines = header.lines().map(str::trim);
+        if lines.next() != Some("ply") {
+            return Err("not a PLY file".into());
+        }
+
+        let mut format = None;
+        let mut elements: Vec<Element> = vec![];
+        for line in lines {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let scalar =
+                |name: &str| Scalar::from_name(name).ok_or(format!("unknown type `{}`", name));
+
+            match words[..] {
+                ["format", f, _] => format = Some(f.to_string()),
+                ["element", name, count] => elements.push(Element {
+                    name: name.into(),
+                    count: count
+                        .parse()
+                        .map_err(|_| format!("invalid count `{}`", count))?,
+                    properties: vec![],
+                }),
+                ["property", "list", count, item, name] => elements
+                    .last_mut()
+                    .ok_or("property outside of an element")?
+                    .properties
+                    .push(Property {
+                        name: name.into(),
+                        kind: PropertyKind::List(scalar(count)?, scalar(item)?),
+                    }),
+                ["property", ty, name] => elements
+                    .last_mut()
+                    .ok_or("property outside of an element")?
+                    .properties
+                    .push(Property {
+                        name: name.into(),
+                        kind: PropertyKind::Scalar(scalar(ty)?),
+                    }),
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => return Err(format!("invalid header line `{}`", line)),
+            }
+        }
+
+        let body = &data[body_start..];
+        let mut body = match format.as_deref() {
+            Some("ascii") => Body::Ascii(
+                std::str::from_utf8(body)
+                    .map_err(|_| "the body is not text")?
+                    .split_ascii_whitespace(),
+            ),
+            Some("binary_little_endian") => Body::Binary {
+                data: body,
+                big_endian: false,
+            },
+            Some("binary_big_endian") => Body::Binary {
+                data: body,
+                big_endian: true,
+            },
+            _ => return Err("unknown format".into()),
+        };
+
+        let mut mesh = Self {
+            vertices: vec![],
+            normals: None,
+            colors: None,
+            faces: vec![],
+        };
+        let mut normals = vec![];
+        let mut colors = vec![];
+
+        for element in &elements {
+            // Slot of each property in the attributes of the vertices.
+            let slots = element
+                .properties
+                .iter()
+                .map(|p| {
+                    VERTEX_PROPERTIES
+                        .iter()
+                        .position(|names| names.contains(&p.name.as_str()))
+                })
+                .collect::<Vec<_>>();
+            let has = |slot: usize| slots.contains(&Some(slot));
+            let color_scale = element
+                .properties
+                .iter()
+                .zip(&slots)
+                .find_map(|(p, slot)| match (slot, p.kind) {
+                    (Some(6), PropertyKind::Scalar(s)) => Some(s.color_scale()),
+                    _ => None,
+                })
+                .unwrap_or(1.);
+
+            for _ in 0..element.count {
+                let mut attributes = [0.; 9];
+
+                for (property, slot) in element.properties.iter().zip(&slots) {
+                    match property.kind {
+                        PropertyKind::Scalar(scalar) => {
+                            let value = body.read(scalar)?;
+                            if let Some(slot) = slot {
+                                attributes[*slot] = value;
+                            }
+                        }
+                        PropertyKind::List(count, item) => {
+                            let count = body.read(count)? as usize;
+                            let items = (0..count)
+                                .map(|_| body.read(item).map(|i| i as usize))
+                                .collect::<Result<Vec<_>, _>>()?;
+
+                            let indices = ["vertex_indices", "vertex_index"];
+                            if element.name == "face" && indices.contains(&property.name.as_str()) {
+                                if items.len() < 3 {
+                                    return Err("a face needs at least 3 vertices".into());
+                                }
+                                mesh.faces.extend(
+                                    (1..items.len() - 1)
+                                        .map(|j| [items[0], items[j], items[j + 1]]),
+                                );
+                            }
+                        }
+                    }
+                }
+
+                if element.name == "vertex" {
+                    let a = attributes.map(|a| a as Float);
+                    mesh.vertices.push(Vec3(a[0], a[1], a[2]));
+                    if has(3) {
+                        normals.push(Vec3::unit(Vec3(a[3], a[4], a[5])));
+                    }
+                    if has(6) {
+                        // The colors are gamma encoded.
+                        let linear = |c: f64| (c / color_scale).powi(2) as Float;
+                        colors.push(Color::new((
+                            linear(attributes[6]),
+                            linear(attributes[7]),
+                            linear(attributes[8]),
+                        )));
+                    }
+                }
+            }
+        }
+
+        if mesh.vertices.is_empty() {
+            return Err("there are no vertices".into());
+        }
+        if mesh
+            .faces
+            .iter()
+            .flatten()
+            .any(|i| *i >= mesh.vertices.len())
+        {
+            return Err("a face has indices out of range".into());
+        }
+        mesh.normals = Some(normals).filter(|n| !n.is_empty());
+        mesh.colors = Some(colors).filter(|c| !c.is_empty());
+
+        Ok(mesh)
+    }
+
+    /// Transform the vertices with `point` and the normals with `normal`.
+    pub fn transform(&mut self, point: impl Fn(Vec3) -> Vec3, normal: impl Fn(Vec3) -> Vec3) {
+        for vertex in &mut self.vertices {
+            *vertex = point(*vertex);
+        }
+        for n in self.normals.iter_mut().flatten() {
+            *n = Vec3::unit(normal(*n));
+        }
+    }
+
+    /// Points of the vertices, with their colors when given.
+    pub fn points(&self) -> Vec<Point> {
+        self.vertices
+            .iter()
+            .enumerate()
+            .map(|(i, position)| Point {
+                position: *position,
+                color: self.colors.as_ref().map(|colors| colors[i]),
+            })
+            .collect()
+    }
+
+    /// Triangles of the faces made of the `material`.
+    pub fn triangles(&self, material: Material) -> Vec<Triangle> {
+        self.faces
+            .iter()
+            .map(|face| {
+                let mut triangle = Triangle::new(face.map(|i| self.vertices[i]), material);
+                if let Some(normals) = &self.normals {
+                    triangle = triangle.with_normals(face.map(|i| normals[i]));
+                }
+                if let Some(colors) = &self.colors {
+                    triangle = triangle.with_colors(face.map(|i| colors[i]));
+                }
+                triangle
+            })
+            .collect()
+    }
+}
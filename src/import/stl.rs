@@ -0,0 +1,58 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use super::{framing_camera, ImportedScene};
+use crate::{
+    description::RenderSettings,
+    material::Material,
+    mesh::{Mesh, CREASE_ANGLE},
+    object::Object,
+    triangle::Triangle,
+    vec3::{Color, Vec3},
+};
+
+/// Load the mesh of a binary or ASCII STL file, framed by the camera.
+///
+/// STL models are usually meant for 3D printing, with the z axis up,
+/// so they are turned to have the y axis up. The winding of the faces
+/// follows their normals, when given.
+pub fn load(path: &Path) -> Result<ImportedScene, String> {
+    let invalid = |e: std::io::Error| format!("invalid mesh `{}`: {}", path.display(), e);
+    let file =
+        File::open(path).map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    // Light gray plastic, like the one of the printers.
+    let material = Material::lambertian(Color::new((0.7, 0.7, 0.7)), None);
+    let z_up = |v: stl_io::Vector<f32>| Vec3::from_f32([v.0[0], v.0[2], -v.0[1]]);
+
+    let triangles = stl_io::create_stl_reader(&mut reader)
+        .map_err(invalid)?
+        .map(|t| {
+            let t = t.map_err(invalid)?;
+            let [a, b, c] = t.vertices.map(z_up);
+            let winding = Vec3::cross(b - a, c - a);
+
+            Ok(match Vec3::dot(winding, z_up(t.normal)) < 0. {
+                true => Triangle::new([a, c, b], material),
+                false => Triangle::new([a, b, c], material),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if triangles.is_empty() {
+        return Err(format!(
+            "invalid mesh `{}`: there are no faces",
+            path.display()
+        ));
+    }
+    // The files only have the normals of the faces, which make the
+    // curved surfaces faceted.
+    let mut mesh = Mesh::new(triangles);
+    mesh.smooth(CREASE_ANGLE);
+
+    Ok(ImportedScene {
+        camera: framing_camera(mesh.bounds()),
+        render: RenderSettings::default(),
+        objects: vec![Object::Mesh(mesh)],
+    })
+}
@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::vec3::Float;
+
+/// Closed interval of numbers, like the distances along a ray where
+/// the hits are looked for.
+///
+/// It is empty when `min` is above `max`, as in [`Interval::EMPTY`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interval {
+    pub min: Float,
+    pub max: Float,
+}
+
+impl Interval {
+    pub const EMPTY: Self = Self::new(Float::INFINITY, Float::NEG_INFINITY);
+
+    pub const UNIVERSE: Self = Self::new(Float::NEG_INFINITY, Float::INFINITY);
+
+    pub const fn new(min: Float, max: Float) -> Self {
+        Self { min, max }
+    }
+
+    pub fn size(self) -> Float {
+        self.max - self.min
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.max < self.min
+    }
+
+    /// Whether `x` is inside the interval or on its bounds.
+    pub fn contains(self, x: Float) -> bool {
+        self.min <= x && x <= self.max
+    }
+
+    /// Whether `x` is strictly inside the interval, the hits use it so
+    /// that a hit at the distance of the closest one doesn't replace it.
+    pub fn surrounds(self, x: Float) -> bool {
+        self.min < x && x < self.max
+    }
+
+    /// The nearest value to `x` in the interval, which must not be empty.
+    pub fn clamp(self, x: Float) -> Float {
+        x.max(self.min).min(self.max)
+    }
+
+    /// The interval grown by `delta`, half on each side.
+    pub fn expand(self, delta: Float) -> Self {
+        Self::new(self.min - delta / 2., self.max + delta / 2.)
+    }
+
+    /// The interval with the upper bound lowered to `max`, like the
+    /// distance of the closest hit found so far.
+    pub fn until(self, max: Float) -> Self {
+        Self::new(self.min, max)
+    }
+}
+
+impl From<Range<Float>> for Interval {
+    fn from(range: Range<Float>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
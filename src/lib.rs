@@ -0,0 +1,92 @@
+//! Ray tracer of spheres and triangle meshes, following the series
+//! "Ray Tracing in One Weekend".
+//!
+//! A scene is a [`World`](world::World) of objects seen by a
+//! [`Camera`](camera::Camera), and the [`Tracer`](render::Tracer) renders
+//! the linear color of each pixel:
+//!
+//! ```
+//! use raycasting_rs::{
+//!     camera::Camera,
+//!     material::Material,
+//!     render::Tracer,
+//!     sphere::Sphere,
+//!     vec3::{Color, Vec3},
+//!     world::World,
+//! };
+//!
+//! let world = World::builder()
+//!     .add(Sphere::new(
+//!         Vec3(0., -100.5, -1.),
+//!         100.,
+//!         Material::lambertian(Color::new((0.5, 0.5, 0.5)), None),
+//!     ))
+//!     .add(Sphere::new(Vec3(0., 0., -1.), 0.5, Material::dielectric(1.5, None)))
+//!     .build()
+//!     .unwrap();
+//!
+//! let camera = Camera::new(2., 40, 90., Vec3::ZERO, Vec3(0., 0., -1.), 0., 1.).quiet();
+//! let pixels = Tracer::new(10).render(&camera, &world, 4);
+//!
+//! assert_eq!(pixels.len(), 40 * 20);
+//! ```
+//!
+//! The pixels can then be tone mapped with [`tonemap`] and saved with
+//! [`output`], while the scenes of other programs are loaded by [`import`].
+
+pub mod aabb;
+pub mod affinity;
+pub mod animation;
+pub mod aov;
+pub mod buffer;
+pub mod bvh;
+pub mod camera;
+pub mod colorspace;
+pub mod curve;
+pub mod denoise;
+pub mod description;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod film;
+pub mod firefly;
+pub mod fractal;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hit;
+pub mod import;
+pub mod interval;
+pub mod lod;
+pub mod lsystem;
+pub mod lut;
+mod macros;
+pub mod material;
+pub mod matrix;
+pub mod mesh;
+pub mod metaballs;
+pub mod object;
+pub mod onb;
+pub mod output;
+pub mod pause;
+pub mod plugin;
+pub mod pointcloud;
+pub mod post;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quat;
+pub mod ray;
+pub mod raypath;
+pub mod render;
+pub mod scene;
+pub mod scenes;
+pub mod script;
+pub mod sphere;
+pub mod stats;
+pub mod stereo;
+pub mod subdivision;
+pub mod text;
+pub mod tonemap;
+pub mod triangle;
+pub mod vec3;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod world;
@@ -0,0 +1,78 @@
+//! Level of detail of the meshes, simplified from the point of view of
+//! the camera so that the details smaller than a pixel aren't traced.
+
+use crate::{
+    camera::Camera,
+    object::Object,
+    sphere::Sphere,
+    vec3::{Float, Vec3},
+};
+
+/// What [`simplify`] did to the meshes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LodSummary {
+    /// Meshes replaced by a simpler version.
+    pub simplified: usize,
+    /// Meshes replaced by their bounding sphere.
+    pub replaced: usize,
+    /// Triangles of the meshes before and after.
+    pub triangles_before: usize,
+    pub triangles_after: usize,
+}
+
+/// Replace the meshes of the `objects` by simpler versions, merging the
+/// vertices closer than `tolerance` pixels as seen by the `camera`. The
+/// meshes smaller than that become their bounding sphere, with the
+/// average material of the mesh.
+///
+/// The distance of each mesh is the one of its closest point, so that
+/// the near side of large meshes keeps its details, and the meshes
+/// around the camera are left as they are.
+pub fn simplify(objects: &mut [Object], camera: &Camera, tolerance: Float) -> LodSummary {
+    let eye = camera.settings().look_from;
+    let mut summary = LodSummary::default();
+
+    for object in objects.iter_mut() {
+        let Object::Mesh(mesh) = object else {
+            continue;
+        };
+
+        let bounds = mesh.bounds();
+        let distance = bounds.distance(eye);
+        if distance <= 0. {
+            continue;
+        }
+
+        let cell = tolerance * camera.pixel_size(distance);
+        let size = bounds.max - bounds.min;
+        let before = mesh.triangles().len();
+
+        // A mesh within a single cell collapses whole.
+        let simplified = match size.0.max(size.1).max(size.2) > cell {
+            true => mesh.simplify(cell),
+            false => None,
+        };
+
+        match simplified {
+            Some(simplified) if simplified.triangles().len() < before => {
+                summary.simplified += 1;
+                summary.triangles_before += before;
+                summary.triangles_after += simplified.triangles().len();
+                *mesh = simplified;
+            }
+            Some(_) => {}
+            None => {
+                let sphere = Sphere::new(
+                    bounds.center(),
+                    0.5 * Vec3::norm(size),
+                    mesh.average_material(),
+                );
+                summary.replaced += 1;
+                summary.triangles_before += before;
+                *object = Object::Sphere(sphere);
+            }
+        }
+    }
+
+    summary
+}
@@ -0,0 +1,38 @@
+use std::io;
+
+use tracing::Level;
+use tracing_subscriber::{
+    fmt::{self, format::FmtSpan, time::Uptime},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// Log the events at `level` or above to the standard error, unless the
+/// `RUST_LOG` variable chooses them, with the seconds since the start or
+/// as JSON lines when `json` is set.
+/// The time taken by the scene, the BVH and each pass and frame is
+/// logged when their span closes.
+///
+/// With the `tracy` feature all the spans, down to the shading of each
+/// pixel, are also sent to the Tracy profiler whatever the `level`.
+///
+/// Only the first call has an effect, the following ones happen when
+/// the options are parsed again while watching the scene.
+pub fn init(level: Level, json: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(level.as_str().to_lowercase()));
+    let log = fmt::layer()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr);
+    let log = match json {
+        true => log.json().boxed(),
+        false => log.with_timer(Uptime::default()).with_target(false).boxed(),
+    };
+
+    let registry = tracing_subscriber::registry().with(log.with_filter(filter));
+    #[cfg(feature = "tracy")]
+    let registry = registry.with(tracing_tracy::TracyLayer::default());
+
+    let _ = registry.try_init();
+}
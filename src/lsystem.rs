@@ -0,0 +1,249 @@
+//! Plants grown from the rules of Lindenmayer systems, drawn by a turtle
+//! as branches and leaves.
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    material::Material,
+    mesh::Mesh,
+    object::Object,
+    quat::Quat,
+    sphere::Sphere,
+    triangle::Triangle,
+    vec3::{consts::TAU, Float, Vec3},
+};
+
+/// Sides of the tubes of the branches.
+const SIDES: usize = 8;
+
+/// Rules rewriting each symbol of a string, starting from the `axiom`,
+/// for a number of `iterations`. The expanded string is then drawn by a
+/// turtle reading the symbols as commands:
+///
+/// - `F` moves forward drawing a branch, `f` without drawing.
+/// - `+` and `-` turn left and right by the `angle`, `&` and `^` pitch
+///   down and up, `\` and `/` roll left and right, `|` turns around.
+/// - `[` and `]` save and restore the state of the turtle, to grow a
+///   branch and come back to its start.
+/// - `!` thins the following branches and `L` draws a leaf.
+///
+/// The other symbols only take part in the rewriting.
+#[derive(Debug, Clone)]
+pub struct LSystem {
+    pub axiom: String,
+    /// Replacement of a symbol, one of them picked at random when a
+    /// symbol has several.
+    pub rules: Vec<(char, String)>,
+    pub iterations: u32,
+    /// Angle of the turns in degrees.
+    pub angle: Float,
+}
+
+impl LSystem {
+    /// Tree branching in three at each step, with leaves along the
+    /// branches, after the Figure 1.25 of "The Algorithmic Beauty of
+    /// Plants".
+    pub fn tree() -> Self {
+        Self {
+            axiom: "FFA".into(),
+            rules: vec![
+                ('A', "[&FL!A]/////[&FL!A]///////[&FL!A]".into()),
+                ('F', "S/////F".into()),
+                ('S', "FL".into()),
+            ],
+            iterations: 5,
+            angle: 22.5,
+        }
+    }
+
+    /// Bush of thin stems which keep branching on one side or the
+    /// other, with leaves at their tips.
+    pub fn bush() -> Self {
+        Self {
+            axiom: "X".into(),
+            rules: vec![
+                ('X', "F[+XL][-XL]/F[&XL]".into()),
+                ('X', "F[+XL]F[^XL]\\XL".into()),
+                ('F', "FF".into()),
+            ],
+            iterations: 5,
+            angle: 25.,
+        }
+    }
+
+    /// The axiom with the rules applied to it `iterations` times. The
+    /// random number generator picks between the rules of a symbol.
+    pub fn expand(&self, rng: &mut impl Rng) -> String {
+        let mut text = self.axiom.clone();
+
+        for _ in 0..self.iterations {
+            let mut next = String::with_capacity(2 * text.len());
+
+            for c in text.chars() {
+                let rules = self
+                    .rules
+                    .iter()
+                    .filter(|(symbol, _)| *symbol == c)
+                    .collect::<Vec<_>>();
+
+                match rules.choose(rng) {
+                    Some((_, replacement)) => next.push_str(replacement),
+                    None => next.push(c),
+                }
+            }
+
+            text = next;
+        }
+
+        text
+    }
+}
+
+/// Look of a plant drawn from an [`LSystem`].
+#[derive(Debug, Clone, Copy)]
+pub struct Plant {
+    /// Length of a step of the turtle.
+    pub step: Float,
+    /// Radius of the first branches.
+    pub radius: Float,
+    /// Factor of the radius at each `!`.
+    pub thinning: Float,
+    /// Length of the leaves, which are half as wide.
+    pub leaf_size: Float,
+    /// Random change of the angles in degrees, so that the plants grown
+    /// from the same rules don't all look the same.
+    pub jitter: Float,
+    pub bark: Material,
+    pub leaf: Material,
+}
+
+/// State of the turtle drawing the plant.
+#[derive(Debug, Clone, Copy)]
+struct Turtle {
+    position: Vec3,
+    /// Direction the turtle moves towards.
+    heading: Vec3,
+    /// Left of the turtle, the axis of the pitch.
+    left: Vec3,
+    /// Up of the turtle, the axis of the turns.
+    up: Vec3,
+    radius: Float,
+}
+
+impl Turtle {
+    fn turn(&mut self, angle: Float) {
+        let q = Quat::from_axis_angle(self.up, angle);
+        self.heading = q.rotate(self.heading);
+        self.left = q.rotate(self.left);
+    }
+
+    fn pitch(&mut self, angle: Float) {
+        let q = Quat::from_axis_angle(self.left, angle);
+        self.heading = q.rotate(self.heading);
+        self.up = q.rotate(self.up);
+    }
+
+    fn roll(&mut self, angle: Float) {
+        let q = Quat::from_axis_angle(self.heading, angle);
+        self.left = q.rotate(self.left);
+        self.up = q.rotate(self.up);
+    }
+}
+
+/// Grow the plant of the `system` from the `base`, towards the sky
+/// and turned by `yaw` degrees around it. Each branch is a capsule made
+/// of a tube and a sphere on its joint, and each leaf is a quad.
+pub fn grow(
+    system: &LSystem,
+    plant: &Plant,
+    base: Vec3,
+    yaw: Float,
+    rng: &mut impl Rng,
+) -> Vec<Object> {
+    let mut turtle = Turtle {
+        position: base,
+        heading: Vec3(0., 1., 0.),
+        left: Vec3(-1., 0., 0.),
+        up: Vec3(0., 0., 1.),
+        radius: plant.radius,
+    };
+    turtle.roll(yaw);
+
+    let mut stack = Vec::new();
+    let mut objects = vec![Object::Sphere(Sphere::new(base, plant.radius, plant.bark))];
+    let angle = |sign: Float, rng: &mut _| {
+        sign * system.angle + plant.jitter * (2. * Rng::gen::<Float>(rng) - 1.)
+    };
+
+    for c in system.expand(rng).chars() {
+        match c {
+            'F' => {
+                let end = turtle.position + plant.step * turtle.heading;
+                objects.push(Object::Mesh(tube(&turtle, end, plant.bark)));
+                objects.push(Object::Sphere(Sphere::new(end, turtle.radius, plant.bark)));
+                turtle.position = end;
+            }
+            'f' => turtle.position += plant.step * turtle.heading,
+            '+' => turtle.turn(angle(1., rng)),
+            '-' => turtle.turn(angle(-1., rng)),
+            '&' => turtle.pitch(angle(1., rng)),
+            '^' => turtle.pitch(angle(-1., rng)),
+            '\\' => turtle.roll(angle(1., rng)),
+            '/' => turtle.roll(angle(-1., rng)),
+            '|' => turtle.turn(180.),
+            '[' => stack.push(turtle),
+            ']' => {
+                if let Some(saved) = stack.pop() {
+                    turtle = saved;
+                }
+            }
+            '!' => turtle.radius *= plant.thinning,
+            'L' => objects.push(Object::Mesh(leaf(&turtle, plant))),
+            _ => {}
+        }
+    }
+
+    objects
+}
+
+/// Tube of the radius of the `turtle`, from its position to the `end`.
+fn tube(turtle: &Turtle, end: Vec3, material: Material) -> Mesh {
+    let ring = |i: usize| {
+        let (sin, cos) = (i as Float * TAU / SIDES as Float).sin_cos();
+        cos * turtle.left + sin * turtle.up
+    };
+
+    let triangles = (0..SIDES)
+        .flat_map(|i| {
+            let (a, b) = (ring(i), ring(i + 1));
+            let (r, start) = (turtle.radius, turtle.position);
+
+            [
+                Triangle::new([start + r * a, start + r * b, end + r * b], material)
+                    .with_normals([a, b, b]),
+                Triangle::new([start + r * a, end + r * b, end + r * a], material)
+                    .with_normals([a, b, a]),
+            ]
+        })
+        .collect();
+
+    Mesh::new(triangles)
+}
+
+/// Leaf in front of the `turtle`, along its heading and facing its up.
+fn leaf(turtle: &Turtle, plant: &Plant) -> Mesh {
+    let length = plant.leaf_size * turtle.heading;
+    let width = 0.5 * plant.leaf_size * turtle.left;
+    let corner = turtle.position - 0.5 * width;
+
+    Mesh::new(vec![
+        Triangle::new(
+            [corner, corner + width, corner + width + length],
+            plant.leaf,
+        ),
+        Triangle::new(
+            [corner, corner + width + length, corner + length],
+            plant.leaf,
+        ),
+    ])
+}
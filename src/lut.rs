@@ -0,0 +1,136 @@
+use std::{fs, path::Path};
+
+use crate::vec3::{Color, Float, Vec3};
+
+/// 3D lookup table grading the colors encoded for display, loaded from
+/// the `.cube` files exported by most color grading software.
+#[derive(Debug, Clone)]
+pub struct Lut {
+    /// Number of entries along each axis.
+    size: usize,
+    domain_min: Vec3,
+    domain_max: Vec3,
+    /// Output color of each entry, with red changing the fastest and
+    /// blue the slowest.
+    table: Vec<Color>,
+}
+
+impl Lut {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+
+        Self::parse(&text).map_err(|e| format!("invalid LUT `{}`: {}", path.display(), e))
+    }
+
+    /// Parse the text of a `.cube` file, which lists the size of the
+    /// table, an optional domain of the input and then the entries.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut domain_min = Vec3(0., 0., 0.);
+        let mut domain_max = Vec3(1., 1., 1.);
+        let mut table = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |e: String| format!("line {}: {}", i + 1, e);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap();
+            let numbers = |words: std::str::SplitWhitespace| {
+                words
+                    .map(|w| w.parse::<Float>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| error(e.to_string()))
+            };
+            let triple = |numbers: Vec<Float>| match numbers[..] {
+                [r, g, b] => Ok(Vec3(r, g, b)),
+                _ => Err(error(format!(
+                    "expected 3 numbers, found {}",
+                    numbers.len()
+                ))),
+            };
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err(error("1D LUTs are not supported".into())),
+                "LUT_3D_SIZE" => {
+                    let n = words
+                        .next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|n| (2..=256).contains(n))
+                        .ok_or_else(|| error("the size must be between 2 and 256".into()))?;
+                    size = Some(n);
+                    table.reserve(n * n * n);
+                }
+                "DOMAIN_MIN" => domain_min = triple(numbers(words)?)?,
+                "DOMAIN_MAX" => domain_max = triple(numbers(words)?)?,
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    return Err(error(format!("unknown keyword `{}`", keyword)));
+                }
+                _ => {
+                    let entry = triple(numbers(line.split_whitespace())?)?;
+                    table.push(Color { rgb: entry });
+                }
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        if table.len() != size * size * size {
+            return Err(format!(
+                "expected {} entries for a size of {}, found {}",
+                size * size * size,
+                size,
+                table.len()
+            ));
+        }
+        if domain_min.0 >= domain_max.0
+            || domain_min.1 >= domain_max.1
+            || domain_min.2 >= domain_max.2
+        {
+            return Err("the domain minimum must be below the maximum".into());
+        }
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Grade the color `c`, interpolating the 8 entries around it. The
+    /// colors outside of the domain take the entries on its bounds.
+    pub fn apply(&self, c: Color) -> Color {
+        let n = (self.size - 1) as Float;
+        let coord = |x: Float, min: Float, max: Float| {
+            let x = ((x - min) / (max - min)).clamp(0., 1.) * n;
+            // The last entry is the upper end of the last cell.
+            let i = (x.floor() as usize).min(self.size - 2);
+
+            (i, x - i as Float)
+        };
+
+        let (r, tr) = coord(c.rgb.0, self.domain_min.0, self.domain_max.0);
+        let (g, tg) = coord(c.rgb.1, self.domain_min.1, self.domain_max.1);
+        let (b, tb) = coord(c.rgb.2, self.domain_min.2, self.domain_max.2);
+
+        let at = |dr: usize, dg: usize, db: usize| {
+            self.table[(r + dr) + (g + dg) * self.size + (b + db) * self.size * self.size]
+        };
+
+        let c00 = Color::lerp(at(0, 0, 0), at(1, 0, 0), tr);
+        let c10 = Color::lerp(at(0, 1, 0), at(1, 1, 0), tr);
+        let c01 = Color::lerp(at(0, 0, 1), at(1, 0, 1), tr);
+        let c11 = Color::lerp(at(0, 1, 1), at(1, 1, 1), tr);
+
+        Color::lerp(Color::lerp(c00, c10, tg), Color::lerp(c01, c11, tg), tb)
+    }
+}
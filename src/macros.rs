@@ -0,0 +1,97 @@
+/// Build the objects of a scene from a declarative list, optionally
+/// preceded by the camera which is returned with them:
+///
+/// ```
+/// use raycasting_rs::scene;
+///
+/// let (camera, objects) = scene! {
+///     camera { look_from: (0., 1., 3.), look_at: (0., 0.5, 0.), fov: 40. }
+///     sphere { center: (0., -1000., 0.), radius: 1000., material: lambertian((0.5, 0.5, 0.5)) }
+///     sphere { center: (0., 1., 0.), radius: 1., material: dielectric(1.5) }
+///     sphere { center: (2., 1., 0.), radius: 1., material: metal((0.8, 0.6, 0.2), 0.3) }
+///     triangle {
+///         vertices: [(-2., 0., -1.), (0., 2., -1.), (-2., 2., -1.)],
+///         material: emissive((4., 4., 4.)),
+///     }
+/// };
+///
+/// assert_eq!(camera.fov, Some(40.));
+/// assert_eq!(objects.len(), 4);
+/// ```
+///
+/// The fields of the camera are the ones of a
+/// [`CameraDescription`](crate::description::CameraDescription), the
+/// missing ones are left to the defaults. The materials take their color
+/// or refraction index followed by the optional fuzz, and the numbers
+/// must be written as floats.
+#[macro_export]
+macro_rules! scene {
+    (camera { $($field:ident: $value:expr),* $(,)? } $($objects:tt)*) => {
+        (
+            $crate::description::CameraDescription {
+                $($field: Some($value),)*
+                ..Default::default()
+            },
+            $crate::scene!($($objects)*),
+        )
+    };
+    ($($kind:ident { $($body:tt)* })*) => {
+        vec![$($crate::scene!(@object $kind { $($body)* })),*]
+    };
+
+    (@object sphere {
+        center: $center:expr,
+        radius: $radius:expr,
+        material: $material:ident($($args:tt)*) $(,)?
+    }) => {
+        $crate::object::Object::Sphere($crate::sphere::Sphere::new(
+            $crate::vec3::Vec3::new($center),
+            $radius,
+            $crate::scene!(@material $material($($args)*)),
+        ))
+    };
+    (@object triangle {
+        vertices: [$a:expr, $b:expr, $c:expr $(,)?],
+        material: $material:ident($($args:tt)*) $(,)?
+    }) => {
+        $crate::object::Object::Mesh($crate::mesh::Mesh::new(vec![
+            $crate::triangle::Triangle::new(
+                [
+                    $crate::vec3::Vec3::new($a),
+                    $crate::vec3::Vec3::new($b),
+                    $crate::vec3::Vec3::new($c),
+                ],
+                $crate::scene!(@material $material($($args)*)),
+            ),
+        ]))
+    };
+
+    (@material lambertian($color:expr $(, $fuzz:expr)? $(,)?)) => {
+        $crate::material::Material::lambertian(
+            $crate::vec3::Color::new($color),
+            $crate::scene!(@option $($fuzz)?),
+        )
+    };
+    (@material metal($color:expr $(, $fuzz:expr)? $(,)?)) => {
+        $crate::material::Material::metal(
+            $crate::vec3::Color::new($color),
+            $crate::scene!(@option $($fuzz)?),
+        )
+    };
+    (@material dielectric($refraction_index:expr $(, $fuzz:expr)? $(,)?)) => {
+        $crate::material::Material::dielectric(
+            $refraction_index,
+            $crate::scene!(@option $($fuzz)?),
+        )
+    };
+    (@material emissive($color:expr $(,)?)) => {
+        $crate::material::Material::emissive($crate::vec3::Color::new($color))
+    };
+
+    (@option) => {
+        None
+    };
+    (@option $value:expr) => {
+        Some($value)
+    };
+}
@@ -1,147 +1,910 @@
+use std::fs;
+use std::io;
 use std::iter::Iterator;
-use std::time::Instant;
-
-use hit::Hit;
-use material::Scatter;
-use rand::Rng;
-use sphere::Sphere;
-
-use crate::camera::Camera;
-use crate::material::Material;
-use crate::vec3::{Color, Vec3};
-
-use crate::ray::Ray;
-
-mod camera;
-mod hit;
-mod material;
-mod ray;
-mod sphere;
-mod vec3;
-
-fn ray_color(mut r: Ray, world: &[Sphere], depth: u32) -> Color {
-    let mut attenuation = Color::WHITE;
-
-    for _ in 0..depth {
-        let mut max_t = f32::INFINITY;
-        let mut hit = None;
-
-        // Find the closest hitted object.
-        for s in world {
-            if let Some(s_hit) = s.hit(&r, 0.001..max_t) {
-                max_t = s_hit.t;
-                hit = Some(s_hit)
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use indicatif::HumanCount;
+use serde::Serialize;
+use tracing::{debug, debug_span, error, info, warn};
+
+use raycasting_rs::aov::{Aov, AovPass};
+use raycasting_rs::buffer::ImageBuffer;
+use raycasting_rs::camera::{Camera, Tile};
+use raycasting_rs::description::{CameraDescription, RenderSettings, SceneDescription};
+use raycasting_rs::film::Sample;
+use raycasting_rs::object::Object;
+use raycasting_rs::output::{self, RowWriter};
+use raycasting_rs::pause::Pause;
+use raycasting_rs::render::{self, Tracer};
+use raycasting_rs::stats::{self, RenderStats, BOUNCE_BUCKETS};
+use raycasting_rs::vec3::{Color, Float, Vec3};
+use raycasting_rs::world::{World, WorldFile};
+use raycasting_rs::{lod, post, raypath, stereo, tonemap};
+
+use options::Options;
+use preview::{PreviewBuffer, PreviewMode};
+use tiles::TileStore;
+use video::VideoEncoder;
+
+mod config;
+mod estimate;
+mod furnace;
+mod logging;
+mod options;
+mod preset;
+mod preview;
+#[cfg(feature = "serve")]
+mod serve;
+mod terminal;
+mod tiles;
+mod video;
+
+/// Pause of the renders, switched by `SIGUSR1` or from the preview window.
+static PAUSE: LazyLock<Arc<Pause>> = LazyLock::new(Default::default);
+
+/// Linear color and AOVs of each pixel of a rendered image.
+type Rendered = (ImageBuffer, Vec<Aov>);
+
+/// Render the image seen by the `camera`, returning the linear color
+/// and the AOVs of each pixel. The snapshots of the image are saved
+/// next to the `output`.
+///
+/// When streaming, the rows are instead written to the `output` as
+/// they are completed and nothing is returned, the same happens when
+/// the tiles spilled to disk are assembled at the end.
+fn render(
+    camera: &Camera,
+    world: &World,
+    options: &Options,
+    output: Option<&Path>,
+    samples: u32,
+    depth: u32,
+) -> Result<Option<Rendered>, String> {
+    let (width, height) = (camera.width(), camera.height());
+    let exposure = tonemap::exposure_scale(options.exposure);
+    let preview = (options.preview.is_some() || options.snapshot_interval.is_some())
+        .then(|| PreviewBuffer::new(width, height));
+    let partial_path =
+        output.map(|output| output::with_suffix(output, "partial").with_extension("png"));
+
+    let stream = match options.stream && options.tile_size.is_none() {
+        true => Some(Mutex::new(
+            RowWriter::new(output, options.format, width, height)
+                .map_err(|e| format!("failed to write the image: {}", e))?,
+        )),
+        false => None,
+    };
+    let tiles = match (options.tile_size, output) {
+        (Some(tile_size), Some(output)) => Some(
+            TileStore::new(output, width, height, tile_size)
+                .map_err(|e| format!("failed to create the tiles directory: {}", e))?,
+        ),
+        _ => None,
+    };
+    let stream_error = Mutex::new(None);
+
+    let display = |c: Color| display(exposure * c, options);
+
+    let show_row = |y: u32, row: &[(Color, Aov)]| {
+        let row = row.iter().map(|(c, _)| display(*c));
+
+        if let Some(preview) = &preview {
+            preview.set_row(y, row.clone());
+        }
+
+        if let Some(stream) = &stream {
+            if let Err(e) = stream.lock().unwrap().write_row(y, row.collect()) {
+                stream_error.lock().unwrap().get_or_insert(e);
             }
         }
+    };
+
+    let save_tile = |tile: Tile, pixels: &[(Color, Aov)]| {
+        let pixels = pixels.iter().map(|(c, _)| display(*c)).collect::<Vec<_>>();
 
-        if let Some(hit) = hit {
-            let scatter = hit.material.scatter(r, hit.normal, 1.0, hit.front_face);
+        if let Some(preview) = &preview {
+            for (y, row) in (tile.y..).zip(pixels.chunks_exact(tile.width as usize)) {
+                preview.set_span(tile.x, y, row.iter().copied());
+            }
+        }
 
-            match scatter {
-                Scatter::Absorbed { solid_color } => {
-                    return Color::blend(attenuation, solid_color);
+        if let Err(e) = tiles.as_ref().unwrap().save(tile, &pixels) {
+            stream_error.lock().unwrap().get_or_insert(e);
+        }
+    };
+
+    let done = AtomicBool::new(false);
+    let is_done = || done.load(Ordering::Acquire);
+
+    let colors = thread::scope(|s| {
+        let render = s.spawn(|| {
+            let tracer = Tracer {
+                max_depth: depth,
+                transparent: options.transparent,
+                firefly: options.firefly,
+            };
+            let map_pixel = |r: &[Sample]| tracer.pixel(r, world);
+            let colors = match (options.tile_size, options.stream) {
+                (Some(tile_size), _) => {
+                    camera.ray_tiles(samples, tile_size, map_pixel, save_tile);
+                    None
                 }
-                Scatter::Scattered {
-                    direction,
-                    attenuation: att,
-                } => {
-                    attenuation = Color::blend(att, attenuation);
-                    r = Ray::new(hit.p, direction);
-                    continue;
+                (None, true) => {
+                    camera.ray_stream(samples, map_pixel, show_row);
+                    None
                 }
+                (None, false) => Some(match options.time_limit {
+                    Some(time_limit) => {
+                        render_in_time(camera, samples, time_limit, map_pixel, show_row)
+                    }
+                    None => camera.ray_map(samples, map_pixel, show_row),
+                }),
             };
+            done.store(true, Ordering::Release);
+            colors
+        });
+
+        if let (Some(interval), Some(preview), Some(path)) =
+            (options.snapshot_interval, &preview, &partial_path)
+        {
+            s.spawn(move || preview::save_snapshots(preview, path, interval, is_done));
         }
 
-        let dir = Vec3::unit(r.dir);
-        let a = 0.5 * (dir.y() + 1.0);
+        if let (Some(preview), Some(PreviewMode::Terminal(graphics))) = (&preview, options.preview)
+        {
+            s.spawn(move || terminal::show(preview, graphics, is_done));
+        }
 
-        let final_color = (1.0 - a) * Color::new((1.0, 1.0, 1.0)) + a * Color::new((0.5, 0.7, 1.0));
-        return Color::blend(attenuation, final_color);
+        if let Some(preview) = preview
+            .as_ref()
+            .filter(|_| options.preview == Some(PreviewMode::Window))
+        {
+            if !preview::show(preview, &PAUSE, is_done)? {
+                warn!("Render aborted");
+                process::exit(1);
+            }
+        }
+
+        Ok::<_, String>(render.join().unwrap())
+    })?;
+
+    if let Some(path) = partial_path.filter(|_| options.snapshot_interval.is_some()) {
+        let _ = fs::remove_file(path);
+    }
+
+    if let Some(e) = stream_error.into_inner().unwrap() {
+        return Err(format!("failed to write the image: {}", e));
+    }
+
+    if let Some(stream) = stream {
+        stream
+            .into_inner()
+            .unwrap()
+            .finish()
+            .map_err(|e| format!("failed to write the image: {}", e))?;
+    }
+
+    if let Some(tiles) = tiles {
+        RowWriter::new(output, options.format, width, height)
+            .and_then(|writer| tiles.assemble(writer))
+            .map_err(|e| format!("failed to assemble the tiles: {}", e))?;
+    }
+
+    let Some(colors) = colors else {
+        return Ok(None);
+    };
+
+    let (pixels, aovs): (Vec<_>, Vec<_>) = colors.into_iter().unzip();
+    let mut pixels = ImageBuffer::new(width, height, pixels);
+
+    if let Some(denoiser) = options.denoiser {
+        denoiser.denoise(width, height, &mut pixels, &aovs)?;
     }
+    post::apply(&options.post, &mut pixels);
 
-    Color::BLACK
+    Ok(Some((pixels, aovs)))
 }
 
-fn main() {
-    let aspect_ratio = 19.0 / 9.0 as f32;
-    let width = 800u32;
-    let height = (width as f32 / aspect_ratio) as u32;
+/// Render the image in passes until it has `samples` rays for each
+/// pixel or the `time_limit` is over. Each pass traces as many rays as
+/// the previous ones together, or fewer when the rate of the previous
+/// passes shows that they wouldn't end in time.
+///
+/// The colors are the average of the passes, while the AOVs are the
+/// ones of the pass with the most samples.
+fn render_in_time<F, R>(
+    camera: &Camera,
+    samples: u32,
+    time_limit: Duration,
+    f: F,
+    on_row: R,
+) -> ImageBuffer<(Color, Aov)>
+where
+    F: Fn(&[Sample]) -> (Color, Aov) + Sync,
+    R: Fn(u32, &[(Color, Aov)]) + Sync,
+{
+    let start = Instant::now();
+    let width = camera.width() as usize;
+    let mut sum = vec![Color::BLACK; width * camera.height() as usize];
+    let mut aovs = vec![];
+    let mut aov_samples = 0;
+    let mut done = 0;
 
-    let camera = Camera::new(
-        aspect_ratio,
-        width,
-        20.,
-        Vec3(13., 2., 3.),
-        Vec3(0., 0., 0.),
-        0.6,
-        10.,
-    );
+    while done < samples {
+        let pass = match done {
+            0 => 1,
+            _ => {
+                let per_sample = start.elapsed().as_secs_f32() / done as f32;
+                let left = time_limit.saturating_sub(start.elapsed()).as_secs_f32();
 
-    let ground_material = Material::lambertian(Color::new((0.5, 0.5, 0.5)), None);
-    let mut world = vec![
-        Sphere::new(Vec3::new((0., -1000., 0.)), 1000., ground_material),
-        Sphere::new(Vec3::new((0., 1., 0.)), 1., Material::dielectric(1.5, None)),
-        Sphere::new(
-            Vec3::new((0.0, 2.3, 0.0)),
-            0.3,
-            Material::lambertian(Color::new((0.2, 0.2, 0.8)), None),
-        ),
-        Sphere::new(
-            Vec3::new((4., 1., 0.)),
-            1.,
-            Material::metal(Color::new((0.8, 0.8, 0.8)), None),
-        ),
-        Sphere::new(
-            Vec3::new((-4.0, 1., 0.)),
-            1.,
-            Material::metal(Color::new((0.8, 0.6, 0.2)), Some(0.3)),
+                ((left / per_sample) as u32).min(done).min(samples - done)
+            }
+        };
+        if pass == 0 {
+            break;
+        }
+
+        // The rows shown are the average of all the passes so far.
+        let total = (done + pass) as Float;
+        let show_row = |y: u32, row: &[(Color, Aov)]| {
+            let start = y as usize * width;
+            let row = row
+                .iter()
+                .zip(&sum[start..])
+                .map(|((c, aov), s)| ((1. / total) * (*s + pass as Float * *c), *aov))
+                .collect::<Vec<_>>();
+
+            on_row(y, &row);
+        };
+        let _span = debug_span!("pass", samples = pass).entered();
+        let pixels = camera.ray_map(pass, &f, show_row);
+
+        for (s, (c, _)) in sum.iter_mut().zip(&pixels) {
+            *s += pass as Float * *c;
+        }
+        if pass > aov_samples {
+            aovs = pixels.into_iter().map(|(_, aov)| aov).collect();
+            aov_samples = pass;
+        }
+        done += pass;
+    }
+
+    if done < samples {
+        info!(
+            "The time limit is over after {} of the {} samples",
+            done, samples
+        );
+    }
+
+    let pixels = sum
+        .into_iter()
+        .zip(aovs)
+        .map(|(s, aov)| ((1. / done as Float) * s, aov))
+        .collect();
+
+    ImageBuffer::new(camera.width(), camera.height(), pixels)
+}
+
+/// Encode the linear `pixels` for the output format.
+fn encode(mut pixels: ImageBuffer, aovs: &[Aov], options: &Options) -> ImageBuffer {
+    let exposure = tonemap::exposure_scale(options.exposure);
+
+    for (c, aov) in pixels.iter_mut().zip(aovs) {
+        let linear = match aov.alpha {
+            // With transparency, colors are premultiplied by the coverage.
+            a if options.transparent && a > 0. => (1. / a) * *c,
+            _ => *c,
+        };
+
+        *c = match options.format.is_linear() {
+            true => exposure * linear,
+            false => display(exposure * linear, options),
+        };
+    }
+
+    pixels
+}
+
+/// Tone map the linear color `c` and encode it for display, graded by
+/// the LUT of the `options` when given.
+fn display(c: Color, options: &Options) -> Color {
+    let encoded = options.tone_map.apply(c).to_gamma();
+
+    match &options.lut {
+        Some(lut) => lut.apply(encoded),
+        None => encoded,
+    }
+}
+
+/// Save the encoded `pixels` and the AOV passes to `output`, or
+/// write the image to the standard output when missing.
+fn save(
+    pixels: &ImageBuffer,
+    aovs: &[Aov],
+    options: &Options,
+    output: Option<&Path>,
+) -> Result<(), String> {
+    let _span = debug_span!("save").entered();
+    // The images store the alpha in single precision like the colors.
+    #[allow(clippy::unnecessary_cast)]
+    let alpha = options
+        .transparent
+        .then(|| aovs.iter().map(|aov| aov.alpha as f32).collect::<Vec<_>>());
+
+    match output {
+        Some(path) => pixels.save(path, options.format, alpha.as_deref()),
+        None => output::write_ppm(
+            io::stdout().lock(),
+            options.format.depth(),
+            pixels.width(),
+            pixels.height(),
+            pixels,
         ),
-    ];
-
-    let mut rng = rand::thread_rng();
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rng.gen::<f32>();
-            let center = Vec3(
-                a as f32 + 0.9 * rng.gen::<f32>(),
-                0.2,
-                b as f32 + 0.9 * rng.gen::<f32>(),
-            );
+    }
+    .map_err(|e| format!("failed to write the image: {}", e))?;
 
-            let material = if (0.0..0.7).contains(&choose_mat) {
-                Material::lambertian(Color::rand(0.0..1.0), None)
-            } else if (0.7..0.9).contains(&choose_mat) {
-                Material::metal(Color::rand(0.4..0.8), Some(rng.gen()))
-            } else {
-                Material::dielectric(rng.gen_range(1.0..5.0), None)
-            };
+    if let Some(output) = output {
+        for pass in &options.aov_passes {
+            let path = pass.path(output);
+            let colors = pass.colors(aovs, options.format.is_linear());
 
-            world.push(Sphere::new(center, 0.2, material));
+            ImageBuffer::new(pixels.width(), pixels.height(), colors)
+                .save(&path, options.format, None)
+                .map_err(|e| format!("failed to write the {} pass: {}", pass.name(), e))?;
         }
     }
 
-    print!("P3\n{} {}\n255\n", width, height);
+    Ok(())
+}
+
+/// Trace the rays of the pixels to dump of the `options` and export
+/// the paths they bounce along.
+fn dump_paths(options: &Options) -> Result<(), String> {
+    let Some(path) = &options.dump_paths else {
+        return Ok(());
+    };
+
+    let world = World::builder()
+        .add_many(options.scene.build())
+        .with_color_space(options.color_space)
+        .build()?;
+    let camera = Camera::new(
+        options.aspect_ratio,
+        options.width,
+        options.v_fov,
+        options.look_from,
+        options.look_at,
+        options.defocus_angle,
+        options.focus_dist,
+    )
+    .up(options.up)
+    .filter(options.filter)
+    .quiet();
+
+    let pixels = match &options.dump_pixels[..] {
+        [] => vec![(camera.width() / 2, camera.height() / 2)],
+        pixels => pixels.to_vec(),
+    };
+    if let Some((x, y)) = pixels
+        .iter()
+        .find(|(x, y)| *x >= camera.width() || *y >= camera.height())
+    {
+        return Err(format!(
+            "the pixel ({}, {}) is outside of the {}x{} image",
+            x,
+            y,
+            camera.width(),
+            camera.height()
+        ));
+    }
+
+    let paths = pixels
+        .iter()
+        .flat_map(|&(x, y)| {
+            camera
+                .pixel_samples(x, y, options.samples)
+                .into_iter()
+                .map(move |sample| ((x, y), sample))
+        })
+        .map(|(pixel, sample)| {
+            (
+                pixel,
+                render::ray_path(sample.ray, &world, options.max_depth),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    raypath::save(path, &paths)
+        .map_err(|e| format!("failed to write the paths to `{}`: {}", path.display(), e))?;
+    info!(
+        "Saved the {} paths of {} pixels to `{}`",
+        paths.len(),
+        pixels.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Save the `objects` with the camera and the render settings of the
+/// `options`, as RON when `path` ends with `.ron` and as JSON otherwise,
+/// so that the scene renders the same when loaded back.
+fn save_scene(path: &Path, objects: &[Object], options: &Options) -> Result<(), String> {
+    let render = RenderSettings {
+        width: Some(options.width),
+        aspect_ratio: Some(options.aspect_ratio),
+        samples: Some(options.samples),
+        max_depth: Some(options.max_depth),
+        post: options.post.clone(),
+    };
+
+    if path.extension().is_some_and(|e| e == "ron") {
+        let camera = CameraDescription {
+            look_from: Some(options.look_from.into()),
+            look_at: Some(options.look_at.into()),
+            up: Some(options.up.into()),
+            fov: Some(options.v_fov),
+            defocus_angle: Some(options.defocus_angle),
+            focus_dist: Some(options.focus_dist),
+        };
+
+        return SceneDescription::from_objects(camera, objects, render)
+            .map_err(|e| format!("failed to describe the scene: {}", e))?
+            .save(path);
+    }
+
+    let camera = Camera::new(
+        options.aspect_ratio,
+        options.width,
+        options.v_fov,
+        options.look_from,
+        options.look_at,
+        options.defocus_angle,
+        options.focus_dist,
+    )
+    .up(options.up);
+
+    WorldFile {
+        camera,
+        objects: objects.to_vec(),
+        render,
+    }
+    .save_json(path)
+}
+
+/// Render the image, or the frames of the animation, described by
+/// the `options`.
+fn run(options: &Options) -> Result<(), String> {
+    let aspect_ratio = options.aspect_ratio;
+    let width = options.width;
+    let height = (width as Float / aspect_ratio) as u32;
+
+    let v_fov = options.v_fov;
+    let look_from = options.look_from;
+    let look_at = options.look_at;
+    let defocus_angle = options.defocus_angle;
+
+    let (objects, animation) = {
+        let _span = debug_span!("build").entered();
+        let (objects, animation) = options.scene.build_animated();
+        debug!("{} objects", objects.len());
+
+        (objects, animation)
+    };
+
+    let samples = options.samples;
+    let depth = options.max_depth;
+
+    if let Some(path) = &options.save_scene {
+        save_scene(path, &objects, options)?;
+    }
 
-    let samples = 50;
-    let depth = 20;
     let time = Instant::now();
+    if options.stats || options.stats_json.is_some() {
+        stats::enable();
+    }
 
-    let colors = camera.ray_map(samples, |r| {
-        let pixel_color = r
-            .iter()
-            .map(|r| ray_color(*r, &world, depth))
-            .sum::<Color>();
+    let exposure = tonemap::exposure_scale(options.exposure);
 
-        (1.0 / samples as f32) * pixel_color
-    });
+    if options.interactive {
+        let tracer = Tracer {
+            max_depth: depth,
+            transparent: options.transparent,
+            firefly: options.firefly,
+        };
+        let world = World::builder()
+            .add_many(objects)
+            .with_color_space(options.color_space)
+            .build()?;
+        let explored = preview::explore(
+            width,
+            height,
+            look_from,
+            look_at,
+            |look_from, look_at| {
+                let focus_dist = Vec3::norm(look_from - look_at);
+                let camera = Camera::new(
+                    aspect_ratio,
+                    width,
+                    v_fov,
+                    look_from,
+                    look_at,
+                    defocus_angle,
+                    focus_dist,
+                )
+                .up(options.up)
+                .threads(options.threads)
+                .pin_threads(options.pin_threads)
+                .filter(options.filter)
+                .quiet();
+
+                camera
+                    .ray_map(1, |r| tracer.pixel(r, &world), |_, _| {})
+                    .into_iter()
+                    .map(|(c, _)| c)
+                    .collect()
+            },
+            |c| display(exposure * c, options),
+        );
+
+        return explored;
+    }
 
-    colors.iter().flatten().for_each(|c| println!("{}", c));
+    let mut video = match &options.output {
+        Some(path) if options.video => {
+            Some(VideoEncoder::new(path, width, height, options.fps).map_err(|e| e.to_string())?)
+        }
+        _ => None,
+    };
+
+    let eyes_at = |t: Float| {
+        let eye = animation.look_from(look_from, look_at, t);
+
+        match options.anaglyph {
+            true => stereo::eye_pair(eye, look_at).to_vec(),
+            false => vec![eye],
+        }
+    };
+    let new_camera = |eye: Vec3| {
+        Camera::new(
+            aspect_ratio,
+            width,
+            v_fov,
+            eye,
+            look_at,
+            defocus_angle,
+            options.focus_dist,
+        )
+        .up(options.up)
+        .threads(options.threads)
+        .pin_threads(options.pin_threads)
+        .filter(options.filter)
+        .pausable(PAUSE.clone())
+    };
+
+    for frame in 0..options.frames {
+        let _span = debug_span!("frame", frame = frame + 1).entered();
+        let t = frame as Float / options.fps;
+        // The motion of the pixels is measured towards the next frame.
+        let next_t = (frame + 1) as Float / options.fps;
+
+        let eyes = eyes_at(t);
+        let mut frame_objects = objects.clone();
+        animation.move_objects(&mut frame_objects, t);
+        if let Some(tolerance) = options.lod {
+            // The eyes of an anaglyph are close enough to share the
+            // simplified meshes.
+            let summary = lod::simplify(&mut frame_objects, &new_camera(eyes[0]), tolerance);
+            debug!(
+                "{} meshes simplified and {} replaced by spheres, {} triangles out of {}",
+                summary.simplified,
+                summary.replaced,
+                summary.triangles_after,
+                summary.triangles_before
+            );
+        }
+        let world = World::builder()
+            .add_many(frame_objects)
+            .with_color_space(options.color_space)
+            .build()?;
+
+        let output = match (&options.output, options.frames) {
+            (Some(path), _) if options.video => Some(path.clone()),
+            (Some(path), 1) => Some(path.clone()),
+            (Some(path), _) => Some(output::frame_path(path, frame + 1)),
+            (None, _) => None,
+        };
+
+        if options.frames > 1 {
+            info!("Frame {}/{}", frame + 1, options.frames);
+        }
+
+        eyes.into_iter()
+            .map(|eye| {
+                render(
+                    &new_camera(eye),
+                    &world,
+                    options,
+                    output.as_deref(),
+                    samples,
+                    depth,
+                )
+            })
+            .collect::<Result<Option<Vec<_>>, _>>()
+            .and_then(|views| {
+                // Streamed images are already saved.
+                let Some(views) = views else {
+                    return Ok(());
+                };
+                let mut views = views
+                    .into_iter()
+                    .map(|(pixels, aovs)| (encode(pixels, &aovs, options), aovs));
+
+                // The AOVs of the left eye are kept for the anaglyph.
+                let (mut pixels, mut aovs) = views.next().unwrap();
+                if let Some((right, _)) = views.next() {
+                    pixels = ImageBuffer::new(width, height, stereo::anaglyph(&pixels, &right));
+                }
+
+                if options.aov_passes.contains(&AovPass::Motion) {
+                    Aov::set_motion(
+                        &mut aovs,
+                        width,
+                        &new_camera(eyes_at(next_t)[0]),
+                        |object| {
+                            animation.offset(object as usize, next_t)
+                                - animation.offset(object as usize, t)
+                        },
+                    );
+                }
+
+                match (&mut video, output) {
+                    (Some(video), _) => video
+                        .write_frame(&pixels)
+                        .map_err(|e| format!("failed to encode the frame: {}", e)),
+                    (None, output) => save(&pixels, &aovs, options, output.as_deref()),
+                }
+            })?;
+    }
+
+    if let Some(video) = video {
+        video
+            .finish()
+            .map_err(|e| format!("failed to encode the video: {}", e))?;
+    }
 
     let elapsed = time.elapsed();
-    eprintln!("\rDone.                                   ");
-    eprintln!(
-        "Time took: {}m {:.2}s",
+    info!(
+        "Done in {}m {:.2}s",
         elapsed.as_secs() / 60,
         elapsed.as_secs_f32() % 60.
     );
+
+    report_stats(stats::take(), elapsed, options)
+}
+
+/// Number of objects listed by the statistics, the ones tested the most.
+const TOP_OBJECTS: usize = 10;
+
+/// Print the statistics of the render and export them as JSON, as
+/// asked by the `options`.
+fn report_stats(stats: RenderStats, elapsed: Duration, options: &Options) -> Result<(), String> {
+    let rays = stats.primary_rays + stats.secondary_rays;
+    let rays_per_second = rays as f64 / elapsed.as_secs_f64();
+
+    if options.stats {
+        eprintln!(
+            "Rays: {} primary, {} secondary, {}/s",
+            HumanCount(stats.primary_rays),
+            HumanCount(stats.secondary_rays),
+            HumanCount(rays_per_second as u64)
+        );
+        eprintln!(
+            "Intersection tests: {}, {:.1} per ray",
+            HumanCount(stats.intersection_tests),
+            stats.intersection_tests as f64 / rays.max(1) as f64
+        );
+        eprintln!(
+            "BVH nodes visited: {}, {:.1} per ray",
+            HumanCount(stats.bvh_node_visits),
+            stats.bvh_node_visits as f64 / rays.max(1) as f64
+        );
+
+        let paths = stats.bounces.iter().sum::<u64>().max(1);
+        let histogram = (0..)
+            .zip(stats.bounces)
+            .filter(|(_, count)| *count > 0)
+            .map(|(bounces, count)| {
+                let more = if bounces == BOUNCE_BUCKETS - 1 {
+                    "+"
+                } else {
+                    ""
+                };
+                format!(
+                    "{}{}: {:.1}%",
+                    bounces,
+                    more,
+                    count as f64 * 100. / paths as f64
+                )
+            })
+            .collect::<Vec<_>>();
+        eprintln!(
+            "Bounces: {:.2} on average ({})",
+            stats.average_bounces(),
+            histogram.join(", ")
+        );
+        if stats.non_finite_samples > 0 {
+            eprintln!(
+                "NaN or infinite samples: {}, rendered black",
+                HumanCount(stats.non_finite_samples)
+            );
+        }
+
+        let top = stats.top_objects(TOP_OBJECTS);
+        if !top.is_empty() {
+            eprintln!("Objects tested the most:");
+        }
+        for (object, counts) in top {
+            eprintln!(
+                "  object {}: {} tests ({:.1}%), {} hits ({:.1}% of its tests)",
+                object,
+                HumanCount(counts.intersection_tests),
+                counts.intersection_tests as f64 * 100. / stats.intersection_tests.max(1) as f64,
+                HumanCount(counts.hits),
+                counts.hits as f64 * 100. / counts.intersection_tests as f64
+            );
+        }
+    }
+
+    if let Some(path) = &options.stats_json {
+        #[derive(Serialize)]
+        struct Report {
+            #[serde(flatten)]
+            stats: RenderStats,
+            average_bounces: f64,
+            seconds: f64,
+            rays_per_second: f64,
+        }
+
+        let report = Report {
+            average_bounces: stats.average_bounces(),
+            stats,
+            seconds: elapsed.as_secs_f64(),
+            rays_per_second,
+        };
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("failed to serialize the statistics: {}", e))?;
+        fs::write(path, json + "\n")
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// How often the scene file is checked for changes in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Render the scene file at `path` again every time it changes, with
+/// passes quadrupling the samples from one up to the requested ones.
+/// The passes restart as soon as the file is saved, while the errors
+/// of invalid scenes are shown until the file is fixed.
+fn watch(path: &Path, mut options: Options) -> Result<(), String> {
+    let modified = || fs::metadata(path).and_then(|m| m.modified()).ok();
+    let wait_change = |version| {
+        while modified() == version {
+            thread::sleep(WATCH_INTERVAL);
+        }
+    };
+
+    loop {
+        let version = modified();
+        let mut samples = 1;
+
+        loop {
+            samples = samples.min(options.samples);
+            run(&Options {
+                samples,
+                ..options.clone()
+            })?;
+
+            if samples == options.samples || modified() != version {
+                break;
+            }
+            samples *= 4;
+        }
+
+        wait_change(version);
+
+        // Parsing again the command line reloads the scene.
+        options = loop {
+            let version = modified();
+            info!("Reloading `{}`", path.display());
+
+            match Options::parse() {
+                Ok(options) => break options,
+                Err(e) => error!("{}", e),
+            }
+            wait_change(version);
+        };
+    }
+}
+
+/// Pause the render when the process receives `SIGUSR1`, and resume
+/// it at the next one.
+#[cfg(unix)]
+fn toggle_pause_on_signal() -> Result<(), String> {
+    use signal_hook::{consts::SIGUSR1, iterator::Signals};
+
+    let mut signals =
+        Signals::new([SIGUSR1]).map_err(|e| format!("failed to handle the signals: {}", e))?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            match PAUSE.toggle() {
+                true => info!("Paused, send SIGUSR1 again to resume"),
+                false => info!("Resumed"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn toggle_pause_on_signal() -> Result<(), String> {
+    Ok(())
+}
+
+/// Lower the priority of the process to the lowest one, which the render
+/// threads started afterwards inherit.
+#[cfg(unix)]
+fn lower_priority() -> Result<(), String> {
+    // SAFETY: `setpriority` only changes the scheduling of the process.
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) } {
+        0 => Ok(()),
+        _ => Err(format!(
+            "failed to lower the priority: {}",
+            io::Error::last_os_error()
+        )),
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_priority() -> Result<(), String> {
+    Err("`--nice` is only supported on Unix".into())
+}
+
+fn main() {
+    let result = Options::parse().and_then(|options| {
+        if options.nice {
+            lower_priority()?;
+        }
+
+        match (&options.command, &options.watch) {
+            // Without their features the commands are rejected by the options.
+            #[cfg(feature = "serve")]
+            (Some(options::Command::Serve { addr }), _) => serve::serve(addr, &options),
+            #[cfg(feature = "grpc")]
+            (Some(options::Command::Grpc { addr }), _) => {
+                raycasting_rs::grpc::serve(addr, options.threads)
+            }
+            _ if options.dry_run => estimate::dry_run(&options),
+            _ if options.furnace => furnace::furnace(&options),
+            _ if options.dump_paths.is_some() => dump_paths(&options),
+            (_, Some(path)) => {
+                let path = path.clone();
+                toggle_pause_on_signal().and_then(|_| watch(&path, options))
+            }
+            _ => toggle_pause_on_signal().and_then(|_| run(&options)),
+        }
+    });
+
+    if let Err(e) = result {
+        error!("{}", e);
+        process::exit(1);
+    }
 }
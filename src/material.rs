@@ -1,34 +1,49 @@
 use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    onb::Onb,
     ray::Ray,
-    vec3::{Color, Vec3},
+    vec3::{consts::PI, Color, Float, Vec3},
 };
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MaterialType {
     #[default]
     Metal,
     Lambertian,
     Dielectric,
+    /// Light source emitting its color.
+    Emissive,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Scatter {
-    Absorbed { solid_color: Color },
-    Scattered { direction: Vec3, attenuation: Color },
+    Absorbed {
+        solid_color: Color,
+    },
+    Scattered {
+        direction: Vec3,
+        attenuation: Color,
+        /// Density of the `direction` over the solid angle, `None` for
+        /// the mirror reflections and refractions.
+        pdf: Option<Float>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Material {
     pub material: MaterialType,
     pub solid_color: Color,
-    pub refraction_index: f32,
-    pub fuzz: Option<f32>,
+    pub refraction_index: Float,
+    pub fuzz: Option<Float>,
 }
 
 impl Material {
-    pub fn metal(solid_color: Color, fuzz: Option<f32>) -> Self {
+    pub fn metal(solid_color: Color, fuzz: Option<Float>) -> Self {
         Self {
             material: MaterialType::Metal,
             solid_color,
@@ -37,7 +52,7 @@ impl Material {
         }
     }
 
-    pub fn lambertian(solid_color: Color, fuzz: Option<f32>) -> Self {
+    pub fn lambertian(solid_color: Color, fuzz: Option<Float>) -> Self {
         Self {
             material: MaterialType::Lambertian,
             solid_color,
@@ -46,7 +61,7 @@ impl Material {
         }
     }
 
-    pub fn dielectric(refraction_index: f32, fuzz: Option<f32>) -> Self {
+    pub fn dielectric(refraction_index: Float, fuzz: Option<Float>) -> Self {
         Self {
             material: MaterialType::Dielectric,
             solid_color: Color::WHITE,
@@ -55,27 +70,76 @@ impl Material {
         }
     }
 
+    /// Check that the parameters are meaningful, as the invalid ones
+    /// render black or NaN pixels.
+    pub fn validate(&self) -> Result<(), String> {
+        let Vec3(r, g, b) = self.solid_color.rgb;
+        if !self.solid_color.rgb.is_finite() || r < 0. || g < 0. || b < 0. {
+            return Err(format!(
+                "the color can't be negative, found {}",
+                self.solid_color.rgb
+            ));
+        }
+
+        if let Some(fuzz) = self.fuzz.filter(|f| !(0. ..=1.).contains(f)) {
+            return Err(format!("the fuzz must be between 0 and 1, found {}", fuzz));
+        }
+
+        if let MaterialType::Dielectric = self.material {
+            if !(self.refraction_index > 0. && self.refraction_index.is_finite()) {
+                return Err(format!(
+                    "the refraction index must be positive, found {}",
+                    self.refraction_index
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Area light emitting `color`, which can be brighter than white.
+    pub fn emissive(color: Color) -> Self {
+        Self {
+            material: MaterialType::Emissive,
+            solid_color: color,
+            fuzz: None,
+            refraction_index: 1.0,
+        }
+    }
+
     pub fn scatter(
         &self,
         r: Ray,
         normal: Vec3,
-        refraction_index: f32,
+        refraction_index: Float,
         front_face: bool,
     ) -> Scatter {
+        // Open surfaces, like triangles, can be hit from behind.
+        let facing_normal = if front_face { normal } else { -normal };
+
         let mut result = match self.material {
-            MaterialType::Lambertian => Scatter::Scattered {
-                direction: Vec3::lambertian_distribution(normal),
-                attenuation: self.solid_color,
+            MaterialType::Emissive => Scatter::Absorbed {
+                solid_color: self.solid_color,
             },
+            MaterialType::Lambertian => {
+                let local = Vec3::rand_cosine_direction();
+
+                Scatter::Scattered {
+                    direction: Onb::new(facing_normal).local(local),
+                    attenuation: self.solid_color,
+                    pdf: Some(local.z() / PI),
+                }
+            }
             MaterialType::Metal => {
                 let mut direction = Vec3::reflect(r.dir, normal);
                 if Vec3::norm(direction) < 1e-8 {
-                    direction = normal;
+                    direction = facing_normal;
                 }
 
                 Scatter::Scattered {
                     direction,
                     attenuation: self.solid_color,
+                    pdf: None,
                 }
             }
             MaterialType::Dielectric => {
@@ -89,7 +153,7 @@ impl Material {
 
                 let r_dir = Vec3::unit(r.dir);
                 let cos_theta = Vec3::dot(-r_dir, normal).min(1.0);
-                let sin_theta = f32::sqrt(1.0 - f32::powi(cos_theta, 2));
+                let sin_theta = Float::sqrt(1.0 - Float::powi(cos_theta, 2));
 
                 let cannot_refract = refraction_ratio * sin_theta > 1.0
                     || Self::reflectance(cos_theta, refraction_ratio);
@@ -103,6 +167,7 @@ impl Material {
                 Scatter::Scattered {
                     direction,
                     attenuation: self.solid_color,
+                    pdf: None,
                 }
             }
         };
@@ -113,18 +178,21 @@ impl Material {
                 Scatter::Scattered {
                     direction,
                     attenuation,
+                    ..
                 },
             ) => {
                 let direction = Vec3::unit(direction) + fuzz * Vec3::rand_unit();
 
-                if Vec3::dot(direction, normal) < 0.0 {
+                if Vec3::dot(direction, facing_normal) < 0.0 {
                     Scatter::Absorbed {
                         solid_color: self.solid_color,
                     }
                 } else {
+                    // The density of the blurred direction isn't known.
                     Scatter::Scattered {
                         direction,
                         attenuation,
+                        pdf: None,
                     }
                 }
             }
@@ -134,10 +202,35 @@ impl Material {
         result
     }
 
-    fn reflectance(cos_theta: f32, ref_ratio: f32) -> bool {
+    /// Stable identifier of the material, the FNV-1a hash of its parameters.
+    pub fn id(&self) -> u32 {
+        let kind = match self.material {
+            MaterialType::Metal => 0,
+            MaterialType::Lambertian => 1,
+            MaterialType::Dielectric => 2,
+            MaterialType::Emissive => 3,
+        };
+        let params = [
+            self.solid_color.rgb.0,
+            self.solid_color.rgb.1,
+            self.solid_color.rgb.2,
+            self.refraction_index,
+            self.fuzz.unwrap_or(-1.),
+        ];
+
+        params
+            .iter()
+            .flat_map(|p| p.to_bits().to_le_bytes())
+            .chain([kind])
+            .fold(0x811c9dc5, |hash, byte| {
+                (hash ^ byte as u32).wrapping_mul(0x01000193)
+            })
+    }
+
+    fn reflectance(cos_theta: Float, ref_ratio: Float) -> bool {
         /* Use Schlick's approximation for reflectance. */
-        let r0 = f32::powi((1. - ref_ratio) / (1. + ref_ratio), 2);
+        let r0 = Float::powi((1. - ref_ratio) / (1. + ref_ratio), 2);
 
-        (r0 + (1. - r0) * f32::powi(1. - cos_theta, 5)) > rand::thread_rng().gen()
+        (r0 + (1. - r0) * Float::powi(1. - cos_theta, 5)) > rand::thread_rng().gen()
     }
 }
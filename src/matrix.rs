@@ -0,0 +1,182 @@
+use std::ops::Mul;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    quat::Quat,
+    vec3::{Float, Vec3},
+};
+
+/// Affine transform, as a row-major 4x4 matrix, placing the imported
+/// meshes and cameras in the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Matrix(pub [[Float; 4]; 4]);
+
+impl Matrix {
+    pub const IDENTITY: Self = Self([
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ]);
+
+    /// Matrix of the 16 `values` given row by row.
+    pub fn from_rows(values: &[Float]) -> Result<Self, String> {
+        if values.len() != 16 {
+            return Err("a transform needs 16 numbers".into());
+        }
+
+        let mut m = [[0.; 4]; 4];
+        for (i, value) in values.iter().enumerate() {
+            m[i / 4][i % 4] = *value;
+        }
+
+        Ok(Self(m))
+    }
+
+    /// Matrix of the 16 `values` given column by column, like pbrt does.
+    pub fn from_columns(values: &[Float]) -> Result<Self, String> {
+        let Self(m) = Self::from_rows(values)?;
+
+        Ok(Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| m[j][i])
+        })))
+    }
+
+    pub fn translate(v: Vec3) -> Self {
+        Self([
+            [1., 0., 0., v.0],
+            [0., 1., 0., v.1],
+            [0., 0., 1., v.2],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scale(v: Vec3) -> Self {
+        Self([
+            [v.0, 0., 0., 0.],
+            [0., v.1, 0., 0.],
+            [0., 0., v.2, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Rotation of `angle` degrees around the `axis`.
+    pub fn rotate(angle: Float, axis: Vec3) -> Self {
+        Self::from_quat(Quat::from_axis_angle(axis, angle))
+    }
+
+    /// The `rotation` as a matrix.
+    pub fn from_quat(rotation: Quat) -> Self {
+        Self::from_frame(
+            rotation.rotate(Vec3(1., 0., 0.)),
+            rotation.rotate(Vec3(0., 1., 0.)),
+            rotation.rotate(Vec3(0., 0., 1.)),
+            Vec3::ZERO,
+        )
+    }
+
+    /// Transform placing the axes of a frame at `origin`.
+    pub fn from_frame(x: Vec3, y: Vec3, z: Vec3, origin: Vec3) -> Self {
+        Self([
+            [x.0, y.0, z.0, origin.0],
+            [x.1, y.1, z.1, origin.1],
+            [x.2, y.2, z.2, origin.2],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Determinant of the linear part, negative for mirroring transforms.
+    pub fn determinant(&self) -> Float {
+        let m = &self.0;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Inverse by Gauss-Jordan elimination, `None` for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut m = self.0;
+        let mut inv = Self::IDENTITY.0;
+
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))?;
+            if m[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            m.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1. / m[col][col];
+            for j in 0..4 {
+                m[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in (0..4).filter(|row| *row != col) {
+                let factor = m[row][col];
+                for j in 0..4 {
+                    m[row][j] -= factor * m[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Self(inv))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[j][i])
+        }))
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        let m = &self.0;
+        let row = |i: usize| m[i][0] * p.0 + m[i][1] * p.1 + m[i][2] * p.2 + m[i][3];
+
+        Vec3(row(0), row(1), row(2))
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.0;
+        let row = |i: usize| m[i][0] * v.0 + m[i][1] * v.1 + m[i][2] * v.2;
+
+        Vec3(row(0), row(1), row(2))
+    }
+
+    /// Transform the normal `n` by the inverse transpose of the linear
+    /// part, so that it stays perpendicular to the transformed surface
+    /// even when the scale isn't uniform. The result isn't a unit vector.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        // The cofactors are the inverse transpose times the determinant,
+        // whose sign keeps the normals of mirrored surfaces outwards.
+        let m = &self.0;
+        let cofactor = |i: usize, j: usize| {
+            let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+            let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let row = |i: usize| cofactor(i, 0) * n.0 + cofactor(i, 1) * n.1 + cofactor(i, 2) * n.2;
+
+        self.determinant().signum() * Vec3(row(0), row(1), row(2))
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+
+        Self(m)
+    }
+}
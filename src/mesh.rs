@@ -0,0 +1,609 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    matrix::Matrix,
+    ray::Ray,
+    triangle::Triangle,
+    vec3::{consts::TAU, Color, Float, Vec3},
+};
+
+/// Angle in degrees between two faces above which the edge they share
+/// is kept sharp instead of smoothed by the normals.
+pub const CREASE_ANGLE: Float = 40.;
+
+/// Triangles sharing a bounding box, which is tested before them.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "Vec<Triangle>", try_from = "Vec<Triangle>")
+)]
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    bounds: Aabb,
+}
+
+impl TryFrom<Vec<Triangle>> for Mesh {
+    type Error = String;
+
+    fn try_from(triangles: Vec<Triangle>) -> Result<Self, String> {
+        match triangles.is_empty() {
+            true => Err("a mesh needs at least one triangle".into()),
+            false => Ok(Self::new(triangles)),
+        }
+    }
+}
+
+impl From<Mesh> for Vec<Triangle> {
+    fn from(mesh: Mesh) -> Self {
+        mesh.triangles
+    }
+}
+
+impl Mesh {
+    /// Mesh of the `triangles`, which must not be empty.
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let bounds = Aabb::from_points(triangles.iter().flat_map(|t| t.vertices));
+
+        Self { triangles, bounds }
+    }
+
+    /// Surface of revolution of the `profile` around the y axis, like
+    /// the one turned on a lathe, with `segments` steps around the axis.
+    ///
+    /// The profile lists the points as `(radius, height)`, its front side
+    /// being on the right when going from one to the next: from bottom to
+    /// top for the outside of a vase, and back down for its inside. The
+    /// normals are smooth around the axis, and along the profile except
+    /// on the corners sharper than [`CREASE_ANGLE`].
+    pub fn lathe(
+        profile: &[(Float, Float)],
+        segments: u32,
+        material: Material,
+    ) -> Result<Self, String> {
+        if profile.len() < 2 {
+            return Err("the profile needs at least two points".into());
+        }
+        if let Some((r, y)) = profile
+            .iter()
+            .find(|(r, y)| !(r.is_finite() && y.is_finite() && *r >= 0.))
+        {
+            return Err(format!(
+                "the points of the profile need a positive radius, found ({}, {})",
+                r, y
+            ));
+        }
+        if profile.windows(2).any(|w| w[0] == w[1]) {
+            return Err("the profile repeats a point".into());
+        }
+        if segments < 3 {
+            return Err(format!(
+                "the lathe needs at least 3 segments, found {}",
+                segments
+            ));
+        }
+
+        // Normal of each edge of the profile, as `(radius, height)`.
+        let edges = profile
+            .windows(2)
+            .map(|w| {
+                let ((r0, y0), (r1, y1)) = (w[0], w[1]);
+                let length = (r1 - r0).hypot(y1 - y0);
+                ((y1 - y0) / length, (r0 - r1) / length)
+            })
+            .collect::<Vec<_>>();
+        let crease = CREASE_ANGLE.to_radians().cos();
+        // Normal of the edge `i` at the point it shares with the edge
+        // `neighbour`, the average of both unless they meet at a crease.
+        let normal = |i: usize, neighbour: Option<usize>| {
+            let (nr, ny) = edges[i];
+
+            match neighbour.and_then(|k| edges.get(k)) {
+                Some((mr, my)) if nr * mr + ny * my >= crease => (nr + mr, ny + my),
+                _ => (nr, ny),
+            }
+        };
+
+        let angle = |k: u32| (k % segments) as Float * TAU / segments as Float;
+        let point = |(r, y): (Float, Float), k: u32| {
+            let (sin, cos) = angle(k).sin_cos();
+            Vec3(r * cos, y, r * sin)
+        };
+        let direction = |(nr, ny): (Float, Float), k: u32| {
+            let (sin, cos) = angle(k).sin_cos();
+            Vec3::unit(Vec3(nr * cos, ny, nr * sin))
+        };
+
+        let mut triangles = Vec::new();
+        for (i, w) in profile.windows(2).enumerate() {
+            let (n0, n1) = (normal(i, i.checked_sub(1)), normal(i, Some(i + 1)));
+
+            for k in 0..segments {
+                let (a, b) = (point(w[0], k), point(w[0], k + 1));
+                let (c, d) = (point(w[1], k + 1), point(w[1], k));
+
+                // The triangles are collapsed where the profile touches
+                // the axis.
+                if w[0].0 > 0. {
+                    triangles.push(Triangle::new([a, c, b], material).with_normals([
+                        direction(n0, k),
+                        direction(n1, k + 1),
+                        direction(n0, k + 1),
+                    ]));
+                }
+                if w[1].0 > 0. {
+                    triangles.push(Triangle::new([a, d, c], material).with_normals([
+                        direction(n0, k),
+                        direction(n1, k),
+                        direction(n1, k + 1),
+                    ]));
+                }
+            }
+        }
+
+        match triangles.is_empty() {
+            true => Err("the profile lies on the axis".into()),
+            false => Ok(Self::new(triangles)),
+        }
+    }
+
+    /// Prism of the `polygon` extruded from the ground up to `height`,
+    /// with flat caps. The polygon lists the points as `(x, z)`, in any
+    /// order around it, and must not cross itself.
+    pub fn extrude(
+        polygon: &[(Float, Float)],
+        height: Float,
+        material: Material,
+    ) -> Result<Self, String> {
+        Self::extrude_contours(&[polygon.to_vec()], height, material)
+    }
+
+    /// Prism of the shape outlined by the `contours`, extruded like
+    /// [`Self::extrude`]. The contours within an odd number of others
+    /// are holes, like the inside of an `o` in the outline of a glyph.
+    pub fn extrude_contours(
+        contours: &[Vec<(Float, Float)>],
+        height: Float,
+        material: Material,
+    ) -> Result<Self, String> {
+        if contours.iter().any(|contour| contour.len() < 3) {
+            return Err("the polygon needs at least three points".into());
+        }
+        if let Some((x, z)) = contours
+            .iter()
+            .flatten()
+            .find(|(x, z)| !(x.is_finite() && z.is_finite()))
+        {
+            return Err(format!(
+                "the points of the polygon must be finite, found ({}, {})",
+                x, z
+            ));
+        }
+        if !(height > 0. && height.is_finite()) {
+            return Err(format!("the height must be positive, found {}", height));
+        }
+
+        // Number of contours around each one, odd for the holes.
+        let depths = contours
+            .iter()
+            .enumerate()
+            .map(|(i, contour)| {
+                (contours.iter().enumerate())
+                    .filter(|(j, other)| *j != i && inside(contour[0], other))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+
+        // The outlines turn counter-clockwise from x to z and the holes
+        // clockwise, so that the sides face out of the prism.
+        let mut contours = contours.to_vec();
+        for (contour, depth) in contours.iter_mut().zip(&depths) {
+            let area = (0..contour.len())
+                .map(|i| cross_2d((0., 0.), contour[i], contour[(i + 1) % contour.len()]))
+                .sum::<Float>();
+            if area == 0. {
+                return Err("the polygon is flat".into());
+            }
+            if (area < 0.) == (depth % 2 == 0) {
+                contour.reverse();
+            }
+        }
+
+        let bottom = |(x, z): (Float, Float)| Vec3(x, 0., z);
+        let top = |(x, z): (Float, Float)| Vec3(x, height, z);
+        let mut triangles = Vec::new();
+
+        for contour in &contours {
+            for i in 0..contour.len() {
+                let (p, q) = (contour[i], contour[(i + 1) % contour.len()]);
+                triangles.push(Triangle::new([bottom(p), top(p), top(q)], material));
+                triangles.push(Triangle::new([bottom(p), top(q), bottom(q)], material));
+            }
+        }
+
+        for (i, outline) in contours.iter().enumerate() {
+            if depths[i] % 2 == 1 {
+                continue;
+            }
+
+            let holes = (0..contours.len())
+                .filter(|&j| depths[j] == depths[i] + 1 && inside(contours[j][0], outline))
+                .map(|j| contours[j].clone())
+                .collect();
+            for [a, b, c] in triangulate(&bridge(outline, holes)?)? {
+                triangles.push(Triangle::new([top(a), top(c), top(b)], material));
+                triangles.push(Triangle::new([bottom(a), bottom(b), bottom(c)], material));
+            }
+        }
+
+        Ok(Self::new(triangles))
+    }
+
+    /// Split the mesh into meshes of `size` consecutive triangles at
+    /// most, so that the BVH can skip the ones far from a ray, as a mesh
+    /// tests all its triangles. The triangles listed together should be
+    /// close to each other.
+    pub fn split(self, size: usize) -> Vec<Mesh> {
+        self.triangles
+            .chunks(size.max(1))
+            .map(|triangles| Self::new(triangles.to_vec()))
+            .collect()
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    /// Let the rays hitting the back of the triangles pass through, see
+    /// [`Triangle::cull_backfaces`].
+    pub fn with_backface_culling(mut self, cull_backfaces: bool) -> Self {
+        for triangle in &mut self.triangles {
+            triangle.cull_backfaces = cull_backfaces;
+        }
+        self
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    /// Apply `f` to the material of each triangle.
+    pub fn map_materials(&mut self, mut f: impl FnMut(&mut Material)) {
+        for triangle in &mut self.triangles {
+            f(&mut triangle.material);
+        }
+    }
+
+    /// Simpler version of the mesh, where the vertices within the same
+    /// cell of a grid of `cell` units are merged into their average and
+    /// the triangles collapsed by the merge are dropped. `None` when all
+    /// the triangles collapse.
+    pub fn simplify(&self, cell: Float) -> Option<Mesh> {
+        #[derive(Default)]
+        struct Cluster {
+            position: Vec3,
+            normal: Vec3,
+            color: Color,
+            vertices: Float,
+            colors: Float,
+        }
+
+        let min = self.bounds.min;
+        let key = |v: Vec3| {
+            let q = (v - min) / cell;
+            (q.0.floor() as i64, q.1.floor() as i64, q.2.floor() as i64)
+        };
+
+        let mut clusters = HashMap::<_, Cluster>::new();
+        for triangle in &self.triangles {
+            for i in 0..3 {
+                let cluster = clusters.entry(key(triangle.vertices[i])).or_default();
+                cluster.position += triangle.vertices[i];
+                cluster.vertices += 1.;
+                if let Some(normals) = triangle.normals {
+                    cluster.normal += normals[i];
+                }
+                if let Some(colors) = triangle.colors {
+                    cluster.color += colors[i];
+                    cluster.colors += 1.;
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let triangles = self
+            .triangles
+            .iter()
+            .filter_map(|triangle| {
+                let keys = triangle.vertices.map(key);
+                let mut sorted = keys;
+                sorted.sort_unstable();
+                // Collapsed to a line or a point, or onto another one.
+                if sorted[0] == sorted[1] || sorted[1] == sorted[2] || !seen.insert(sorted) {
+                    return None;
+                }
+
+                let clusters = keys.map(|k| &clusters[&k]);
+                let mut simplified =
+                    Triangle::new(clusters.map(|c| c.position / c.vertices), triangle.material);
+                simplified.normals = triangle
+                    .normals
+                    .map(|_| clusters.map(|c| Vec3::unit(c.normal)));
+                simplified.colors = triangle
+                    .colors
+                    .map(|_| clusters.map(|c| (1. / c.colors) * c.color));
+
+                Some(simplified)
+            })
+            .collect::<Vec<_>>();
+
+        Mesh::try_from(triangles).ok()
+    }
+
+    /// Material looking like the whole mesh from afar: the one covering
+    /// the most area, with the average color of the triangles weighted
+    /// by their area.
+    pub fn average_material(&self) -> Material {
+        // Ordered, so that the ties are broken the same way on every run.
+        let mut areas = BTreeMap::<u32, (Material, Float)>::new();
+        let mut color = Color::BLACK;
+        let mut total = 0.;
+
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.vertices;
+            let area = 0.5 * Vec3::norm(Vec3::cross(b - a, c - a));
+            let triangle_color = match triangle.colors {
+                Some(colors) => (1. / 3.) * colors.into_iter().sum::<Color>(),
+                None => triangle.material.solid_color,
+            };
+
+            areas
+                .entry(triangle.material.id())
+                .or_insert((triangle.material, 0.))
+                .1 += area;
+            color += area * triangle_color;
+            total += area;
+        }
+
+        let (material, _) = areas
+            .into_values()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("a mesh has at least one triangle");
+
+        Material {
+            solid_color: match total > 0. {
+                true => (1. / total) * color,
+                false => material.solid_color,
+            },
+            ..material
+        }
+    }
+
+    pub fn transform(&mut self, m: &Matrix) {
+        for triangle in &mut self.triangles {
+            triangle.vertices = triangle.vertices.map(|v| m.point(v));
+            if let Some(normals) = &mut triangle.normals {
+                *normals = normals.map(|n| Vec3::unit(m.normal(n)));
+            }
+        }
+        self.bounds = Aabb::from_points(self.triangles.iter().flat_map(|t| t.vertices));
+    }
+
+    /// Give the triangles without normals the ones of their vertices,
+    /// averaged from the triangles around each vertex so that the curved
+    /// surfaces don't look faceted. The triangles more than
+    /// `crease_angle` degrees apart don't share their normals, so that
+    /// the hard edges stay sharp.
+    pub fn smooth(&mut self, crease_angle: Float) {
+        let crease = crease_angle.to_radians().cos();
+        // Normals scaled by the area, so that the small triangles weigh
+        // less in the average.
+        let normals = self
+            .triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.vertices;
+                Vec3::cross(b - a, c - a)
+            })
+            .collect::<Vec<_>>();
+
+        // The triangles around each vertex, which are the ones with a
+        // vertex at the same position: `-0` is the same as `0`.
+        let key = |v: Vec3| [v.0 + 0., v.1 + 0., v.2 + 0.].map(Float::to_bits);
+        let mut around = BTreeMap::<_, Vec<usize>>::new();
+        for (i, triangle) in self.triangles.iter().enumerate() {
+            for v in triangle.vertices {
+                around.entry(key(v)).or_default().push(i);
+            }
+        }
+
+        for (i, triangle) in self.triangles.iter_mut().enumerate() {
+            if triangle.normals.is_some() {
+                continue;
+            }
+
+            let normal = Vec3::unit(normals[i]);
+            let smooth = triangle.vertices.map(|v| {
+                Vec3::unit(
+                    around[&key(v)]
+                        .iter()
+                        .map(|j| normals[*j])
+                        .filter(|n| Vec3::dot(Vec3::unit(*n), normal) >= crease)
+                        .fold(Vec3::ZERO, |sum, n| sum + n),
+                )
+            });
+            if smooth.iter().all(|n| n.is_finite()) {
+                triangle.normals = Some(smooth);
+            }
+        }
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        for triangle in &mut self.triangles {
+            for vertex in &mut triangle.vertices {
+                *vertex += offset;
+            }
+        }
+        self.bounds.translate(offset);
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `c`, positive when it
+/// turns counter-clockwise.
+fn cross_2d(a: (Float, Float), b: (Float, Float), c: (Float, Float)) -> Float {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether the point `p` is inside the `polygon`, from the number of
+/// its edges crossed by a line from the point to the right.
+fn inside(p: (Float, Float), polygon: &[(Float, Float)]) -> bool {
+    let mut inside = false;
+
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+/// Whether the segments `a`, `b` and `c`, `d` cross, without counting
+/// the ends they share.
+fn crosses(a: (Float, Float), b: (Float, Float), c: (Float, Float), d: (Float, Float)) -> bool {
+    if [c, d].contains(&a) || [c, d].contains(&b) {
+        return false;
+    }
+
+    let (d1, d2) = (cross_2d(a, b, c), cross_2d(a, b, d));
+    let (d3, d4) = (cross_2d(c, d, a), cross_2d(c, d, b));
+
+    (d1 > 0.) != (d2 > 0.) && (d3 > 0.) != (d4 > 0.)
+}
+
+/// The counter-clockwise `outline` joined to its clockwise `holes` by
+/// bridges, going to each hole and back along the same segment, so that
+/// it can be triangulated as a single polygon.
+fn bridge(
+    outline: &[(Float, Float)],
+    mut holes: Vec<Vec<(Float, Float)>>,
+) -> Result<Vec<(Float, Float)>, String> {
+    let right = |hole: &Vec<(Float, Float)>| hole.iter().map(|p| p.0).fold(Float::MIN, Float::max);
+    // The holes on the right first, so that the bridges of the next ones
+    // can't be blocked by them.
+    holes.sort_by(|a, b| right(b).total_cmp(&right(a)));
+
+    let mut points = outline.to_vec();
+    for k in 0..holes.len() {
+        let hole = &holes[k];
+        let m = (0..hole.len())
+            .max_by(|&i, &j| hole[i].0.total_cmp(&hole[j].0))
+            .expect("a hole has points");
+        let start = hole[m];
+
+        // The closest point of the polygon reachable without crossing
+        // an edge of it or of a hole.
+        let edges = |polygon: &[(Float, Float)]| {
+            (0..polygon.len())
+                .map(|i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+                .collect::<Vec<_>>()
+        };
+        let blocking = holes[k..]
+            .iter()
+            .flat_map(|hole| edges(hole))
+            .chain(edges(&points))
+            .collect::<Vec<_>>();
+        let distance = |p: (Float, Float)| (p.0 - start.0).hypot(p.1 - start.1);
+        let v = (0..points.len())
+            .filter(|&i| {
+                !blocking
+                    .iter()
+                    .any(|&(c, d)| crosses(start, points[i], c, d))
+            })
+            .min_by(|&i, &j| distance(points[i]).total_cmp(&distance(points[j])))
+            .ok_or("the polygon must not cross itself")?;
+
+        let mut joined = points[..=v].to_vec();
+        joined.extend(&hole[m..]);
+        joined.extend(&hole[..=m]);
+        joined.extend(&points[v..]);
+        points = joined;
+    }
+
+    Ok(points)
+}
+
+/// Triangles covering the counter-clockwise `polygon`, by clipping its
+/// ears: the corners whose triangle holds no other point of the polygon
+/// can be cut off, until a single triangle is left.
+fn triangulate(polygon: &[(Float, Float)]) -> Result<Vec<[(Float, Float); 3]>, String> {
+    let mut points = polygon.to_vec();
+    let mut triangles = Vec::new();
+
+    while points.len() > 3 {
+        let n = points.len();
+        let corner = |i: usize| (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+
+            cross_2d(a, b, c) > 0.
+                && !points.iter().any(|&p| {
+                    ![a, b, c].contains(&p)
+                        && cross_2d(a, b, p) > 0.
+                        && cross_2d(b, c, p) > 0.
+                        && cross_2d(c, a, p) >= 0.
+                })
+        };
+        // Without ears, a point on a straight edge can still go, as its
+        // triangle is flat.
+        let flat = |i: usize| {
+            let (a, b, c) = corner(i);
+            cross_2d(a, b, c) == 0.
+        };
+
+        let i = (0..n)
+            .find(|&i| is_ear(i))
+            .or_else(|| (0..n).find(|&i| flat(i)))
+            .ok_or("the polygon must not cross itself")?;
+        let (a, b, c) = corner(i);
+        if !flat(i) {
+            triangles.push([a, b, c]);
+        }
+        points.remove(i);
+    }
+    triangles.push([points[0], points[1], points[2]]);
+
+    Ok(triangles)
+}
+
+impl Hit<Ray> for Mesh {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        if !self.bounds.hit(r, t_range) {
+            return None;
+        }
+
+        let mut closest = None;
+        let mut max_t = t_range.max;
+
+        for triangle in &self.triangles {
+            if let Some(hit) = triangle.hit(r, t_range.until(max_t)) {
+                max_t = hit.t;
+                closest = Some(hit);
+            }
+        }
+
+        closest
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.bounds.hit(r, t_range) && self.triangles.iter().any(|t| t.hit_any(r, t_range))
+    }
+}
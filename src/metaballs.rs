@@ -0,0 +1,227 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Float, Vec3},
+};
+
+/// Steepest slope of the field of a ball of unit radius, reached at a
+/// distance of `1 / sqrt(5)` from its center.
+const MAX_SLOPE: Float = 1.7173;
+
+/// Threshold of the metaballs described without one, at which a lone
+/// ball is a sphere of about 0.6 times its radius.
+pub const DEFAULT_THRESHOLD: Float = 0.25;
+
+/// Steps of the ray marching at most, past which the ray is taken as
+/// missing the surface.
+const MAX_STEPS: u32 = 512;
+
+/// Ball of a [`Metaballs`] object, whose field falls from 1 at the
+/// center to 0 at the `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Ball {
+    pub center: Vec3,
+    pub radius: Float,
+}
+
+impl Ball {
+    /// Field of the ball at the squared distance `d2` from its center,
+    /// smooth and null past the radius so that the far balls don't
+    /// count.
+    fn field(&self, d2: Float) -> Float {
+        let x = 1. - d2 / (self.radius * self.radius);
+
+        match x > 0. {
+            true => x * x * x,
+            false => 0.,
+        }
+    }
+
+    /// Distances along the ray `r` where it is within the radius.
+    fn span(&self, r: &Ray) -> Option<Interval> {
+        let oc = r.start - self.center;
+        let a = Vec3::dot(r.dir, r.dir);
+        let half_b = Vec3::dot(r.dir, oc);
+        let c = Vec3::dot(oc, oc) - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if !(discriminant > 0. && a > 0.) {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+
+        Some(Interval::new((-half_b - sqrtd) / a, (-half_b + sqrtd) / a))
+    }
+}
+
+/// Blobby surface where the sum of the fields of the balls reaches the
+/// `threshold`, so that the balls close enough merge smoothly into one
+/// another. A lone ball is a sphere of radius `sqrt(1 - cbrt(threshold))`
+/// times its own.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Metaballs {
+    pub balls: Vec<Ball>,
+    pub threshold: Float,
+    pub material: Material,
+}
+
+impl Metaballs {
+    pub fn new(balls: Vec<Ball>, threshold: Float, material: Material) -> Self {
+        Self {
+            balls,
+            threshold,
+            material,
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(self.balls.iter().flat_map(|ball| {
+            let extent = Vec3(ball.radius, ball.radius, ball.radius);
+            [ball.center - extent, ball.center + extent]
+        }))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.balls.is_empty() {
+            return Err("the metaballs need at least one ball".into());
+        }
+        for (i, ball) in self.balls.iter().enumerate() {
+            if !ball.center.is_finite() {
+                return Err(format!("ball {}: the center must be finite", i));
+            }
+            if !(ball.radius > 0. && ball.radius.is_finite()) {
+                return Err(format!(
+                    "ball {}: the radius must be positive, found {}",
+                    i, ball.radius
+                ));
+            }
+        }
+        if !(self.threshold > 0. && self.threshold < 1.) {
+            return Err(format!(
+                "the threshold must be between 0 and 1, found {}",
+                self.threshold
+            ));
+        }
+
+        self.material.validate()
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        for ball in &mut self.balls {
+            ball.center += offset;
+        }
+    }
+
+    /// Field of the `balls` at the point `p`, minus the threshold so
+    /// that it is positive inside the surface.
+    fn field(&self, balls: &[Ball], p: Vec3) -> Float {
+        balls
+            .iter()
+            .map(|ball| {
+                let d = p - ball.center;
+                ball.field(Vec3::dot(d, d))
+            })
+            .sum::<Float>()
+            - self.threshold
+    }
+
+    /// Gradient of the field at the point `p`, pointing inwards.
+    fn gradient(&self, p: Vec3) -> Vec3 {
+        self.balls.iter().fold(Vec3::ZERO, |sum, ball| {
+            let d = p - ball.center;
+            let r2 = ball.radius * ball.radius;
+            let x = 1. - Vec3::dot(d, d) / r2;
+
+            match x > 0. {
+                true => sum + (-6. * x * x / r2) * d,
+                false => sum,
+            }
+        })
+    }
+
+    /// Distance of the first crossing of the surface by the ray `r`
+    /// within `t_range`.
+    ///
+    /// The ray marches through the balls it crosses, by steps no longer
+    /// than the distance to the surface that the field guarantees from
+    /// the steepest slope of the balls. The last step crossing the
+    /// surface is then bisected.
+    fn intersect(&self, r: &Ray, t_range: Interval) -> Option<Float> {
+        let mut span = Interval::EMPTY;
+        let mut balls = Vec::new();
+        let mut slope = 0.;
+        for ball in &self.balls {
+            if let Some(s) = ball.span(r) {
+                if s.max > t_range.min && s.min < t_range.max {
+                    span = Interval::new(span.min.min(s.min), span.max.max(s.max));
+                    balls.push(*ball);
+                    slope += MAX_SLOPE / ball.radius;
+                }
+            }
+        }
+
+        let span = Interval::new(span.min.max(t_range.min), span.max.min(t_range.max));
+        if span.is_empty() {
+            return None;
+        }
+
+        let speed = Vec3::norm(r.dir);
+        // The precision of the hits, relative to the size of the balls.
+        let min_step = 1e-4 * balls.iter().map(|b| b.radius).fold(0., Float::max) / speed;
+
+        let mut t = span.min;
+        let mut f = self.field(&balls, r.at(t));
+        let inside = f > 0.;
+        for _ in 0..MAX_STEPS {
+            let next = t + (f.abs() / slope / speed).max(min_step);
+            if next > span.max {
+                return None;
+            }
+
+            let next_f = self.field(&balls, r.at(next));
+            if (next_f > 0.) != inside {
+                return Some(self.bisect(&balls, r, t, next, inside));
+            }
+
+            (t, f) = (next, next_f);
+        }
+
+        None
+    }
+
+    /// Refine the crossing of the surface between the distances `a`,
+    /// on the side given by `inside`, and `b`, on the other.
+    fn bisect(&self, balls: &[Ball], r: &Ray, mut a: Float, mut b: Float, inside: bool) -> Float {
+        for _ in 0..16 {
+            let mid = 0.5 * (a + b);
+            match (self.field(balls, r.at(mid)) > 0.) == inside {
+                true => a = mid,
+                false => b = mid,
+            }
+        }
+
+        b
+    }
+}
+
+impl Hit<Ray> for Metaballs {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let t = self.intersect(r, t_range)?;
+        let p = r.at(t);
+        let normal = Vec3::unit(-self.gradient(p));
+
+        Some(HitRecord::new(p, normal, t, &self.material, r))
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.intersect(r, t_range).is_some()
+    }
+}
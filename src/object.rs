@@ -0,0 +1,191 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    curve::Curves,
+    fractal::Fractal,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    mesh::Mesh,
+    metaballs::Metaballs,
+    plugin::Custom,
+    pointcloud::PointCloud,
+    ray::Ray,
+    sphere::Sphere,
+    vec3::Vec3,
+};
+
+/// Objects that can be placed in the world.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Object {
+    Sphere(Sphere),
+    Mesh(Mesh),
+    Metaballs(Metaballs),
+    Fractal(Fractal),
+    PointCloud(PointCloud),
+    Curves(Curves),
+    /// Shape registered with the [`plugin`](crate::plugin) API.
+    Custom(Custom),
+}
+
+impl Object {
+    /// Center of the object, the one of its bounds for meshes.
+    pub fn center(&self) -> Vec3 {
+        match self {
+            Self::Sphere(sphere) => sphere.center,
+            Self::Mesh(mesh) => mesh.bounds().center(),
+            Self::Metaballs(metaballs) => metaballs.bounds().center(),
+            Self::Fractal(fractal) => fractal.center,
+            Self::PointCloud(cloud) => cloud.bounds().center(),
+            Self::Curves(curves) => curves.bounds().center(),
+            Self::Custom(custom) => custom.bounds().center(),
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            Self::Sphere(sphere) => sphere.bounds(),
+            Self::Mesh(mesh) => mesh.bounds(),
+            Self::Metaballs(metaballs) => metaballs.bounds(),
+            Self::Fractal(fractal) => fractal.bounds(),
+            Self::PointCloud(cloud) => cloud.bounds(),
+            Self::Curves(curves) => curves.bounds(),
+            Self::Custom(custom) => custom.bounds(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Sphere(sphere) => sphere.validate(),
+            Self::Mesh(mesh) => {
+                mesh.triangles().iter().enumerate().try_for_each(|(i, t)| {
+                    t.validate().map_err(|e| format!("triangle {}: {}", i, e))
+                })
+            }
+            Self::Metaballs(metaballs) => metaballs.validate(),
+            Self::Fractal(fractal) => fractal.validate(),
+            Self::PointCloud(cloud) => cloud.validate(),
+            Self::Curves(curves) => curves.validate(),
+            Self::Custom(custom) => custom.validate(),
+        }
+    }
+
+    /// Apply `f` to the materials of the object, failing when a custom
+    /// shape can't be built with its new material.
+    pub fn map_materials(&mut self, mut f: impl FnMut(&mut Material)) -> Result<(), String> {
+        match self {
+            Self::Sphere(sphere) => f(&mut sphere.material),
+            Self::Mesh(mesh) => mesh.map_materials(f),
+            Self::Metaballs(metaballs) => f(&mut metaballs.material),
+            Self::Fractal(fractal) => f(&mut fractal.material),
+            Self::PointCloud(cloud) => f(&mut cloud.material),
+            Self::Curves(curves) => f(&mut curves.material),
+            Self::Custom(custom) => return custom.map_material(f),
+        }
+
+        Ok(())
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        match self {
+            Self::Sphere(sphere) => sphere.center += offset,
+            Self::Mesh(mesh) => mesh.translate(offset),
+            Self::Metaballs(metaballs) => metaballs.translate(offset),
+            Self::Fractal(fractal) => fractal.center += offset,
+            Self::PointCloud(cloud) => cloud.translate(offset),
+            Self::Curves(curves) => curves.translate(offset),
+            Self::Custom(custom) => custom.translate(offset),
+        }
+    }
+}
+
+/// Validate the `objects`, the errors name the index of the
+/// offending object.
+pub fn validate(objects: &[Object]) -> Result<(), String> {
+    objects.iter().enumerate().try_for_each(|(i, object)| {
+        let kind = match object {
+            Object::Sphere(_) => "sphere",
+            Object::Mesh(_) => "mesh",
+            Object::Metaballs(_) => "metaballs",
+            Object::Fractal(_) => "fractal",
+            Object::PointCloud(_) => "point cloud",
+            Object::Curves(_) => "curves",
+            Object::Custom(custom) => &custom.params().shape,
+        };
+
+        object
+            .validate()
+            .map_err(|e| format!("object {} ({}): {}", i, kind, e))
+    })
+}
+
+impl From<Sphere> for Object {
+    fn from(sphere: Sphere) -> Self {
+        Self::Sphere(sphere)
+    }
+}
+
+impl From<Mesh> for Object {
+    fn from(mesh: Mesh) -> Self {
+        Self::Mesh(mesh)
+    }
+}
+
+impl From<Metaballs> for Object {
+    fn from(metaballs: Metaballs) -> Self {
+        Self::Metaballs(metaballs)
+    }
+}
+
+impl From<Fractal> for Object {
+    fn from(fractal: Fractal) -> Self {
+        Self::Fractal(fractal)
+    }
+}
+
+impl From<PointCloud> for Object {
+    fn from(cloud: PointCloud) -> Self {
+        Self::PointCloud(cloud)
+    }
+}
+
+impl From<Curves> for Object {
+    fn from(curves: Curves) -> Self {
+        Self::Curves(curves)
+    }
+}
+
+impl From<Custom> for Object {
+    fn from(custom: Custom) -> Self {
+        Self::Custom(custom)
+    }
+}
+
+impl Hit<Ray> for Object {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        match self {
+            Self::Sphere(sphere) => sphere.hit(r, t_range),
+            Self::Mesh(mesh) => mesh.hit(r, t_range),
+            Self::Metaballs(metaballs) => metaballs.hit(r, t_range),
+            Self::Fractal(fractal) => fractal.hit(r, t_range),
+            Self::PointCloud(cloud) => cloud.hit(r, t_range),
+            Self::Curves(curves) => curves.hit(r, t_range),
+            Self::Custom(custom) => custom.hit(r, t_range),
+        }
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        match self {
+            Self::Sphere(sphere) => sphere.hit_any(r, t_range),
+            Self::Mesh(mesh) => mesh.hit_any(r, t_range),
+            Self::Metaballs(metaballs) => metaballs.hit_any(r, t_range),
+            Self::Fractal(fractal) => fractal.hit_any(r, t_range),
+            Self::PointCloud(cloud) => cloud.hit_any(r, t_range),
+            Self::Curves(curves) => curves.hit_any(r, t_range),
+            Self::Custom(custom) => custom.hit_any(r, t_range),
+        }
+    }
+}
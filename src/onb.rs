@@ -0,0 +1,31 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis, whose `w` axis is a given direction like the
+/// normal of a surface, translating the directions sampled around the
+/// z axis to the world.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Basis around the direction `n`, which doesn't need to be a unit vector.
+    pub fn new(n: Vec3) -> Self {
+        let w = Vec3::unit(n);
+        let a = match w.x().abs() > 0.9 {
+            true => Vec3(0., 1., 0.),
+            false => Vec3(1., 0., 0.),
+        };
+        let v = Vec3::unit(Vec3::cross(w, a));
+        let u = Vec3::cross(w, v);
+
+        Self { u, v, w }
+    }
+
+    /// The world direction of the local coordinates `a`.
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+}
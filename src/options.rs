@@ -0,0 +1,901 @@
+use std::{
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    value_parser, Parser, Subcommand,
+};
+use tracing::Level;
+
+use raycasting_rs::{
+    aov::AovPass,
+    camera::CameraSettings,
+    colorspace::ColorSpace,
+    denoise::Denoiser,
+    description::{CameraDescription, SceneDescription, DEFAULT_ASPECT_RATIO},
+    film::PixelFilter,
+    firefly::FireflyFilter,
+    import::{self, ImportedScene},
+    lut::Lut,
+    object,
+    output::{BitDepth, ImageFormat, RowWriter, DEFAULT_QUALITY},
+    post::PostEffect,
+    scene::Scene,
+    scenes::MaterialWeights,
+    tonemap::ToneMap,
+    vec3::{Float, Vec3},
+    world::WorldFile,
+};
+
+use crate::{
+    config::{self, RenderConfig},
+    logging,
+    preset::Preset,
+    preview::PreviewMode,
+    video,
+};
+
+/// Options taken from the command line.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Where to save the image, when missing the image
+    /// is written as PPM on the standard output.
+    pub output: Option<PathBuf>,
+    pub format: ImageFormat,
+    pub tone_map: ToneMap,
+    /// Color grading applied after the tone mapping.
+    pub lut: Option<Lut>,
+    /// Working color space of the light.
+    pub color_space: ColorSpace,
+    /// Exposure compensation in EV stops, applied before tone mapping.
+    pub exposure: Float,
+    pub firefly: FireflyFilter,
+    /// Simplify the meshes merging their vertices closer than this many
+    /// pixels, see [`lod::simplify`](raycasting_rs::lod::simplify).
+    pub lod: Option<Float>,
+    /// Reconstruction filter weighting the samples of each pixel.
+    pub filter: PixelFilter,
+    /// Effects applied to the linear image once rendered.
+    pub post: Vec<PostEffect>,
+    pub denoiser: Option<Denoiser>,
+    /// Auxiliary passes saved next to the output image.
+    pub aov_passes: Vec<AovPass>,
+    /// Record the background as transparent instead of the sky color.
+    pub transparent: bool,
+    /// Show the image while it is rendered.
+    pub preview: Option<PreviewMode>,
+    /// Move the camera around from the preview window instead
+    /// of rendering the image.
+    pub interactive: bool,
+    /// Save the image rendered so far every interval.
+    pub snapshot_interval: Option<Duration>,
+    /// Number of frames of the animation, each one saved
+    /// as a numbered image.
+    pub frames: u32,
+    /// Frames per second of the animation.
+    pub fps: Float,
+    /// Encode the frames of the animation as a video.
+    pub video: bool,
+    /// Write each row to the output as soon as it is rendered,
+    /// instead of keeping the whole image in memory.
+    pub stream: bool,
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Render the image in square tiles of this size, spilling the
+    /// finished ones to disk until the image is assembled.
+    pub tile_size: Option<u32>,
+    /// Render a stereo pair and combine it in a red/cyan anaglyph.
+    pub anaglyph: bool,
+    pub aspect_ratio: Float,
+    /// Number of rays traced for each pixel.
+    pub samples: u32,
+    /// Time after which the render of each image stops at the end of
+    /// its current pass, even with fewer samples.
+    pub time_limit: Option<Duration>,
+    /// Maximum number of bounces of each ray.
+    pub max_depth: u32,
+    /// Number of render threads, when missing one for each core.
+    pub threads: Option<u32>,
+    /// Each render thread stays on its own core.
+    pub pin_threads: bool,
+    /// Render with the lowest priority.
+    pub nice: bool,
+    pub scene: Scene,
+    /// Scene file rendered again every time it changes.
+    pub watch: Option<PathBuf>,
+    /// Service started instead of rendering the scene.
+    pub command: Option<Command>,
+    /// Report the size of the scene and estimate the render time,
+    /// instead of rendering the image.
+    pub dry_run: bool,
+    /// Render the scene in a white furnace and report how far it is
+    /// from white, instead of rendering the image.
+    pub furnace: bool,
+    /// Where to export the paths of the rays of `dump_pixels`, instead
+    /// of rendering the image.
+    pub dump_paths: Option<PathBuf>,
+    /// Pixels whose rays are exported, as `(x, y)`.
+    pub dump_pixels: Vec<(u32, u32)>,
+    /// Where to save the scene, as RON or JSON.
+    pub save_scene: Option<PathBuf>,
+    /// Report the rays traced and the work they took.
+    pub stats: bool,
+    /// Where to export the statistics of the render as JSON.
+    pub stats_json: Option<PathBuf>,
+    /// Vertical field of view in degrees.
+    pub v_fov: Float,
+    pub look_from: Vec3,
+    pub look_at: Vec3,
+    /// Direction pointing up in the image.
+    pub up: Vec3,
+    /// Aperture of the defocus blur cone in degrees.
+    pub defocus_angle: Float,
+    pub focus_dist: Float,
+}
+
+/// Render a field of spheres.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Where to save the image, the format is chosen from the
+    /// extension. When missing, a PPM image is written on the
+    /// standard output.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Quality of the lossy formats, in the range [1,100].
+    #[arg(short, long, default_value_t = DEFAULT_QUALITY,
+          value_parser = value_parser!(u8).range(1..=100))]
+    quality: u8,
+    /// Bits per channel of the lossless formats.
+    #[arg(long, default_value = "8", value_parser = parse_bit_depth)]
+    bit_depth: BitDepth,
+    /// Operator mapping the radiance to displayable colors, clamp by default.
+    #[arg(long, value_parser = name_parser(&ToneMap::NAMES, ToneMap::from_name))]
+    tone_map: Option<ToneMap>,
+    /// `.cube` 3D LUT grading the colors after the tone mapping, not
+    /// applied to the linear formats.
+    #[arg(long)]
+    lut: Option<PathBuf>,
+    /// Linear color space where the light is computed, the colors of the
+    /// scene and of the images are in Rec. 709. Rec. 709 by default.
+    #[arg(long, value_parser = name_parser(&ColorSpace::NAMES, ColorSpace::from_name))]
+    color_space: Option<ColorSpace>,
+    /// Exposure compensation in EV stops, applied before tone mapping.
+    #[arg(long, allow_negative_numbers = true, value_parser = parse_finite)]
+    exposure: Option<Float>,
+    /// Clamp the luminance of each sample to this maximum.
+    #[arg(long, value_parser = parse_positive)]
+    clamp_luminance: Option<Float>,
+    /// Reject the samples brighter than the mean by this many
+    /// standard deviations.
+    #[arg(long, value_parser = parse_positive)]
+    reject_outliers: Option<Float>,
+    /// Simplify the meshes seen from afar, merging their vertices closer
+    /// than this many pixels, and replace the ones smaller than that by
+    /// a sphere. It cuts the intersection tests of sprawling scenes, at
+    /// the cost of details around the size of the pixels.
+    #[arg(long, value_parser = parse_positive)]
+    lod: Option<Float>,
+    /// Reconstruction filter weighting the samples of each pixel by
+    /// their distance from its center, box by default.
+    #[arg(long, value_parser = name_parser(&PixelFilter::NAMES, PixelFilter::from_name))]
+    filter: Option<PixelFilter>,
+    /// Denoise the image once rendered.
+    #[arg(long, value_parser = name_parser(&Denoiser::NAMES, Denoiser::from_name))]
+    denoise: Option<Denoiser>,
+    /// Auxiliary passes saved next to the output image.
+    #[arg(long, value_delimiter = ',',
+          value_parser = name_parser(&AovPass::NAMES, AovPass::from_name))]
+    aov: Vec<AovPass>,
+    /// Record the background as transparent instead of the sky color.
+    #[arg(long)]
+    transparent: bool,
+    /// Show the image while it is rendered.
+    #[arg(long, value_parser = name_parser(&PreviewMode::NAMES, PreviewMode::from_name))]
+    preview: Option<PreviewMode>,
+    /// Move the camera around from the preview window instead
+    /// of rendering the image.
+    #[arg(long)]
+    interactive: bool,
+    /// Save the image rendered so far at this interval, like `30s` or `5m`.
+    #[arg(long, value_parser = parse_duration)]
+    snapshot_interval: Option<Duration>,
+    /// Number of frames of the animation, each one saved
+    /// as a numbered image.
+    #[arg(long, default_value_t = 1, value_parser = value_parser!(u32).range(1..))]
+    frames: u32,
+    /// Frames per second of the animation.
+    #[arg(long, default_value_t = 24., value_parser = parse_positive)]
+    fps: Float,
+    /// Write each row to the output as soon as it is rendered.
+    #[arg(long)]
+    stream: bool,
+    /// Render the image in square tiles of this size, spilled to
+    /// disk until the image is assembled.
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    tile_size: Option<u32>,
+    /// Render a stereo pair and combine it in a red/cyan anaglyph.
+    #[arg(long)]
+    anaglyph: bool,
+    /// Preset of the resolution scale, samples, maximum depth and
+    /// denoising, which are overridden by the ones given explicitly.
+    #[arg(long, value_parser = name_parser(&Preset::NAMES, Preset::from_name))]
+    preset: Option<Preset>,
+    /// Width of the image in pixels, 800 by default.
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    width: Option<u32>,
+    /// Ratio between the width and the height of the image, either
+    /// as a number or as `width:height`, 19:9 by default.
+    #[arg(long, value_parser = parse_aspect_ratio)]
+    aspect_ratio: Option<Float>,
+    /// Number of rays traced for each pixel, 50 by default.
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    samples: Option<u32>,
+    /// Render each image in passes of increasing samples, stopping
+    /// once this time is over, as seconds or like `90s`, `10m` or `1h30m`.
+    #[arg(long, value_parser = parse_duration)]
+    time_limit: Option<Duration>,
+    /// Maximum number of bounces of each ray, 20 by default.
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    max_depth: Option<u32>,
+    /// Number of render threads, by default one for each core.
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+    /// Pin each render thread to one of the cores the process may run
+    /// on, only supported on Linux.
+    #[arg(long)]
+    pin_threads: bool,
+    /// Render with the lowest priority, so that the other programs stay
+    /// responsive during long renders. Only supported on Unix.
+    #[arg(long)]
+    nice: bool,
+    /// Scene to render, either a RON, JSON, PBRT, Mitsuba XML or glTF
+    /// file, a STL or PLY mesh or the name of a built-in scene.
+    #[arg(long, default_value = "random-spheres")]
+    scene: String,
+    /// Built-in scene to render, instead of the `--scene`.
+    #[arg(long, conflicts_with = "scene",
+          value_parser = name_parser(&Scene::NAMES, Scene::from_name))]
+    scene_name: Option<Scene>,
+    /// Render the scene file again every time it changes, with passes
+    /// of increasing samples.
+    #[arg(long)]
+    watch: bool,
+    /// Seed of the random-spheres, terrain and garden scenes, the same
+    /// seed always generates the same scene.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The random spheres are placed on a grid from minus to plus
+    /// this many units along x and z, 11 by default.
+    #[arg(long)]
+    grid_extent: Option<u32>,
+    /// Relative weights of the lambertian, metal and dielectric random
+    /// spheres as `lambertian,metal,dielectric`, 0.7,0.2,0.1 by default.
+    #[arg(long, value_parser = parse_material_weights)]
+    material_weights: Option<MaterialWeights>,
+    /// Report the size of the scene, the memory it takes and the render
+    /// time estimated from a small probe image, without rendering it.
+    #[arg(long)]
+    dry_run: bool,
+    /// Render the scene with white materials under a uniform white sky,
+    /// where any pixel not white is energy gained or lost by the
+    /// materials, and report the deviation of each of them from white.
+    #[arg(long, conflicts_with = "dry_run")]
+    furnace: bool,
+    /// Trace the rays of the pixels of `--dump-pixel` and export their
+    /// bounces as polylines to inspect in a 3D viewer, as PLY when the
+    /// file ends with `.ply` and as OBJ otherwise, without rendering
+    /// the image.
+    #[arg(long, conflicts_with_all = ["dry_run", "furnace"])]
+    dump_paths: Option<PathBuf>,
+    /// Pixel whose rays are exported by `--dump-paths` as `x,y` from the
+    /// top left corner, can be repeated. The center of the image by
+    /// default.
+    #[arg(long, requires = "dump_paths", value_parser = parse_pixel)]
+    dump_pixel: Vec<(u32, u32)>,
+    /// Save the camera, the objects and the render settings of the
+    /// scene, as RON when the file ends with `.ron` and as JSON otherwise.
+    #[arg(long)]
+    save_scene: Option<PathBuf>,
+    /// Report the rays traced, the intersection tests, the BVH nodes
+    /// visited and the number of bounces of the paths once done.
+    #[arg(long)]
+    stats: bool,
+    /// Export the statistics of the render to a JSON file.
+    #[arg(long)]
+    stats_json: Option<PathBuf>,
+    /// Least severe messages logged, one of error, warn, info, debug
+    /// or trace. The `RUST_LOG` variable overrides it when set.
+    #[arg(long, default_value = "info")]
+    log_level: Level,
+    /// Log the messages as JSON lines, for the automated runs.
+    #[arg(long)]
+    log_json: bool,
+    /// TOML file with the render settings, `render.toml` is loaded
+    /// from the working directory when present.
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Vertical field of view in degrees, 20 by default.
+    #[arg(long, value_parser = parse_positive)]
+    fov: Option<Float>,
+    /// Position of the camera as `x,y,z`, 13,2,3 by default.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vec3)]
+    look_from: Option<Vec3>,
+    /// Point looked at by the camera as `x,y,z`, the origin by default.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vec3)]
+    look_at: Option<Vec3>,
+    /// Direction pointing up in the image as `x,y,z`, 0,1,0 by default.
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vec3)]
+    up: Option<Vec3>,
+    /// Aperture of the defocus blur cone in degrees, 0 disables it
+    /// and 0.6 by default.
+    #[arg(long, value_parser = parse_non_negative)]
+    defocus_angle: Option<Float>,
+    /// Distance of the plane in perfect focus, 10 by default.
+    #[arg(long, value_parser = parse_positive)]
+    focus_dist: Option<Float>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Render the scenes posted to `/render` over HTTP, streaming back
+    /// progressive PNG images. The options given before the command
+    /// are used when missing from the scenes.
+    Serve {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Serve the gRPC API of `proto/render_jobs.proto`, through which
+    /// render jobs are submitted and their tiles and frames collected.
+    Grpc {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: String,
+    },
+}
+
+/// Parser of the values named by `names`, which are listed in the help.
+fn name_parser<T: Clone + Send + Sync + 'static>(
+    names: &'static [&'static str],
+    from_name: fn(&str) -> Option<T>,
+) -> impl TypedValueParser<Value = T> {
+    PossibleValuesParser::new(names).map(move |name| from_name(&name).unwrap())
+}
+
+fn parse_bit_depth(s: &str) -> Result<BitDepth, String> {
+    match s {
+        "8" => Ok(BitDepth::Eight),
+        "16" => Ok(BitDepth::Sixteen),
+        _ => Err("bit depth must be either 8 or 16".into()),
+    }
+}
+
+fn parse_finite(s: &str) -> Result<Float, String> {
+    s.parse()
+        .ok()
+        .filter(|x: &Float| x.is_finite())
+        .ok_or(format!("`{}` is not a number", s))
+}
+
+fn parse_positive(s: &str) -> Result<Float, String> {
+    match parse_finite(s)? {
+        x if x > 0. => Ok(x),
+        _ => Err("the value must be a positive number".into()),
+    }
+}
+
+fn parse_non_negative(s: &str) -> Result<Float, String> {
+    match parse_finite(s)? {
+        x if x >= 0. => Ok(x),
+        _ => Err("the value can't be negative".into()),
+    }
+}
+
+/// Parse a duration like `1h30m`, where each number is followed by
+/// its unit among `h`, `m` and `s`, or a number of seconds.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let error = || {
+        format!(
+            "expected a duration like `90s`, `10m` or `1h30m`, found `{}`",
+            s
+        )
+    };
+
+    if let Ok(seconds) = s.parse::<f32>() {
+        return match seconds > 0. && seconds.is_finite() {
+            true => Ok(Duration::from_secs_f32(seconds)),
+            false => Err("the duration must be positive".into()),
+        };
+    }
+
+    let mut seconds = 0.;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(error)?;
+        let value = rest[..end].parse::<f32>().map_err(|_| error())?;
+        let unit = match rest[end..].chars().next() {
+            Some('h') => 3600.,
+            Some('m') => 60.,
+            Some('s') => 1.,
+            _ => return Err(error()),
+        };
+
+        seconds += value * unit;
+        rest = &rest[end + 1..];
+    }
+
+    match seconds > 0. {
+        true => Ok(Duration::from_secs_f32(seconds)),
+        false => Err("the duration must be positive".into()),
+    }
+}
+
+fn parse_scene(s: &str) -> Result<Scene, String> {
+    let _span = tracing::debug_span!("load", scene = s).entered();
+    let path = Path::new(s);
+    // The imported objects are checked as a whole, so that the errors
+    // name the index of the offending object.
+    let imported = |scene: ImportedScene| match object::validate(&scene.objects) {
+        Ok(()) => Ok(Scene::Imported(Box::new(scene))),
+        Err(e) => Err(format!("invalid scene `{}`: {}", s, e)),
+    };
+
+    match Scene::from_name(s) {
+        Some(scene) => Ok(scene),
+        None if s.ends_with(".ron") => {
+            SceneDescription::load(path).map(|d| Scene::Described(Box::new(d)))
+        }
+        None if s.ends_with(".json") => {
+            WorldFile::load_json(path).map(|w| Scene::Loaded(Box::new(w)))
+        }
+        None if s.ends_with(".pbrt") => import::pbrt::load(path).and_then(imported),
+        None if s.ends_with(".xml") => import::mitsuba::load(path).and_then(imported),
+        None if s.ends_with(".gltf") || s.ends_with(".glb") => {
+            import::gltf::load(path).and_then(imported)
+        }
+        None if s.ends_with(".stl") => import::stl::load(path).and_then(imported),
+        None if s.ends_with(".ply") => import::ply::load(path).and_then(imported),
+        None => Err(format!(
+            "expected a .ron, .json, .pbrt, .xml, .gltf, .glb, .stl or .ply file \
+             or one of: {}",
+            Scene::NAMES.join(", ")
+        )),
+    }
+}
+
+fn parse_material_weights(s: &str) -> Result<MaterialWeights, String> {
+    let weights = s
+        .split(',')
+        .map(parse_non_negative)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match weights[..] {
+        [lambertian, metal, dielectric] if lambertian + metal + dielectric > 0. => {
+            Ok(MaterialWeights {
+                lambertian,
+                metal,
+                dielectric,
+            })
+        }
+        [_, _, _] => Err("at least one of the weights must be positive".into()),
+        _ => Err(format!(
+            "`{}` are not weights, expected `lambertian,metal,dielectric`",
+            s
+        )),
+    }
+}
+
+fn parse_pixel(s: &str) -> Result<(u32, u32), String> {
+    s.split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| format!("`{}` is not a pixel, expected `x,y`", s))
+}
+
+fn parse_aspect_ratio(s: &str) -> Result<Float, String> {
+    match s.split_once(':') {
+        Some((w, h)) => Ok(parse_positive(w)? / parse_positive(h)?),
+        None => parse_positive(s),
+    }
+}
+
+fn parse_vec3(s: &str) -> Result<Vec3, String> {
+    match s
+        .split(',')
+        .map(parse_finite)
+        .collect::<Result<Vec<_>, _>>()?[..]
+    {
+        [x, y, z] => Ok(Vec3(x, y, z)),
+        _ => Err(format!("`{}` is not a vector, expected `x,y,z`", s)),
+    }
+}
+
+impl Options {
+    /// Parse the command line, exiting with the usage on invalid arguments.
+    /// The scene and the config files are loaded again at every call.
+    pub fn parse() -> Result<Self, String> {
+        let Cli {
+            output,
+            quality,
+            bit_depth: depth,
+            tone_map,
+            lut,
+            color_space,
+            exposure,
+            clamp_luminance,
+            reject_outliers,
+            lod,
+            filter,
+            denoise: denoiser,
+            aov: aov_passes,
+            transparent,
+            preview,
+            interactive,
+            snapshot_interval,
+            frames,
+            fps,
+            stream,
+            tile_size,
+            anaglyph,
+            preset,
+            width,
+            aspect_ratio,
+            samples,
+            time_limit,
+            max_depth,
+            threads,
+            pin_threads,
+            nice,
+            scene,
+            scene_name,
+            watch,
+            seed,
+            grid_extent,
+            material_weights,
+            dry_run,
+            furnace,
+            dump_paths,
+            dump_pixel,
+            save_scene,
+            stats,
+            stats_json,
+            log_level,
+            log_json,
+            config,
+            fov,
+            look_from,
+            look_at,
+            up,
+            defocus_angle,
+            focus_dist,
+            command,
+        } = Cli::parse();
+        logging::init(log_level, log_json);
+
+        let watch = match watch {
+            true if Scene::from_name(&scene).is_some() || scene_name.is_some() => {
+                return Err("`--watch` needs a scene file as `--scene`".into())
+            }
+            true => Some(PathBuf::from(&scene)),
+            false => None,
+        };
+        let mut scene = match scene_name {
+            Some(scene) => scene,
+            None => parse_scene(&scene)?,
+        };
+        if let Scene::RandomSpheres {
+            seed: s,
+            grid_extent: g,
+            material_weights: w,
+        } = &mut scene
+        {
+            *s = seed.unwrap_or(*s);
+            *g = grid_extent.unwrap_or(*g);
+            *w = material_weights.unwrap_or(*w);
+        } else if grid_extent.is_some() || material_weights.is_some() {
+            return Err("`--grid-extent` and `--material-weights` only apply \
+                 to the random-spheres scene"
+                .into());
+        } else if let Scene::Terrain { seed: s } | Scene::Garden { seed: s } = &mut scene {
+            *s = seed.unwrap_or(*s);
+        } else if seed.is_some() {
+            return Err(
+                "`--seed` only applies to the random-spheres, terrain and garden scenes".into(),
+            );
+        }
+
+        let config = match config {
+            Some(path) => RenderConfig::load(&path)?,
+            None if Path::new(config::DEFAULT_PATH).exists() => {
+                RenderConfig::load(Path::new(config::DEFAULT_PATH))?
+            }
+            None => RenderConfig::default(),
+        };
+
+        // The settings of the command line override the ones of the
+        // config, which in turn override the ones of the scene.
+        let (camera, render) = scene.settings();
+
+        let output = output.or(config.output);
+        let tone_map = tone_map.or(config.tone_map).unwrap_or_default();
+        let lut = lut
+            .or(config.lut)
+            .map(|path| Lut::load(&path))
+            .transpose()?;
+        let filter = filter.or(config.filter).unwrap_or_default();
+        let color_space = color_space.or(config.color_space).unwrap_or_default();
+        let exposure = exposure.or(config.exposure).unwrap_or(0.);
+        let threads = threads.or(config.threads);
+        let pin_threads = pin_threads || config.pin_threads.unwrap_or(false);
+        // The preset scales the width of the config or of the scene,
+        // and takes precedence over their other settings.
+        let width = width.unwrap_or_else(|| {
+            let width = config.width.or(render.width).unwrap_or(800);
+            let scale = preset.map_or(1., Preset::resolution_scale);
+
+            ((scale * width as Float) as u32).max(1)
+        });
+        let aspect_ratio = aspect_ratio
+            .or(config.aspect_ratio)
+            .or(render.aspect_ratio)
+            .unwrap_or(DEFAULT_ASPECT_RATIO);
+        let samples = samples
+            .or(preset.map(Preset::samples))
+            .or(config.samples)
+            .or(render.samples)
+            .unwrap_or(50);
+        let max_depth = max_depth
+            .or(preset.map(Preset::max_depth))
+            .or(config.max_depth)
+            .or(render.max_depth)
+            .unwrap_or(20);
+        render
+            .post
+            .iter()
+            .try_for_each(PostEffect::validate)
+            .map_err(|e| format!("invalid post effect: {}", e))?;
+        let camera = CameraDescription {
+            look_from: look_from.map(Into::into).or(camera.look_from),
+            look_at: look_at.map(Into::into).or(camera.look_at),
+            up: up.map(Into::into).or(camera.up),
+            fov: fov.or(camera.fov),
+            defocus_angle: defocus_angle.or(camera.defocus_angle),
+            focus_dist: focus_dist.or(camera.focus_dist),
+        }
+        .settings(width, aspect_ratio);
+        let CameraSettings {
+            v_fov: fov,
+            look_from,
+            look_at,
+            vup: up,
+            defocus_angle,
+            focus_dist,
+            ..
+        } = camera;
+
+        if !(look_from.is_finite() && look_at.is_finite() && up.is_finite()) {
+            return Err(
+                "the position, target and up direction of the camera must be finite".into(),
+            );
+        }
+
+        if look_from == look_at {
+            return Err(format!(
+                "the camera looks at its own position {}, \
+                 `--look-from` and `--look-at` must differ",
+                look_from
+            ));
+        }
+
+        if Vec3::cross(up, look_from - look_at) == Vec3::ZERO {
+            return Err(format!(
+                "the up direction {} is parallel to the view direction of the camera",
+                up
+            ));
+        }
+
+        if !(fov > 0. && fov < 180.) {
+            return Err(format!(
+                "the field of view must be between 0 and 180 degrees, found {}",
+                fov
+            ));
+        }
+
+        let firefly = FireflyFilter {
+            max_luminance: clamp_luminance,
+            outlier_sigma: reject_outliers,
+        };
+
+        // Tiled images are streamed while they are assembled.
+        let streamed = stream || tile_size.is_some();
+
+        // The streamed images can't be denoised, so the denoiser of
+        // the preset is skipped for them.
+        let denoiser = denoiser.or(preset.and_then(Preset::denoiser).filter(|_| !streamed));
+
+        if let Some(d) = denoiser.filter(|d| !d.is_available()) {
+            return Err(format!(
+                "the {:?} denoiser is not available in this build, \
+                 rebuild with `--features oidn`",
+                d
+            ));
+        }
+
+        if (preview == Some(PreviewMode::Window) || interactive) && !cfg!(feature = "preview") {
+            return Err("the preview window requires building with `--features preview`".into());
+        }
+
+        if preview.is_some_and(|mode| mode != PreviewMode::Window) && !io::stderr().is_terminal() {
+            return Err("the terminal preview requires the standard error to be a terminal".into());
+        }
+
+        let video = output.as_deref().is_some_and(video::is_video);
+
+        let format = match &output {
+            // Frames are piped to ffmpeg with 8-bit channels.
+            Some(_) if video => ImageFormat::Png {
+                depth: BitDepth::Eight,
+            },
+            Some(path) => ImageFormat::from_path(path, quality, depth).ok_or(format!(
+                "unsupported image format `{}`, expected one of: ppm, png, tiff, exr, jpg, \
+                 webp or a mp4, webm, mkv, mov video \
+                 (16-bit output is only available for ppm, png and tiff)",
+                path.display()
+            ))?,
+            None => ImageFormat::Ppm { depth },
+        };
+
+        if video && (transparent || !aov_passes.is_empty()) {
+            return Err("videos can't be saved with transparency or aov passes".into());
+        }
+
+        if transparent && !format.supports_alpha() {
+            return Err("transparency requires a png, tiff, exr or webp `--output`".into());
+        }
+
+        if streamed && (video || !RowWriter::supports(format)) {
+            return Err("only ppm and png images can be streamed or rendered in tiles".into());
+        }
+
+        if streamed
+            && (transparent
+                || denoiser.is_some()
+                || !aov_passes.is_empty()
+                || !render.post.is_empty())
+        {
+            return Err(
+                "streamed or tiled images can't be saved with transparency, \
+                        denoising, aov passes or post effects"
+                    .into(),
+            );
+        }
+
+        if watch.is_some() && (interactive || frames > 1 || video) {
+            return Err("the watch mode can't be used with animations or `--interactive`".into());
+        }
+
+        if watch.is_some() && output.is_none() && preview.is_none() {
+            return Err("the watch mode needs either `--output` or `--preview`".into());
+        }
+
+        let missing_feature = match &command {
+            Some(Command::Serve { .. }) if !cfg!(feature = "serve") => Some("serve"),
+            Some(Command::Grpc { .. }) if !cfg!(feature = "grpc") => Some("grpc"),
+            _ => None,
+        };
+
+        if let Some(feature) = missing_feature {
+            return Err(format!(
+                "the `{}` command requires building with `--features {}`",
+                feature, feature
+            ));
+        }
+
+        if command.is_some()
+            && (output.is_some()
+                || preview.is_some()
+                || interactive
+                || watch.is_some()
+                || frames > 1
+                || streamed
+                || anaglyph
+                || !aov_passes.is_empty()
+                || snapshot_interval.is_some()
+                || dry_run
+                || furnace
+                || dump_paths.is_some()
+                || save_scene.is_some()
+                || stats
+                || stats_json.is_some())
+        {
+            return Err(
+                "the render services can't be used with `--output`, previews, animations, \
+                 streaming, tiles, anaglyphs, aov passes, `--dry-run`, `--furnace`, \
+                 `--dump-paths`, `--save-scene` or the statistics"
+                    .into(),
+            );
+        }
+
+        if anaglyph && streamed {
+            return Err("anaglyphs can't be streamed or rendered in tiles".into());
+        }
+
+        if time_limit.is_some() && streamed {
+            return Err(
+                "the images rendered in passes can't be streamed or rendered in tiles".into(),
+            );
+        }
+
+        if output.is_none() && tile_size.is_some() {
+            return Err("tiled images can only be saved together with `--output`".into());
+        }
+
+        if output.is_none() && frames > 1 {
+            return Err("animations can only be saved together with `--output`".into());
+        }
+
+        if output.is_none() && snapshot_interval.is_some() {
+            return Err("snapshots can only be saved together with `--output`".into());
+        }
+
+        if output.is_none() && !aov_passes.is_empty() {
+            return Err("aov passes can only be saved together with `--output`".into());
+        }
+
+        Ok(Self {
+            output,
+            format,
+            tone_map,
+            lut,
+            color_space,
+            exposure,
+            firefly,
+            lod,
+            filter,
+            post: render.post,
+            denoiser,
+            aov_passes,
+            transparent,
+            preview,
+            interactive,
+            snapshot_interval,
+            frames,
+            fps,
+            video,
+            stream,
+            width,
+            tile_size,
+            anaglyph,
+            aspect_ratio,
+            samples,
+            time_limit,
+            max_depth,
+            threads,
+            pin_threads,
+            nice,
+            scene,
+            watch,
+            command,
+            dry_run,
+            furnace,
+            dump_paths,
+            dump_pixels: dump_pixel,
+            save_scene,
+            stats,
+            stats_json,
+            v_fov: fov,
+            look_from,
+            look_at,
+            up,
+            defocus_angle,
+            focus_dist,
+        })
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use image::{
+    codecs::{jpeg::JpegEncoder, openexr::OpenExrEncoder, png::PngEncoder, tiff::TiffEncoder},
+    ExtendedColorType, ImageEncoder,
+};
+
+use crate::vec3::Color;
+
+pub const DEFAULT_QUALITY: u8 = 90;
+
+/// Number of bits used to store each color channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+    /// 32-bit floating point, only used by EXR.
+    Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Ppm {
+        depth: BitDepth,
+    },
+    Png {
+        depth: BitDepth,
+    },
+    Tiff {
+        depth: BitDepth,
+    },
+    /// OpenEXR, stores the linear radiance without tone mapping.
+    Exr,
+    /// Lossy JPEG, `quality` is in the range `[1, 100]`.
+    Jpeg {
+        quality: u8,
+    },
+    /// Lossy WebP, `quality` is in the range `[0, 100]`.
+    WebP {
+        quality: u8,
+    },
+}
+
+impl ImageFormat {
+    /// Guess the image format from the extension of `path`, lossy
+    /// formats will be encoded with the given `quality` while lossless
+    /// formats will use the given `depth`.
+    ///
+    /// Returns `None` if the extension is not known or if the format
+    /// does not support the requested depth. EXR images are always
+    /// stored as floating point.
+    pub fn from_path(path: &Path, quality: u8, depth: BitDepth) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        let format = match ext.as_str() {
+            "ppm" => Self::Ppm { depth },
+            "png" => Self::Png { depth },
+            "tif" | "tiff" => Self::Tiff { depth },
+            "exr" => return Some(Self::Exr),
+            "jpg" | "jpeg" => Self::Jpeg { quality },
+            "webp" => Self::WebP { quality },
+            _ => return None,
+        };
+
+        (format.depth() == depth).then_some(format)
+    }
+
+    pub fn depth(self) -> BitDepth {
+        match self {
+            Self::Ppm { depth } | Self::Png { depth } | Self::Tiff { depth } => depth,
+            Self::Exr => BitDepth::Float,
+            Self::Jpeg { .. } | Self::WebP { .. } => BitDepth::Eight,
+        }
+    }
+
+    /// Whether the format stores the linear radiance instead
+    /// of tone mapped colors.
+    pub fn is_linear(self) -> bool {
+        matches!(self, Self::Exr)
+    }
+
+    pub fn supports_alpha(self) -> bool {
+        matches!(
+            self,
+            Self::Png { .. } | Self::Tiff { .. } | Self::Exr | Self::WebP { .. }
+        )
+    }
+}
+
+/// Path next to `path` with the `suffix` added before the extension,
+/// e.g. `render.png` becomes `render.<suffix>.png`.
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.{}.{}", stem, suffix, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.{}", stem, suffix)),
+    }
+}
+
+/// Path of the numbered `frame` of an animation saved at `path`,
+/// e.g. `render.png` becomes `render_0001.png`.
+pub fn frame_path(path: &Path, frame: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    match path.extension() {
+        Some(ext) => {
+            path.with_file_name(format!("{}_{:04}.{}", stem, frame, ext.to_string_lossy()))
+        }
+        None => path.with_file_name(format!("{}_{:04}", stem, frame)),
+    }
+}
+
+/// Write the `pixels`, in row-major order, as a plain text PPM image.
+/// The pixels must already be encoded for display.
+pub fn write_ppm<W: Write>(
+    mut w: W,
+    depth: BitDepth,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+) -> io::Result<()> {
+    write_ppm_header(&mut w, depth, width, height)?;
+    write_ppm_pixels(&mut w, depth, pixels)?;
+
+    w.flush()
+}
+
+fn write_ppm_header<W: Write>(
+    w: &mut W,
+    depth: BitDepth,
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    match depth {
+        BitDepth::Eight => write!(w, "P3\n{} {}\n255\n", width, height),
+        // PPM stores at most 16 bits per channel.
+        BitDepth::Sixteen | BitDepth::Float => write!(w, "P3\n{} {}\n65535\n", width, height),
+    }
+}
+
+fn write_ppm_pixels<W: Write>(w: &mut W, depth: BitDepth, pixels: &[Color]) -> io::Result<()> {
+    for c in pixels {
+        match depth {
+            BitDepth::Eight => {
+                let [r, g, b] = c.to_rgb8();
+                writeln!(w, "{} {} {}", r, g, b)?;
+            }
+            BitDepth::Sixteen | BitDepth::Float => {
+                let [r, g, b] = c.to_rgb16();
+                writeln!(w, "{} {} {}", r, g, b)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+enum RowEncoder {
+    Ppm(BufWriter<Box<dyn Write + Send>>),
+    Png(Box<png::StreamWriter<'static, BufWriter<File>>>),
+}
+
+/// Write an image row by row as the rows are rendered, so that the
+/// whole image is never kept in memory. Rows can be completed out of
+/// order, only the ones ahead of the next row to write are buffered.
+pub struct RowWriter {
+    encoder: RowEncoder,
+    depth: BitDepth,
+    width: u32,
+    height: u32,
+    next: u32,
+    pending: BTreeMap<u32, Vec<Color>>,
+}
+
+impl RowWriter {
+    /// Whether the rows of `format` can be written one at a time.
+    pub fn supports(format: ImageFormat) -> bool {
+        matches!(format, ImageFormat::Ppm { .. } | ImageFormat::Png { .. })
+    }
+
+    /// Start writing the image to `path`, or as PPM to the standard
+    /// output when missing. The `format` must be supported.
+    pub fn new(
+        path: Option<&Path>,
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+    ) -> io::Result<Self> {
+        let depth = format.depth();
+
+        let encoder = match (path, format) {
+            (Some(path), ImageFormat::Png { .. }) => {
+                let mut encoder =
+                    png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(match depth {
+                    BitDepth::Eight => png::BitDepth::Eight,
+                    BitDepth::Sixteen | BitDepth::Float => png::BitDepth::Sixteen,
+                });
+
+                let stream = encoder
+                    .write_header()
+                    .and_then(|w| w.into_stream_writer())
+                    .map_err(io::Error::other)?;
+
+                RowEncoder::Png(Box::new(stream))
+            }
+            (path, _) => {
+                let w: Box<dyn Write + Send> = match path {
+                    Some(path) => Box::new(File::create(path)?),
+                    None => Box::new(io::stdout()),
+                };
+                let mut w = BufWriter::new(w);
+                write_ppm_header(&mut w, depth, width, height)?;
+
+                RowEncoder::Ppm(w)
+            }
+        };
+
+        Ok(Self {
+            encoder,
+            depth,
+            width,
+            height,
+            next: 0,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    /// Add the row `y`, the `row` must already be encoded for display.
+    pub fn write_row(&mut self, y: u32, row: Vec<Color>) -> io::Result<()> {
+        assert_eq!(row.len(), self.width as usize);
+        self.pending.insert(y, row);
+
+        while let Some(row) = self.pending.remove(&self.next) {
+            match &mut self.encoder {
+                RowEncoder::Ppm(w) => write_ppm_pixels(w, self.depth, &row)?,
+                RowEncoder::Png(w) => {
+                    let data = match self.depth {
+                        BitDepth::Eight => row.iter().flat_map(|c| c.to_rgb8()).collect::<Vec<_>>(),
+                        // PNG stores the samples in big endian.
+                        BitDepth::Sixteen | BitDepth::Float => row
+                            .iter()
+                            .flat_map(|c| c.to_rgb16())
+                            .flat_map(u16::to_be_bytes)
+                            .collect(),
+                    };
+
+                    w.write_all(&data)?
+                }
+            }
+
+            self.next += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Finish the image, all the rows must have been written.
+    pub fn finish(self) -> io::Result<()> {
+        assert_eq!(self.next, self.height, "not all the rows were written");
+
+        match self.encoder {
+            RowEncoder::Ppm(mut w) => w.flush(),
+            RowEncoder::Png(w) => w.finish().map_err(io::Error::other),
+        }
+    }
+}
+
+/// Save the `pixels`, in row-major order, to `path` encoded as `format`.
+/// The pixels must already be encoded for display, unless the format
+/// is linear. The straight `alpha` channel is stored only if the
+/// format supports it.
+pub fn save(
+    path: &Path,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+    alpha: Option<&[f32]>,
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    write(&mut w, format, width, height, pixels, alpha)?;
+    w.flush()
+}
+
+/// Write the `pixels` to `w` encoded as `format`, like [`save`]. The
+/// TIFF and EXR encoders need to seek back, so `w` is seekable.
+pub fn write<W: Write + Seek>(
+    mut w: W,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+    alpha: Option<&[f32]>,
+) -> io::Result<()> {
+    if let ImageFormat::Ppm { depth } = format {
+        return write_ppm(w, depth, width, height, pixels);
+    }
+
+    let alpha = alpha.filter(|_| format.supports_alpha());
+    let samples = pixels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, c)| c.to_f32().into_iter().chain(alpha.map(|alpha| alpha[i])));
+
+    let (data, color_type) = match (format.depth(), alpha.is_some()) {
+        (BitDepth::Eight, has_alpha) => (
+            samples.map(|c| (255.999 * c) as u8).collect::<Vec<_>>(),
+            match has_alpha {
+                false => ExtendedColorType::Rgb8,
+                true => ExtendedColorType::Rgba8,
+            },
+        ),
+        // The encoders expect the samples in native endianness.
+        (BitDepth::Sixteen, has_alpha) => (
+            samples
+                .map(|c| (65535.999 * c) as u16)
+                .flat_map(u16::to_ne_bytes)
+                .collect::<Vec<_>>(),
+            match has_alpha {
+                false => ExtendedColorType::Rgb16,
+                true => ExtendedColorType::Rgba16,
+            },
+        ),
+        (BitDepth::Float, has_alpha) => (
+            samples.flat_map(f32::to_ne_bytes).collect::<Vec<_>>(),
+            match has_alpha {
+                false => ExtendedColorType::Rgb32F,
+                true => ExtendedColorType::Rgba32F,
+            },
+        ),
+    };
+
+    match format {
+        ImageFormat::Ppm { .. } => unreachable!(),
+        ImageFormat::Png { .. } => PngEncoder::new(&mut w)
+            .write_image(&data, width, height, color_type)
+            .map_err(io::Error::other)?,
+        ImageFormat::Tiff { .. } => TiffEncoder::new(&mut w)
+            .write_image(&data, width, height, color_type)
+            .map_err(io::Error::other)?,
+        ImageFormat::Exr => OpenExrEncoder::new(&mut w)
+            .write_image(&data, width, height, color_type)
+            .map_err(io::Error::other)?,
+        ImageFormat::Jpeg { quality } => {
+            JpegEncoder::new_with_quality(&mut w, quality.clamp(1, 100))
+                .write_image(&data, width, height, color_type)
+                .map_err(io::Error::other)?
+        }
+        #[cfg(feature = "webp")]
+        ImageFormat::WebP { quality } => {
+            let encoder = match alpha {
+                Some(_) => webp::Encoder::from_rgba(&data, width, height),
+                None => webp::Encoder::from_rgb(&data, width, height),
+            };
+            let encoded = encoder
+                .encode_simple(false, quality.min(100) as f32)
+                .map_err(|e| io::Error::other(format!("webp encoding failed: {:?}", e)))?;
+
+            w.write_all(&encoded)?
+        }
+        #[cfg(not(feature = "webp"))]
+        ImageFormat::WebP { .. } => {
+            return Err(io::Error::other(
+                "webp images require building with `--features webp`",
+            ))
+        }
+    };
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Switch pausing the render threads of a [`Camera`](crate::camera::Camera),
+/// which stop before their next row or tile until resumed.
+#[derive(Debug, Default)]
+pub struct Pause {
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl Pause {
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        *self.paused.lock().unwrap_or_else(PoisonError::into_inner) = paused;
+
+        if !paused {
+            self.resumed.notify_all();
+        }
+    }
+
+    /// Pause the render if running and resume it otherwise, returning
+    /// whether it is now paused.
+    pub fn toggle(&self) -> bool {
+        // Flipped under a single lock, so that a signal and a key pressed
+        // together don't flip it the same way.
+        let mut paused = self.paused.lock().unwrap_or_else(PoisonError::into_inner);
+        *paused = !*paused;
+        self.resumed.notify_all();
+
+        *paused
+    }
+
+    /// Block the calling thread while the render is paused.
+    pub fn wait(&self) {
+        let paused = self.paused.lock().unwrap_or_else(PoisonError::into_inner);
+        drop(
+            self.resumed
+                .wait_while(paused, |paused| *paused)
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+    }
+}
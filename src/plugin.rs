@@ -0,0 +1,227 @@
+//! Shapes defined outside of the crate, which are registered by name
+//! so that the scenes can use them:
+//!
+//! ```
+//! use std::{collections::BTreeMap, sync::Arc};
+//!
+//! use raycasting_rs::{
+//!     aabb::Aabb,
+//!     hit::{Hit, HitRecord},
+//!     interval::Interval,
+//!     material::Material,
+//!     plugin::{self, Shape},
+//!     ray::Ray,
+//!     vec3::{Color, Float, Vec3},
+//!     world::World,
+//! };
+//!
+//! /// Horizontal disk at the height `y`.
+//! #[derive(Debug)]
+//! struct Disk {
+//!     y: Float,
+//!     radius: Float,
+//!     material: Material,
+//! }
+//!
+//! impl Hit<Ray> for Disk {
+//!     fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+//!         let t = (self.y - r.start.y()) / r.dir.y();
+//!         let p = r.at(t);
+//!
+//!         (t_range.surrounds(t) && p.x() * p.x() + p.z() * p.z() <= self.radius * self.radius)
+//!             .then(|| HitRecord::new(p, Vec3(0., 1., 0.), t, &self.material, r))
+//!     }
+//! }
+//!
+//! impl Shape for Disk {
+//!     fn bounds(&self) -> Aabb {
+//!         let r = self.radius;
+//!         Aabb::new(Vec3(-r, self.y - 1e-3, -r), Vec3(r, self.y + 1e-3, r))
+//!     }
+//! }
+//!
+//! plugin::register_shape("disk", |params, material| {
+//!     let param = |name| params.get(name).copied().ok_or(format!("missing `{}`", name));
+//!     let disk = Disk { y: param("y")?, radius: param("radius")?, material };
+//!
+//!     Ok(Arc::new(disk) as Arc<dyn Shape>)
+//! });
+//!
+//! let params = BTreeMap::from([("y".into(), 0.), ("radius".into(), 2.)]);
+//! let disk = plugin::custom("disk", params, Material::emissive(Color::WHITE)).unwrap();
+//! let world = World::builder().add(disk).build().unwrap();
+//! ```
+//!
+//! The custom objects are saved with the name of their shape and its
+//! parameters, and built again when loaded. Materials still have to be
+//! one of the built-in ones.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Float, Vec3},
+};
+
+/// Shape that can be placed in the world like the built-in ones.
+pub trait Shape: Hit<Ray> + fmt::Debug + Send + Sync {
+    /// Box enclosing the whole shape, used to skip the rays missing it.
+    fn bounds(&self) -> Aabb;
+
+    /// Check that the shape can be rendered.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Build a shape from its parameters, failing on the invalid ones.
+pub type ShapeFactory = fn(&BTreeMap<String, Float>, Material) -> Result<Arc<dyn Shape>, String>;
+
+static SHAPES: RwLock<BTreeMap<String, ShapeFactory>> = RwLock::new(BTreeMap::new());
+
+/// Register the shape `name`, replacing the one with the same name.
+pub fn register_shape(name: &str, factory: ShapeFactory) {
+    SHAPES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name.into(), factory);
+}
+
+/// Names of the registered shapes.
+pub fn shape_names() -> Vec<String> {
+    SHAPES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Build the registered shape `name` with its `params`.
+pub fn custom(
+    name: &str,
+    params: BTreeMap<String, Float>,
+    material: Material,
+) -> Result<Custom, String> {
+    Custom::try_from(CustomParams {
+        shape: name.into(),
+        params,
+        material,
+        offset: Vec3::ZERO,
+    })
+}
+
+/// What a [`Custom`] object is built from.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CustomParams {
+    /// Name of the registered shape.
+    pub shape: String,
+    pub params: BTreeMap<String, Float>,
+    pub material: Material,
+    /// Translation of the shape since it was built.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    pub offset: Vec3,
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(v: &Vec3) -> bool {
+    *v == Vec3::ZERO
+}
+
+/// Object of a registered shape, which is moved by translating the
+/// rays hitting it instead of the shape itself.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "CustomParams", try_from = "CustomParams")
+)]
+pub struct Custom {
+    params: CustomParams,
+    shape: Arc<dyn Shape>,
+}
+
+impl TryFrom<CustomParams> for Custom {
+    type Error = String;
+
+    fn try_from(params: CustomParams) -> Result<Self, String> {
+        let factory = SHAPES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&params.shape)
+            .copied()
+            .ok_or_else(|| format!("the shape `{}` is not registered", params.shape))?;
+        let shape = factory(&params.params, params.material)
+            .map_err(|e| format!("the shape `{}`: {}", params.shape, e))?;
+
+        Ok(Self { params, shape })
+    }
+}
+
+impl From<Custom> for CustomParams {
+    fn from(custom: Custom) -> Self {
+        custom.params
+    }
+}
+
+impl Custom {
+    pub fn params(&self) -> &CustomParams {
+        &self.params
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let mut bounds = self.shape.bounds();
+        bounds.translate(self.params.offset);
+
+        bounds
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.shape.validate()
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        self.params.offset += offset;
+    }
+
+    /// Apply `f` to the material of the object, building the shape again
+    /// as its factory received the material. The object is left as it was
+    /// when the factory rejects the new material.
+    pub fn map_material(&mut self, f: impl FnOnce(&mut Material)) -> Result<(), String> {
+        let mut params = self.params.clone();
+        f(&mut params.material);
+
+        *self = Self::try_from(params)?;
+        Ok(())
+    }
+}
+
+impl Hit<Ray> for Custom {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let offset = self.params.offset;
+        let moved = Ray::new(r.start - offset, r.dir);
+
+        self.shape.hit(&moved, t_range).map(|mut hit| {
+            hit.p += offset;
+            hit
+        })
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        let moved = Ray::new(r.start - self.params.offset, r.dir);
+
+        self.shape.hit_any(&moved, t_range)
+    }
+}
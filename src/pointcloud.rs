@@ -0,0 +1,206 @@
+//! Point clouds, like the ones of LiDAR scans, drawn as small splats
+//! around their points.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Color, Float, Vec3},
+};
+
+/// Shape drawn around each point of a [`PointCloud`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Splat {
+    /// Disc facing the ray, which looks like a sphere from every side
+    /// but is flat shaded towards the viewer, as the splats of the scans
+    /// usually are.
+    #[default]
+    Disc,
+    /// Sphere, lit like any other.
+    Sphere,
+}
+
+/// Point of a [`PointCloud`], whose color replaces the one of the
+/// material when given.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Point {
+    pub position: Vec3,
+    pub color: Option<Color>,
+}
+
+/// Points drawn as splats of the same `radius`. Each cloud tests all its
+/// points, so the large ones are [`split`](Self::split) for the BVH.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PointCloud {
+    points: Vec<Point>,
+    pub radius: Float,
+    pub splat: Splat,
+    pub material: Material,
+    bounds: Aabb,
+}
+
+impl PointCloud {
+    pub fn new(points: Vec<Point>, radius: Float, splat: Splat, material: Material) -> Self {
+        let extent = Vec3(radius, radius, radius);
+        let bounds = Aabb::from_points(
+            points
+                .iter()
+                .flat_map(|p| [p.position - extent, p.position + extent]),
+        );
+
+        Self {
+            points,
+            radius,
+            splat,
+            material,
+            bounds,
+        }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.points.is_empty() {
+            return Err("the point cloud has no points".into());
+        }
+        for (i, point) in self.points.iter().enumerate() {
+            if !point.position.is_finite() {
+                return Err(format!("point {}: the position must be finite", i));
+            }
+            if !point.color.is_none_or(|c| c.rgb.is_finite()) {
+                return Err(format!("point {}: the color must be finite", i));
+            }
+        }
+        if !(self.radius > 0. && self.radius.is_finite()) {
+            return Err(format!(
+                "the radius must be positive, found {}",
+                self.radius
+            ));
+        }
+
+        self.material.validate()
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        for point in &mut self.points {
+            point.position += offset;
+        }
+        self.bounds.translate(offset);
+    }
+
+    /// Split the cloud into clouds of `size` points at most, halving
+    /// the points along their widest axis so that each cloud is compact.
+    pub fn split(self, size: usize) -> Vec<PointCloud> {
+        let mut clouds = Vec::new();
+        let mut parts = vec![self.points];
+
+        while let Some(mut points) = parts.pop() {
+            if points.len() <= size.max(1) {
+                clouds.push(Self::new(points, self.radius, self.splat, self.material));
+                continue;
+            }
+
+            let bounds = Aabb::from_points(points.iter().map(|p| p.position));
+            let Vec3(x, y, z) = bounds.max - bounds.min;
+            let key = |p: &Point| match (x >= y && x >= z, y >= z) {
+                (true, _) => p.position.0,
+                (false, true) => p.position.1,
+                (false, false) => p.position.2,
+            };
+            let half = points.len() / 2;
+            points.select_nth_unstable_by(half, |a, b| key(a).total_cmp(&key(b)));
+
+            let right = points.split_off(half);
+            parts.push(points);
+            parts.push(right);
+        }
+
+        clouds
+    }
+
+    /// Distance of the hit of the ray `r` with the splat of the `point`
+    /// within `t_range`, with the normal there.
+    fn intersect(&self, point: &Point, r: &Ray, t_range: Interval) -> Option<(Float, Vec3)> {
+        let oc = point.position - r.start;
+        let a = Vec3::dot(r.dir, r.dir);
+        let r2 = self.radius * self.radius;
+
+        match self.splat {
+            Splat::Disc => {
+                // The rays leaving a splat would hit it again, as it
+                // turns to face them.
+                if Vec3::dot(oc, oc) <= r2 || a.is_nan() || a == 0. {
+                    return None;
+                }
+
+                let t = Vec3::dot(oc, r.dir) / a;
+                let d = r.at(t) - point.position;
+                match t_range.surrounds(t) && Vec3::dot(d, d) <= r2 {
+                    true => Some((t, (-1. / a.sqrt()) * r.dir)),
+                    false => None,
+                }
+            }
+            Splat::Sphere => {
+                let half_b = Vec3::dot(r.dir, oc);
+                let discriminant = half_b * half_b - a * (Vec3::dot(oc, oc) - r2);
+                if !(discriminant >= 0. && a > 0.) {
+                    return None;
+                }
+
+                let sqrtd = discriminant.sqrt();
+                let t = [(half_b - sqrtd) / a, (half_b + sqrtd) / a]
+                    .into_iter()
+                    .find(|t| t_range.surrounds(*t))?;
+                Some((t, (1. / self.radius) * (r.at(t) - point.position)))
+            }
+        }
+    }
+}
+
+impl Hit<Ray> for PointCloud {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        if !self.bounds.hit(r, t_range) {
+            return None;
+        }
+
+        let mut closest = None;
+        let mut max_t = t_range.max;
+
+        for point in &self.points {
+            if let Some((t, normal)) = self.intersect(point, r, t_range.until(max_t)) {
+                max_t = t;
+                closest = Some((t, normal, point));
+            }
+        }
+
+        closest.map(|(t, normal, point)| {
+            let record = HitRecord::new(r.at(t), normal, t, &self.material, r);
+            match point.color {
+                Some(color) => record.with_color(color),
+                None => record,
+            }
+        })
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.bounds.hit(r, t_range)
+            && self
+                .points
+                .iter()
+                .any(|point| self.intersect(point, r, t_range).is_some())
+    }
+}
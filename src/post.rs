@@ -0,0 +1,340 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buffer::ImageBuffer,
+    vec3::{Color, Float},
+};
+
+/// Effects applied to the linear image once rendered, before the tone
+/// mapping, listed in the render settings of the scene:
+///
+/// ```ron
+/// render: (
+///     post: [
+///         Bloom(threshold: 1.5, intensity: 0.2),
+///         Vignette(strength: 0.4),
+///         ChromaticAberration(amount: 0.005),
+///         WhiteBalance(temperature: 3200),
+///         Grain(),
+///     ],
+/// ),
+/// ```
+///
+/// The effects are applied in the order they are listed, the missing
+/// parameters take their default value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub enum PostEffect {
+    /// Glow spreading around the pixels brighter than `threshold`, like
+    /// the light scattered in a real lens.
+    Bloom {
+        /// Luminance above which the pixels glow.
+        #[cfg_attr(feature = "serde", serde(default = "default_threshold"))]
+        threshold: Float,
+        /// Fraction of the excess light added back as glow.
+        #[cfg_attr(feature = "serde", serde(default = "default_intensity"))]
+        intensity: Float,
+        /// Size of the glow as a fraction of the width of the image.
+        #[cfg_attr(feature = "serde", serde(default = "default_radius"))]
+        radius: Float,
+    },
+    /// Darkening of the corners of the image.
+    Vignette {
+        /// Fraction of the light lost in the corners.
+        #[cfg_attr(feature = "serde", serde(default = "default_strength"))]
+        strength: Float,
+    },
+    /// Lateral chromatic aberration, the red and blue fringes on the
+    /// edges towards the corners of a cheap lens.
+    ChromaticAberration {
+        /// Difference of magnification of the red and blue channels
+        /// from the green one, negative to swap the fringes.
+        #[cfg_attr(feature = "serde", serde(default = "default_aberration"))]
+        amount: Float,
+    },
+    /// White balance neutralizing the color of the light of the scene,
+    /// or giving it a warm or cold look.
+    WhiteBalance {
+        /// Color temperature of the light rendered white, in kelvin: a
+        /// low one like the 3200 K of a tungsten lamp cools the image
+        /// down, while a high one warms it up.
+        #[cfg_attr(feature = "serde", serde(default = "default_temperature"))]
+        temperature: Float,
+        /// Shift towards magenta, or towards green when negative, in
+        /// stops of green removed.
+        #[cfg_attr(feature = "serde", serde(default))]
+        tint: Float,
+    },
+    /// Random noise like the grain of a film.
+    Grain {
+        /// Standard deviation of the noise, relative to the color.
+        #[cfg_attr(feature = "serde", serde(default = "default_amount"))]
+        amount: Float,
+        /// Seed of the noise, the same seed gives the same grain.
+        #[cfg_attr(feature = "serde", serde(default))]
+        seed: u64,
+    },
+}
+
+#[cfg(feature = "serde")]
+fn default_threshold() -> Float {
+    1.
+}
+
+#[cfg(feature = "serde")]
+fn default_intensity() -> Float {
+    0.1
+}
+
+#[cfg(feature = "serde")]
+fn default_radius() -> Float {
+    0.01
+}
+
+#[cfg(feature = "serde")]
+fn default_strength() -> Float {
+    0.5
+}
+
+#[cfg(feature = "serde")]
+fn default_aberration() -> Float {
+    0.003
+}
+
+#[cfg(feature = "serde")]
+fn default_temperature() -> Float {
+    6500.
+}
+
+#[cfg(feature = "serde")]
+fn default_amount() -> Float {
+    0.05
+}
+
+impl PostEffect {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Self::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => {
+                if ![threshold, intensity, radius]
+                    .iter()
+                    .all(|x| (0. ..).contains(x))
+                {
+                    return Err("the bloom threshold, intensity and radius must be positive".into());
+                }
+            }
+            Self::Vignette { strength } => {
+                if !(0. ..=1.).contains(&strength) {
+                    return Err(format!(
+                        "the vignette strength must be between 0 and 1, found {}",
+                        strength
+                    ));
+                }
+            }
+            Self::ChromaticAberration { amount } => {
+                if !(-0.1..=0.1).contains(&amount) {
+                    return Err(format!(
+                        "the chromatic aberration must be between -0.1 and 0.1, found {}",
+                        amount
+                    ));
+                }
+            }
+            Self::WhiteBalance { temperature, tint } => {
+                // Below 2000 K the light has almost no blue to balance.
+                if !(2000. ..=25000.).contains(&temperature) {
+                    return Err(format!(
+                        "the white balance temperature must be between 2000 and 25000 K, found {}",
+                        temperature
+                    ));
+                }
+                if !(-2. ..=2.).contains(&tint) {
+                    return Err(format!(
+                        "the white balance tint must be between -2 and 2, found {}",
+                        tint
+                    ));
+                }
+            }
+            Self::Grain { amount, .. } => {
+                if !(0. ..).contains(&amount) {
+                    return Err(format!(
+                        "the grain amount must be positive, found {}",
+                        amount
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the effect to the linear colors of the `image`.
+    pub fn apply(&self, image: &mut ImageBuffer) {
+        match *self {
+            Self::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => bloom(image, threshold, intensity, radius),
+            Self::Vignette { strength } => vignette(image, strength),
+            Self::ChromaticAberration { amount } => chromatic_aberration(image, amount),
+            Self::WhiteBalance { temperature, tint } => white_balance(image, temperature, tint),
+            Self::Grain { amount, seed } => grain(image, amount, seed),
+        }
+    }
+}
+
+/// Apply the `effects` to the `image` in order.
+pub fn apply(effects: &[PostEffect], image: &mut ImageBuffer) {
+    for effect in effects {
+        let _span = tracing::debug_span!("post", effect = ?effect).entered();
+        effect.apply(image);
+    }
+}
+
+fn bloom(image: &mut ImageBuffer, threshold: Float, intensity: Float, radius: Float) {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let radius = (radius * width as Float).round().max(1.) as usize;
+
+    // Only the light above the threshold glows, so that the image isn't
+    // blurred all over.
+    let mut glow = image
+        .iter()
+        .map(|c| match c.luminance() {
+            l if l > threshold => ((l - threshold) / l) * *c,
+            _ => Color::BLACK,
+        })
+        .collect::<Vec<_>>();
+
+    // Three box blurs are close to a Gaussian blur, at a cost that
+    // doesn't grow with the radius.
+    let mut line = Vec::new();
+    for _ in 0..3 {
+        for y in 0..height {
+            box_blur(&mut glow, y * width, 1, width, radius, &mut line);
+        }
+        for x in 0..width {
+            box_blur(&mut glow, x, width, height, radius, &mut line);
+        }
+    }
+
+    for (c, g) in image.iter_mut().zip(glow) {
+        *c += intensity * g;
+    }
+}
+
+/// Blur the `len` pixels starting at `start`, `stride` apart, averaging
+/// each one with the `radius` pixels on each side. The pixels past the
+/// edges repeat the ones on the edges.
+fn box_blur(
+    pixels: &mut [Color],
+    start: usize,
+    stride: usize,
+    len: usize,
+    radius: usize,
+    line: &mut Vec<Color>,
+) {
+    line.clear();
+    line.extend((0..len).map(|i| pixels[start + i * stride]));
+
+    let at = |i: isize| line[i.clamp(0, len as isize - 1) as usize];
+    let r = radius as isize;
+    let scale = 1. / (2 * radius + 1) as Float;
+
+    let mut sum = (-r..=r).map(at).sum::<Color>();
+    for i in 0..len as isize {
+        pixels[start + i as usize * stride] = scale * sum;
+        sum += at(i + r + 1) - at(i - r);
+    }
+}
+
+fn vignette(image: &mut ImageBuffer, strength: Float) {
+    let (width, height) = (image.width() as Float, image.height() as Float);
+    let (cx, cy) = (width / 2., height / 2.);
+    // Squared distance of the corners from the center.
+    let corner = cx * cx + cy * cy;
+
+    for (y, row) in image.rows_mut().enumerate() {
+        for (x, c) in row.iter_mut().enumerate() {
+            let (dx, dy) = (x as Float + 0.5 - cx, y as Float + 0.5 - cy);
+            let d = (dx * dx + dy * dy) / corner;
+
+            // Smooth falloff from the center to the corners.
+            *c *= 1. - strength * d * d;
+        }
+    }
+}
+
+fn chromatic_aberration(image: &mut ImageBuffer, amount: Float) {
+    if image.is_empty() {
+        return;
+    }
+
+    let source = image.clone();
+    let (cx, cy) = (image.width() as Float / 2., image.height() as Float / 2.);
+
+    // Bilinear sample of the source at the point `(x, y)` in pixels,
+    // clamped to the edges.
+    let sample = |x: Float, y: Float| {
+        let x = (x - 0.5).clamp(0., (source.width() - 1) as Float);
+        let y = (y - 0.5).clamp(0., (source.height() - 1) as Float);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = (
+            (x0 + 1).min(source.width() - 1),
+            (y0 + 1).min(source.height() - 1),
+        );
+        let (tx, ty) = (x - x0 as Float, y - y0 as Float);
+
+        Color::lerp(
+            Color::lerp(source[(x0, y0)], source[(x1, y0)], tx),
+            Color::lerp(source[(x0, y1)], source[(x1, y1)], tx),
+            ty,
+        )
+    };
+
+    for (y, row) in image.rows_mut().enumerate() {
+        for (x, c) in row.iter_mut().enumerate() {
+            let (dx, dy) = (x as Float + 0.5 - cx, y as Float + 0.5 - cy);
+            // The red channel is magnified and the blue one shrunk, so
+            // their fringes grow with the distance from the center.
+            let scaled = |scale: Float| sample(cx + scale * dx, cy + scale * dy);
+
+            c.rgb.0 = scaled(1. / (1. + amount)).rgb.0;
+            c.rgb.2 = scaled(1. / (1. - amount)).rgb.2;
+        }
+    }
+}
+
+fn white_balance(image: &mut ImageBuffer, temperature: Float, tint: Float) {
+    // The gains turn the light of the `temperature` into the one of the
+    // white point of sRGB, keeping the green of the image.
+    let light = Color::from_temperature(temperature);
+    let white = Color::from_temperature(6504.);
+    let gains = Color::new((
+        (white.rgb.0 / light.rgb.0) * (light.rgb.1 / white.rgb.1),
+        Float::powf(2., -tint),
+        (white.rgb.2 / light.rgb.2) * (light.rgb.1 / white.rgb.1),
+    ));
+
+    for c in image.iter_mut() {
+        *c = Color::blend(*c, gains);
+    }
+}
+
+fn grain(image: &mut ImageBuffer, amount: Float, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for c in image.iter_mut() {
+        // Sum of uniform numbers with a zero mean and a unit variance,
+        // close enough to a normal distribution for the eye.
+        let noise = (0..4).map(|_| rng.gen::<Float>()).sum::<Float>() - 2.;
+        let noise = noise * Float::sqrt(3.);
+
+        *c = ((1. + amount * noise).max(0.)) * *c;
+    }
+}
@@ -0,0 +1,59 @@
+use raycasting_rs::denoise::Denoiser;
+use raycasting_rs::vec3::Float;
+
+/// Bundle of render settings trading quality for speed, the ones
+/// given explicitly take precedence over the preset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// Quick preview at a quarter of the resolution.
+    Draft,
+    Medium,
+    /// Slow render at full resolution.
+    Final,
+}
+
+impl Preset {
+    pub const NAMES: [&'static str; 3] = ["draft", "medium", "final"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "draft" => Some(Self::Draft),
+            "medium" => Some(Self::Medium),
+            "final" => Some(Self::Final),
+            _ => None,
+        }
+    }
+
+    /// Scale of the width of the image.
+    pub fn resolution_scale(self) -> Float {
+        match self {
+            Self::Draft => 0.25,
+            Self::Medium => 0.5,
+            Self::Final => 1.,
+        }
+    }
+
+    pub fn samples(self) -> u32 {
+        match self {
+            Self::Draft => 4,
+            Self::Medium => 64,
+            Self::Final => 1000,
+        }
+    }
+
+    pub fn max_depth(self) -> u32 {
+        match self {
+            Self::Draft => 8,
+            Self::Medium => 20,
+            Self::Final => 50,
+        }
+    }
+
+    /// Denoiser hiding the noise of the few samples.
+    pub fn denoiser(self) -> Option<Denoiser> {
+        match self {
+            Self::Draft | Self::Medium => Some(Denoiser::Atrous),
+            Self::Final => None,
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use raycasting_rs::{
+    output::{self, BitDepth, ImageFormat},
+    pause::Pause,
+    vec3::{Color, Vec3},
+};
+
+use crate::terminal::TerminalGraphics;
+
+/// Where the image is shown while it is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewMode {
+    Window,
+    /// Draw the image in the terminal, on the standard error.
+    Terminal(TerminalGraphics),
+}
+
+impl PreviewMode {
+    pub const NAMES: [&'static str; 5] = ["window", "terminal", "kitty", "sixel", "ansi"];
+
+    /// Parse the mode, `terminal` detects the graphics supported
+    /// by the terminal while the others force them.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "window" => Some(Self::Window),
+            "terminal" => Some(Self::Terminal(TerminalGraphics::detect())),
+            "kitty" => Some(Self::Terminal(TerminalGraphics::Kitty)),
+            "sixel" => Some(Self::Terminal(TerminalGraphics::Sixel)),
+            "ansi" => Some(Self::Terminal(TerminalGraphics::HalfBlocks)),
+            _ => None,
+        }
+    }
+}
+
+/// Pixels shown by the preview window and saved in the snapshots,
+/// the render threads write each row as soon as it is completed.
+pub struct PreviewBuffer {
+    width: u32,
+    height: u32,
+    /// Pixels in the `0RGB` format expected by the window.
+    pixels: Mutex<Vec<u32>>,
+}
+
+impl PreviewBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: Mutex::new(vec![0; (width * height) as usize]),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Write the row `y`, the `colors` must already be encoded for display.
+    pub fn set_row<I: Iterator<Item = Color>>(&self, y: u32, colors: I) {
+        self.set_span(0, y, colors);
+    }
+
+    /// Write the pixels of the row `y` starting from the column `x`,
+    /// the `colors` must already be encoded for display.
+    pub fn set_span<I: Iterator<Item = Color>>(&self, x: u32, y: u32, colors: I) {
+        let start = (y * self.width + x) as usize;
+        let mut pixels = self.pixels.lock().unwrap();
+
+        for (p, c) in pixels[start..start + (self.width - x) as usize]
+            .iter_mut()
+            .zip(colors)
+        {
+            let [r, g, b] = c.to_rgb8();
+            *p = u32::from_be_bytes([0, r, g, b]);
+        }
+    }
+
+    /// Copy of the current pixels, encoded for display.
+    pub fn snapshot(&self) -> Vec<Color> {
+        self.pixels
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|p| {
+                let [_, r, g, b] = p.to_be_bytes();
+                Color::from_f32([r, g, b].map(|c| c as f32 / 255.))
+            })
+            .collect()
+    }
+}
+
+/// Save the `buffer` as a PNG image at `path` every `interval`,
+/// until `done` returns true.
+pub fn save_snapshots<D: Fn() -> bool>(
+    buffer: &PreviewBuffer,
+    path: &Path,
+    interval: Duration,
+    done: D,
+) {
+    let mut last = Instant::now();
+
+    while !done() {
+        thread::sleep(Duration::from_millis(100));
+
+        if last.elapsed() < interval {
+            continue;
+        }
+
+        let format = ImageFormat::Png {
+            depth: BitDepth::Eight,
+        };
+        let pixels = buffer.snapshot();
+
+        if let Err(e) = output::save(path, format, buffer.width, buffer.height, &pixels, None) {
+            tracing::warn!("failed to save the snapshot: {}", e);
+        }
+
+        last = Instant::now();
+    }
+}
+
+/// Show the `buffer` in a window until `done` returns true, `P`
+/// pauses and resumes the render.
+///
+/// Returns `false` if the window was closed before the end.
+#[cfg(feature = "preview")]
+pub fn show<D: Fn() -> bool>(
+    buffer: &PreviewBuffer,
+    pause: &Pause,
+    done: D,
+) -> Result<bool, String> {
+    use minifb::{Key, KeyRepeat, Window, WindowOptions};
+
+    let (width, height) = (buffer.width as usize, buffer.height as usize);
+    let mut window = Window::new(
+        "raytracer-rs - preview",
+        width,
+        height,
+        WindowOptions::default(),
+    )
+    .map_err(|e| format!("failed to open the preview window: {}", e))?;
+    window.set_target_fps(10);
+
+    let mut frame = vec![0; width * height];
+
+    while !done() {
+        if !window.is_open() || window.is_key_down(Key::Escape) {
+            return Ok(false);
+        }
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            window.set_title(match pause.toggle() {
+                true => "raytracer-rs - preview (paused)",
+                false => "raytracer-rs - preview",
+            });
+        }
+
+        frame.copy_from_slice(&buffer.pixels.lock().unwrap());
+        window
+            .update_with_buffer(&frame, width, height)
+            .map_err(|e| format!("failed to update the preview window: {}", e))?;
+    }
+
+    Ok(true)
+}
+
+#[cfg(not(feature = "preview"))]
+pub fn show<D: Fn() -> bool>(_: &PreviewBuffer, _: &Pause, _: D) -> Result<bool, String> {
+    Err("the preview window requires building with `--features preview`".into())
+}
+
+/// Explore the scene from the preview window, the image is accumulated
+/// with a pass of `render_pass` at each frame and restarted every time
+/// the camera is moved.
+///
+/// `W`,`A`,`S`,`D` move the camera, `Q`,`E` move it down and up, while
+/// dragging with the left mouse button orbits around the target.
+#[cfg(feature = "preview")]
+pub fn explore<R, D>(
+    width: u32,
+    height: u32,
+    mut look_from: Vec3,
+    mut look_at: Vec3,
+    render_pass: R,
+    display: D,
+) -> Result<(), String>
+where
+    R: Fn(Vec3, Vec3) -> Vec<Color>,
+    D: Fn(Color) -> Color,
+{
+    use raycasting_rs::vec3::{consts::PI, Float};
+
+    use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+
+    const ORBIT_SPEED: Float = 0.01;
+    const MOVE_SPEED: Float = 0.05;
+
+    let (w, h) = (width as usize, height as usize);
+    let mut window = Window::new("raytracer-rs - explore", w, h, WindowOptions::default())
+        .map_err(|e| format!("failed to open the preview window: {}", e))?;
+
+    let buffer = PreviewBuffer::new(width, height);
+    let mut frame = vec![0; w * h];
+    let mut accumulated = vec![Color::BLACK; w * h];
+    let mut passes = 0;
+    let mut last_mouse: Option<(Float, Float)> = None;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let offset = look_from - look_at;
+        let forward = Vec3::unit(-offset);
+        let right = Vec3::unit(Vec3::cross(forward, Vec3(0., 1., 0.)));
+        let step = MOVE_SPEED * Vec3::norm(offset);
+
+        let mut moved = Vec3::ZERO;
+        for key in window.get_keys() {
+            moved += match key {
+                Key::W => step * forward,
+                Key::S => -step * forward,
+                Key::D => step * right,
+                Key::A => -step * right,
+                Key::E => Vec3(0., step, 0.),
+                Key::Q => Vec3(0., -step, 0.),
+                _ => Vec3::ZERO,
+            };
+        }
+
+        // Orbit around the target using spherical coordinates.
+        let mouse = window
+            .get_mouse_pos(MouseMode::Discard)
+            .filter(|_| window.get_mouse_down(MouseButton::Left))
+            .map(|(x, y)| (x as Float, y as Float));
+        let mut orbited = false;
+        if let (Some((x, y)), Some((last_x, last_y))) = (mouse, last_mouse) {
+            let r = Vec3::norm(offset);
+            let theta = Float::atan2(offset.x(), offset.z()) - ORBIT_SPEED * (x - last_x);
+            let phi =
+                (Float::acos(offset.y() / r) - ORBIT_SPEED * (y - last_y)).clamp(0.01, PI - 0.01);
+
+            look_from =
+                look_at + r * Vec3(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos());
+            orbited = x != last_x || y != last_y;
+        }
+        last_mouse = mouse;
+
+        if moved != Vec3::ZERO {
+            look_from += moved;
+            look_at += moved;
+        }
+
+        if orbited || moved != Vec3::ZERO {
+            accumulated.fill(Color::BLACK);
+            passes = 0;
+        }
+
+        for (acc, c) in accumulated.iter_mut().zip(render_pass(look_from, look_at)) {
+            *acc += c;
+        }
+        passes += 1;
+
+        let scale = 1. / passes as Float;
+        for (y, row) in accumulated.chunks_exact(w).enumerate() {
+            buffer.set_row(y as u32, row.iter().map(|c| display(scale * *c)));
+        }
+
+        frame.copy_from_slice(&buffer.pixels.lock().unwrap());
+        window
+            .update_with_buffer(&frame, w, h)
+            .map_err(|e| format!("failed to update the preview window: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "preview"))]
+pub fn explore<R, D>(_: u32, _: u32, _: Vec3, _: Vec3, _: R, _: D) -> Result<(), String>
+where
+    R: Fn(Vec3, Vec3) -> Vec<Color>,
+    D: Fn(Color) -> Color,
+{
+    Err("exploring the scene requires building with `--features preview`".into())
+}
@@ -0,0 +1,157 @@
+use numpy::{ndarray::Array3, IntoPyArray, PyArray3};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    camera::{Camera as CameraInner, CameraSettings},
+    description::{CameraDescription, DEFAULT_ASPECT_RATIO},
+    material::Material as MaterialInner,
+    object::{self, Object},
+    render::Tracer,
+    sphere::Sphere as SphereInner,
+    vec3::{Color, Float, Vec3},
+    world::World,
+};
+
+/// Material of the objects, built with one of its static methods.
+#[pyclass(name = "Material", frozen)]
+#[derive(Clone)]
+pub struct Material(MaterialInner);
+
+#[pymethods]
+impl Material {
+    #[staticmethod]
+    #[pyo3(signature = (color, fuzz = None))]
+    fn lambertian(color: (Float, Float, Float), fuzz: Option<Float>) -> Self {
+        Self(MaterialInner::lambertian(Color::new(color), fuzz))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (color, fuzz = None))]
+    fn metal(color: (Float, Float, Float), fuzz: Option<Float>) -> Self {
+        Self(MaterialInner::metal(Color::new(color), fuzz))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (refraction_index, fuzz = None))]
+    fn dielectric(refraction_index: Float, fuzz: Option<Float>) -> Self {
+        Self(MaterialInner::dielectric(refraction_index, fuzz))
+    }
+
+    /// Light source, its color can be brighter than white.
+    #[staticmethod]
+    fn emissive(color: (Float, Float, Float)) -> Self {
+        Self(MaterialInner::emissive(Color::new(color)))
+    }
+}
+
+#[pyclass(name = "Sphere", frozen)]
+#[derive(Clone)]
+pub struct Sphere(SphereInner);
+
+#[pymethods]
+impl Sphere {
+    #[new]
+    #[pyo3(signature = (center, radius, material, cull_backfaces = false))]
+    fn new(
+        center: (Float, Float, Float),
+        radius: Float,
+        material: Material,
+        cull_backfaces: bool,
+    ) -> Self {
+        Self(
+            SphereInner::new(Vec3::new(center), radius, material.0)
+                .with_backface_culling(cull_backfaces),
+        )
+    }
+}
+
+/// Camera with the same settings, and defaults, of the command line.
+#[pyclass(name = "Camera", frozen)]
+#[derive(Clone)]
+pub struct Camera(CameraSettings);
+
+#[pymethods]
+impl Camera {
+    #[new]
+    #[pyo3(signature = (
+        width = 400,
+        aspect_ratio = DEFAULT_ASPECT_RATIO,
+        look_from = None,
+        look_at = None,
+        up = None,
+        fov = None,
+        defocus_angle = None,
+        focus_dist = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        width: u32,
+        aspect_ratio: Float,
+        look_from: Option<(Float, Float, Float)>,
+        look_at: Option<(Float, Float, Float)>,
+        up: Option<(Float, Float, Float)>,
+        fov: Option<Float>,
+        defocus_angle: Option<Float>,
+        focus_dist: Option<Float>,
+    ) -> PyResult<Self> {
+        if width == 0 || aspect_ratio.is_nan() || aspect_ratio <= 0. {
+            return Err(PyValueError::new_err(
+                "the width and the aspect ratio must be positive",
+            ));
+        }
+
+        let description = CameraDescription {
+            look_from,
+            look_at,
+            up,
+            fov,
+            defocus_angle,
+            focus_dist,
+        };
+
+        Ok(Self(description.settings(width, aspect_ratio)))
+    }
+}
+
+/// Render the `objects` seen by the `camera`, returning the linear
+/// colors as an array of `height`x`width`x3 floats.
+#[pyfunction]
+#[pyo3(signature = (objects, camera, samples = 50, max_depth = 20, threads = None))]
+fn render<'py>(
+    py: Python<'py>,
+    objects: Vec<Sphere>,
+    camera: Camera,
+    samples: u32,
+    max_depth: u32,
+    threads: Option<u32>,
+) -> PyResult<Bound<'py, PyArray3<f32>>> {
+    let world = objects
+        .into_iter()
+        .map(|s| Object::Sphere(s.0))
+        .collect::<Vec<_>>();
+    object::validate(&world).map_err(PyValueError::new_err)?;
+
+    let camera = CameraInner::from(camera.0)
+        .threads(threads.filter(|t| *t > 0))
+        .quiet();
+    let (width, height) = (camera.width() as usize, camera.height() as usize);
+
+    // The other Python threads can run during the render.
+    let world = World::from(world);
+    let pixels = py.detach(|| Tracer::new(max_depth).render(&camera, &world, samples.max(1)));
+    let data = pixels.into_iter().flat_map(|c| c.to_f32()).collect();
+
+    Ok(Array3::from_shape_vec((height, width, 3), data)
+        .unwrap()
+        .into_pyarray(py))
+}
+
+#[pymodule]
+fn raycasting_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Material>()?;
+    m.add_class::<Sphere>()?;
+    m.add_class::<Camera>()?;
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+
+    Ok(())
+}
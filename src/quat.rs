@@ -0,0 +1,110 @@
+use std::ops::Mul;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::vec3::{Float, Vec3};
+
+/// Unit quaternion of a rotation, which composes without the gimbal
+/// lock of the Euler angles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quat {
+    /// Scalar part, the cosine of half the angle.
+    pub w: Float,
+    /// Vector part, the axis scaled by the sine of half the angle.
+    pub v: Vec3,
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self {
+        w: 1.,
+        v: Vec3::ZERO,
+    };
+
+    /// Rotation of `angle` degrees around the `axis`, counterclockwise
+    /// when the axis points to the viewer.
+    pub fn from_axis_angle(axis: Vec3, angle: Float) -> Self {
+        let (sin, cos) = (angle.to_radians() / 2.).sin_cos();
+
+        Self {
+            w: cos,
+            v: sin * Vec3::unit(axis),
+        }
+    }
+
+    pub fn dot(a: Self, b: Self) -> Float {
+        a.w * b.w + Vec3::dot(a.v, b.v)
+    }
+
+    /// The quaternion scaled to unit length, undoing the rounding
+    /// errors of many compositions.
+    pub fn normalize(self) -> Self {
+        let norm = Float::sqrt(Self::dot(self, self));
+
+        Self {
+            w: self.w / norm,
+            v: self.v / norm,
+        }
+    }
+
+    /// The opposite rotation.
+    pub fn inverse(self) -> Self {
+        Self {
+            w: self.w,
+            v: -self.v,
+        }
+    }
+
+    /// Rotate the vector `u`.
+    pub fn rotate(self, u: Vec3) -> Vec3 {
+        let t = 2. * Vec3::cross(self.v, u);
+
+        u + self.w * t + Vec3::cross(self.v, t)
+    }
+
+    /// Spherical interpolation from `a`, at `t` 0, to `b`, at `t` 1,
+    /// turning at constant speed along the shortest arc.
+    pub fn slerp(a: Self, b: Self, t: Float) -> Self {
+        // `b` and `-b` are the same rotation, the closest one is taken.
+        let (b, cos) = match Self::dot(a, b) {
+            cos if cos < 0. => (Self { w: -b.w, v: -b.v }, -cos),
+            cos => (b, cos),
+        };
+
+        // Nearly equal rotations are interpolated linearly, as the
+        // sine of their angle vanishes.
+        let (wa, wb) = match cos > 0.9995 {
+            true => (1. - t, t),
+            false => {
+                let theta = cos.acos();
+                let sin = theta.sin();
+
+                (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+            }
+        };
+
+        Self {
+            w: wa * a.w + wb * b.w,
+            v: wa * a.v + wb * b.v,
+        }
+        .normalize()
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Composition, `a * b` rotates by `b` and then by `a`.
+impl Mul for Quat {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            w: self.w * rhs.w - Vec3::dot(self.v, rhs.v),
+            v: self.w * rhs.v + rhs.w * self.v + Vec3::cross(self.v, rhs.v),
+        }
+    }
+}
@@ -1,6 +1,10 @@
-use crate::vec3::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::vec3::{Float, Vec3};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Ray {
     pub start: Vec3,
     pub dir: Vec3,
@@ -11,7 +15,27 @@ impl Ray {
         Self { start, dir }
     }
 
-    pub fn at(&self, time: f32) -> Vec3 {
-        self.start + Vec3::scale(time, self.dir)
+    /// Ray leaving the surface hit at `p` with the `normal`, started
+    /// slightly off the surface on the side of `dir`, so that it doesn't
+    /// hit the same surface again because of the rounding of `p`.
+    ///
+    /// The offset grows with the magnitude of `p`, whose rounding error
+    /// grows with it, instead of being a fixed distance which is too
+    /// large for small objects and too small for far away ones.
+    pub fn from_surface(p: Vec3, normal: Vec3, dir: Vec3) -> Self {
+        const ULPS: Float = 256.;
+
+        let magnitude = p.0.abs().max(p.1.abs()).max(p.2.abs()).max(1.);
+        let offset = ULPS * Float::EPSILON * magnitude;
+        let normal = match Vec3::dot(dir, normal) < 0. {
+            true => -normal,
+            false => normal,
+        };
+
+        Self::new(p + offset * normal, dir)
+    }
+
+    pub fn at(&self, time: Float) -> Vec3 {
+        self.start + time * self.dir
     }
 }
@@ -0,0 +1,125 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::vec3::{Color, Vec3};
+
+/// Bounces of a ray through the scene, recorded to inspect how it was
+/// reflected and refracted.
+#[derive(Debug, Clone, Default)]
+pub struct RayPath {
+    /// Start of the ray followed by the points where it hit a surface.
+    pub points: Vec<Vec3>,
+    /// Direction of the ray escaping the scene, `None` when the ray
+    /// was absorbed or cut short by the maximum depth.
+    pub escape: Option<Vec3>,
+    /// Linear color brought back by the ray.
+    pub color: Color,
+}
+
+impl RayPath {
+    /// Points of the polyline of the path. The escaping ray is drawn as
+    /// long as the last segment, or one unit long without one.
+    pub fn polyline(&self) -> Vec<Vec3> {
+        let mut points = self.points.clone();
+
+        if let (Some(dir), Some(last)) = (self.escape, points.last()) {
+            let length = match points[..] {
+                [.., a, b] => Vec3::norm(b - a),
+                _ => 1.,
+            };
+            points.push(*last + length * Vec3::unit(dir));
+        }
+
+        points
+    }
+}
+
+/// Save the `paths` traced through each pixel as polylines, as a PLY
+/// file when `path` ends with `.ply` and as OBJ otherwise.
+pub fn save(path: &Path, paths: &[((u32, u32), RayPath)]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    match path.extension().is_some_and(|ext| ext == "ply") {
+        true => write_ply(&mut w, paths)?,
+        false => write_obj(&mut w, paths)?,
+    }
+
+    w.flush()
+}
+
+/// Write the `paths` as the lines of an OBJ file, one object for each
+/// path named after its pixel.
+pub fn write_obj<W: Write>(mut w: W, paths: &[((u32, u32), RayPath)]) -> io::Result<()> {
+    // The vertices are numbered from 1 across the whole file.
+    let mut first = 1;
+
+    for (i, ((x, y), path)) in paths.iter().enumerate() {
+        let points = path.polyline();
+        if points.len() < 2 {
+            continue;
+        }
+
+        writeln!(w, "o pixel_{}_{}_path_{}", x, y, i)?;
+        for Vec3(px, py, pz) in &points {
+            writeln!(w, "v {} {} {}", px, py, pz)?;
+        }
+        write!(w, "l")?;
+        for v in first..first + points.len() {
+            write!(w, " {}", v)?;
+        }
+        writeln!(w)?;
+
+        first += points.len();
+    }
+
+    Ok(())
+}
+
+/// Write the `paths` as the edges of an ASCII PLY file, the vertices
+/// are colored with the color brought back by their path.
+pub fn write_ply<W: Write>(mut w: W, paths: &[((u32, u32), RayPath)]) -> io::Result<()> {
+    let polylines = paths
+        .iter()
+        .map(|(_, path)| (path.polyline(), path.color))
+        .filter(|(points, _)| points.len() >= 2)
+        .collect::<Vec<_>>();
+    let vertices = polylines
+        .iter()
+        .map(|(points, _)| points.len())
+        .sum::<usize>();
+
+    writeln!(w, "ply")?;
+    writeln!(w, "format ascii 1.0")?;
+    writeln!(w, "element vertex {}", vertices)?;
+    writeln!(w, "property float x")?;
+    writeln!(w, "property float y")?;
+    writeln!(w, "property float z")?;
+    writeln!(w, "property uchar red")?;
+    writeln!(w, "property uchar green")?;
+    writeln!(w, "property uchar blue")?;
+    writeln!(w, "element edge {}", vertices - polylines.len())?;
+    writeln!(w, "property int vertex1")?;
+    writeln!(w, "property int vertex2")?;
+    writeln!(w, "end_header")?;
+
+    for (points, color) in &polylines {
+        let [r, g, b] = color.to_gamma().to_rgb8();
+
+        for Vec3(px, py, pz) in points {
+            writeln!(w, "{} {} {} {} {} {}", px, py, pz, r, g, b)?;
+        }
+    }
+
+    let mut first = 0;
+    for (points, _) in &polylines {
+        for v in first..first + points.len() - 1 {
+            writeln!(w, "{} {}", v, v + 1)?;
+        }
+        first += points.len();
+    }
+
+    Ok(())
+}
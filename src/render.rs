@@ -0,0 +1,190 @@
+use crate::{
+    aov::Aov,
+    buffer::ImageBuffer,
+    camera::Camera,
+    colorspace::ColorSpace,
+    film::Sample,
+    firefly::FireflyFilter,
+    interval::Interval,
+    material::Scatter,
+    ray::Ray,
+    raypath::RayPath,
+    stats,
+    vec3::{Color, Float, Vec3},
+    world::World,
+};
+
+/// Trace the ray `r` through the `world` for at most `depth` bounces,
+/// returning its color and the AOVs of the first hit.
+pub fn ray_color(r: Ray, world: &World, depth: u32) -> (Color, Aov) {
+    trace(r, world, depth, None)
+}
+
+/// Trace the ray `r` like [`ray_color`], recording the points where it
+/// bounces.
+pub fn ray_path(r: Ray, world: &World, depth: u32) -> RayPath {
+    let mut path = RayPath::default();
+    path.color = trace(r, world, depth, Some(&mut path)).0;
+
+    path
+}
+
+fn trace(mut r: Ray, world: &World, depth: u32, mut path: Option<&mut RayPath>) -> (Color, Aov) {
+    if let Some(path) = path.as_deref_mut() {
+        path.points.push(r.start);
+    }
+
+    let mut attenuation = Color::WHITE;
+    let mut aov = None;
+    let mut bvh_visits = 0;
+    let finish = |color: Color, aov: Aov, bounces: u32| {
+        stats::count_path(bounces);
+        let aov = Aov {
+            bounces: bounces as Float,
+            ..aov
+        };
+
+        (color, aov)
+    };
+
+    for bounce in 0..depth {
+        stats::count(|s| match bounce {
+            0 => s.primary_rays += 1,
+            _ => s.secondary_rays += 1,
+        });
+
+        let (hit, visits) = world.traverse(&r, Interval::new(0., Float::INFINITY));
+        if bounce == 0 {
+            bvh_visits = visits;
+        }
+
+        if let Some((object, hit)) = hit {
+            if let Some(path) = path.as_deref_mut() {
+                path.points.push(hit.p);
+            }
+
+            let material = hit.surface();
+            aov.get_or_insert(Aov {
+                albedo: material.solid_color,
+                normal: if hit.front_face {
+                    hit.normal
+                } else {
+                    -hit.normal
+                },
+                depth: hit.t * Vec3::norm(r.dir),
+                position: hit.p,
+                motion: (0., 0.),
+                alpha: 1.,
+                object: Some(object as u32),
+                material: Some(material.id()),
+                bvh_visits: bvh_visits as Float,
+                // Only known once the path ends.
+                bounces: 0.,
+            });
+
+            let scatter = material.scatter(r, hit.normal, 1.0, hit.front_face);
+
+            match scatter {
+                Scatter::Absorbed { solid_color } => {
+                    return finish(
+                        Color::blend(attenuation, solid_color),
+                        aov.unwrap_or_default(),
+                        bounce,
+                    );
+                }
+                // A degenerate direction would make NaNs in the next hits.
+                Scatter::Scattered { direction, .. }
+                    if !direction.is_finite() || direction == Vec3::ZERO =>
+                {
+                    return finish(Color::BLACK, aov.unwrap_or_default(), bounce);
+                }
+                Scatter::Scattered {
+                    direction,
+                    attenuation: att,
+                    ..
+                } => {
+                    attenuation = Color::blend(att, attenuation);
+                    r = Ray::from_surface(hit.p, hit.geometric_normal, direction);
+                    continue;
+                }
+            };
+        }
+
+        if let Some(path) = path {
+            path.escape = Some(r.dir);
+        }
+
+        let final_color = world.background_color(r.dir);
+        let aov = aov.unwrap_or(Aov {
+            albedo: final_color,
+            bvh_visits: bvh_visits as Float,
+            ..Default::default()
+        });
+
+        return finish(Color::blend(attenuation, final_color), aov, bounce);
+    }
+
+    finish(Color::BLACK, aov.unwrap_or_default(), depth)
+}
+
+/// How the samples of each pixel are traced and combined.
+#[derive(Debug, Clone, Copy)]
+pub struct Tracer {
+    /// Maximum number of bounces of each ray.
+    pub max_depth: u32,
+    /// Camera rays escaping the scene are black and transparent,
+    /// instead of showing the sky.
+    pub transparent: bool,
+    pub firefly: FireflyFilter,
+}
+
+impl Tracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            transparent: false,
+            firefly: FireflyFilter::default(),
+        }
+    }
+
+    /// Trace the camera rays `r` of a pixel, returning its linear color,
+    /// averaged with the weights of the pixel filter, and the average of
+    /// the AOVs of the samples.
+    pub fn pixel(&self, r: &[Sample], world: &World) -> (Color, Aov) {
+        let _span = tracing::trace_span!("shade").entered();
+        let (samples, aovs): (Vec<_>, Vec<_>) = r
+            .iter()
+            .map(|sample| {
+                let (color, aov) = match ray_color(sample.ray, world, self.max_depth) {
+                    (_, aov) if self.transparent && aov.alpha == 0. => (Color::BLACK, aov),
+                    // A single NaN would spread to the whole pixel, and to
+                    // its neighbours with the denoiser.
+                    (color, aov) if !color.rgb.is_finite() => {
+                        stats::count(|s| s.non_finite_samples += 1);
+                        (Color::BLACK, aov)
+                    }
+                    traced => traced,
+                };
+
+                ((color, sample.weight), aov)
+            })
+            .unzip();
+        stats::flush();
+
+        // The images are in Rec. 709, whatever the working color space.
+        let space = world.color_space();
+        let mut aov = Aov::average(&aovs);
+        aov.albedo = space.convert(aov.albedo, ColorSpace::Rec709);
+
+        (
+            space.convert(self.firefly.resolve(&samples), ColorSpace::Rec709),
+            aov,
+        )
+    }
+
+    /// Render the `world` seen by the `camera` with `samples` rays per
+    /// pixel, returning the linear color of each pixel.
+    pub fn render(&self, camera: &Camera, world: &World, samples: u32) -> ImageBuffer {
+        camera.ray_map(samples, |r| self.pixel(r, world).0, |_, _| {})
+    }
+}
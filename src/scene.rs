@@ -0,0 +1,207 @@
+use crate::{
+    animation::Animation,
+    description::{CameraDescription, RenderSettings, SceneDescription},
+    import::ImportedScene,
+    object::Object,
+    scenes::{self, MaterialWeights},
+    world::WorldFile,
+};
+
+/// Scenes that can be rendered, either built-in or described in a file.
+#[derive(Debug, Clone)]
+pub enum Scene {
+    /// The large spheres surrounded by a field of small random ones.
+    RandomSpheres {
+        seed: u64,
+        grid_extent: u32,
+        material_weights: MaterialWeights,
+    },
+    /// Only the large spheres, quicker to render.
+    Spheres,
+    /// Diffuse, hollow glass and fuzzy metal spheres in a row.
+    ThreeSpheres,
+    /// Boxes in a room lit by an area light.
+    Cornell,
+    /// Glass spheres of different refraction indices.
+    GlassDemo,
+    /// Hills, mountains and lakes generated from noise.
+    Terrain {
+        seed: u64,
+    },
+    /// Trees and bushes grown from L-systems.
+    Garden {
+        seed: u64,
+    },
+    Described(Box<SceneDescription>),
+    /// Scene loaded from JSON.
+    Loaded(Box<WorldFile>),
+    /// Scene imported from the format of another program.
+    Imported(Box<ImportedScene>),
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::RandomSpheres {
+            seed: scenes::DEFAULT_SEED,
+            grid_extent: scenes::DEFAULT_GRID_EXTENT,
+            material_weights: MaterialWeights::default(),
+        }
+    }
+}
+
+impl Scene {
+    /// Names of the built-in scenes.
+    pub const NAMES: [&'static str; 8] = [
+        "random",
+        "random-spheres",
+        "spheres",
+        "three-spheres",
+        "cornell",
+        "glass-demo",
+        "terrain",
+        "garden",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" | "random-spheres" => Some(Self::default()),
+            "spheres" => Some(Self::Spheres),
+            "three-spheres" => Some(Self::ThreeSpheres),
+            "cornell" => Some(Self::Cornell),
+            "glass-demo" => Some(Self::GlassDemo),
+            "terrain" => Some(Self::Terrain {
+                seed: scenes::DEFAULT_SEED,
+            }),
+            "garden" => Some(Self::Garden {
+                seed: scenes::DEFAULT_SEED,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Parse a scene sent as text, JSON objects are worlds while the
+    /// rest is described in RON.
+    #[cfg(feature = "serde")]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim_start().starts_with('{') {
+            true => WorldFile::parse_json(text).map(|w| Self::Loaded(Box::new(w))),
+            false => SceneDescription::parse(text).map(|d| Self::Described(Box::new(d))),
+        }
+        .map_err(|e| format!("invalid scene: {}", e))
+    }
+
+    /// Camera and render settings of the scene, the ones missing are
+    /// taken from the defaults.
+    pub fn settings(&self) -> (CameraDescription, RenderSettings) {
+        match self {
+            Self::Described(d) => (d.camera.clone(), d.render.clone()),
+            Self::Imported(i) => (i.camera.clone(), i.render.clone()),
+            Self::Loaded(w) => {
+                let settings = w.camera.settings();
+                let camera = CameraDescription {
+                    look_from: Some(settings.look_from.into()),
+                    look_at: Some(settings.look_at.into()),
+                    up: Some(settings.vup.into()),
+                    fov: Some(settings.v_fov),
+                    defocus_angle: Some(settings.defocus_angle),
+                    focus_dist: Some(settings.focus_dist),
+                };
+                let render = RenderSettings {
+                    width: Some(settings.width),
+                    aspect_ratio: Some(settings.aspect_ratio),
+                    ..w.render.clone()
+                };
+
+                (camera, render)
+            }
+            Self::ThreeSpheres => (
+                CameraDescription {
+                    look_from: Some((-2., 2., 1.)),
+                    look_at: Some((0., 0., -1.)),
+                    fov: Some(40.),
+                    defocus_angle: Some(2.),
+                    focus_dist: Some(3.4),
+                    ..Default::default()
+                },
+                RenderSettings {
+                    aspect_ratio: Some(16. / 9.),
+                    ..Default::default()
+                },
+            ),
+            Self::Cornell => (
+                CameraDescription {
+                    look_from: Some((0., 1., 3.9)),
+                    look_at: Some((0., 1., 0.)),
+                    fov: Some(40.),
+                    defocus_angle: Some(0.),
+                    ..Default::default()
+                },
+                RenderSettings {
+                    aspect_ratio: Some(1.),
+                    samples: Some(200),
+                    ..Default::default()
+                },
+            ),
+            Self::GlassDemo => (
+                CameraDescription {
+                    look_from: Some((0., 3., 12.)),
+                    look_at: Some((0., 0.8, 0.)),
+                    fov: Some(35.),
+                    defocus_angle: Some(0.),
+                    ..Default::default()
+                },
+                RenderSettings::default(),
+            ),
+            Self::Terrain { .. } => (
+                CameraDescription {
+                    look_from: Some((0., 30., 65.)),
+                    look_at: Some((0., 4., 0.)),
+                    fov: Some(50.),
+                    defocus_angle: Some(0.),
+                    ..Default::default()
+                },
+                RenderSettings::default(),
+            ),
+            Self::Garden { .. } => (
+                CameraDescription {
+                    look_from: Some((0., 2.5, 11.)),
+                    look_at: Some((0., 2., 0.)),
+                    fov: Some(45.),
+                    defocus_angle: Some(0.),
+                    ..Default::default()
+                },
+                RenderSettings::default(),
+            ),
+            _ => Default::default(),
+        }
+    }
+
+    /// Build the objects of the scene.
+    pub fn build(&self) -> Vec<Object> {
+        match self {
+            Self::RandomSpheres {
+                seed,
+                grid_extent,
+                material_weights,
+            } => scenes::random_spheres(*seed, *grid_extent, *material_weights),
+            Self::Spheres => scenes::spheres(),
+            Self::ThreeSpheres => scenes::three_spheres(),
+            Self::Cornell => scenes::cornell(),
+            Self::GlassDemo => scenes::glass_demo(),
+            Self::Terrain { seed } => scenes::terrain(*seed),
+            Self::Garden { seed } => scenes::garden(*seed),
+            Self::Described(description) => description.build(),
+            Self::Loaded(world) => world.objects.clone(),
+            Self::Imported(scene) => scene.objects.clone(),
+        }
+    }
+
+    /// Build the objects of the scene together with their animation,
+    /// only the described scenes are animated.
+    pub fn build_animated(&self) -> (Vec<Object>, Animation) {
+        match self {
+            Self::Described(description) => description.build_animated(),
+            _ => (self.build(), Animation::default()),
+        }
+    }
+}
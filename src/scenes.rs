@@ -0,0 +1,538 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    curve::{Curve, CurveShape, Curves},
+    description::CHUNK,
+    lsystem::{self, LSystem, Plant},
+    material::Material,
+    mesh::Mesh,
+    object::Object,
+    quat::Quat,
+    scene,
+    sphere::Sphere,
+    triangle::Triangle,
+    vec3::{consts::TAU, Color, Float, Vec3},
+};
+
+/// Seed of the random spheres when none is given.
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Extent of the grid of the random spheres when none is given.
+pub const DEFAULT_GRID_EXTENT: u32 = 11;
+
+/// Relative weights of the materials picked for the random spheres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialWeights {
+    pub lambertian: Float,
+    pub metal: Float,
+    pub dielectric: Float,
+}
+
+impl Default for MaterialWeights {
+    fn default() -> Self {
+        Self {
+            lambertian: 0.7,
+            metal: 0.2,
+            dielectric: 0.1,
+        }
+    }
+}
+
+impl MaterialWeights {
+    /// Pick a random material according to the weights.
+    fn pick(&self, rng: &mut impl Rng) -> Material {
+        let total = self.lambertian + self.metal + self.dielectric;
+        let choose_mat = rng.gen::<Float>() * total;
+
+        if choose_mat < self.lambertian {
+            Material::lambertian(Color::new(rng.gen()), None)
+        } else if choose_mat < self.lambertian + self.metal {
+            let albedo = Color::new((
+                rng.gen_range(0.4..0.8),
+                rng.gen_range(0.4..0.8),
+                rng.gen_range(0.4..0.8),
+            ));
+            Material::metal(albedo, Some(rng.gen()))
+        } else {
+            Material::dielectric(rng.gen_range(1.0..5.0), None)
+        }
+    }
+}
+
+/// The ground with the large spheres on top: glass, the small blue
+/// one, polished and brushed metal.
+pub fn spheres() -> Vec<Object> {
+    scene! {
+        sphere { center: (0., -1000., 0.), radius: 1000., material: lambertian((0.5, 0.5, 0.5)) }
+        sphere { center: (0., 1., 0.), radius: 1., material: dielectric(1.5) }
+        sphere { center: (0., 2.3, 0.), radius: 0.3, material: lambertian((0.2, 0.2, 0.8)) }
+        sphere { center: (4., 1., 0.), radius: 1., material: metal((0.8, 0.8, 0.8)) }
+        sphere { center: (-4., 1., 0.), radius: 1., material: metal((0.8, 0.6, 0.2), 0.3) }
+    }
+}
+
+/// The large [`spheres`] surrounded by a field of small random ones,
+/// one for each cell of the grid from `-grid_extent` to `grid_extent`
+/// along x and z. The same `seed` always generates the same scene.
+pub fn random_spheres(
+    seed: u64,
+    grid_extent: u32,
+    material_weights: MaterialWeights,
+) -> Vec<Object> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = spheres();
+    let extent = grid_extent as i32;
+
+    for a in -extent..extent {
+        for b in -extent..extent {
+            let center = Vec3(
+                a as Float + 0.9 * rng.gen::<Float>(),
+                0.2,
+                b as Float + 0.9 * rng.gen::<Float>(),
+            );
+            let material = material_weights.pick(&mut rng);
+
+            world.push(Object::Sphere(Sphere::new(center, 0.2, material)));
+        }
+    }
+
+    world
+}
+
+/// The three spheres of the first book: diffuse, hollow glass and
+/// fuzzy metal, seen through a defocused camera.
+pub fn three_spheres() -> Vec<Object> {
+    scene! {
+        sphere { center: (0., -100.5, -1.), radius: 100., material: lambertian((0.8, 0.8, 0.)) }
+        sphere { center: (0., 0., -1.2), radius: 0.5, material: lambertian((0.1, 0.2, 0.5)) }
+        sphere { center: (-1., 0., -1.), radius: 0.5, material: dielectric(1.5) }
+        // The air bubble inside the glass makes it hollow.
+        sphere { center: (-1., 0., -1.), radius: 0.4, material: dielectric(1. / 1.5) }
+        sphere { center: (1., 0., -1.), radius: 0.5, material: metal((0.8, 0.6, 0.2), 0.3) }
+    }
+}
+
+/// Two triangles spanning the parallelogram with a `corner` and
+/// the edges `u` and `v`.
+fn quad(corner: Vec3, u: Vec3, v: Vec3, material: Material) -> [Triangle; 2] {
+    [
+        Triangle::new([corner, corner + u, corner + u + v], material),
+        Triangle::new([corner, corner + u + v, corner + v], material),
+    ]
+}
+
+/// Box standing on the floor, with the `size` along x, y and z,
+/// turned by `angle` degrees around its vertical axis.
+fn standing_box(base: Vec3, size: Vec3, angle: Float, material: Material) -> Object {
+    let turn = Quat::from_axis_angle(Vec3(0., 1., 0.), angle);
+    let x = turn.rotate(Vec3(size.x(), 0., 0.));
+    let y = Vec3(0., size.y(), 0.);
+    let z = turn.rotate(Vec3(0., 0., size.z()));
+    let corner = base - 0.5 * (x + z);
+
+    let faces = [
+        quad(corner, x, y, material),
+        quad(corner + z, y, x, material),
+        quad(corner, y, z, material),
+        quad(corner + x, z, y, material),
+        quad(corner, z, x, material),
+        quad(corner + y, x, z, material),
+    ];
+
+    Object::Mesh(Mesh::new(faces.into_iter().flatten().collect()))
+}
+
+/// The Cornell box: red and green walls lit by a square light on the
+/// ceiling, with a tall and a short box made of triangles. The room
+/// extends behind the camera, so that the light is the only source.
+pub fn cornell() -> Vec<Object> {
+    let white = Material::lambertian(Color::new((0.73, 0.73, 0.73)), None);
+    let red = Material::lambertian(Color::new((0.65, 0.05, 0.05)), None);
+    let green = Material::lambertian(Color::new((0.12, 0.45, 0.15)), None);
+    let light = Material::emissive(Color::new((5., 5., 5.)));
+
+    // The room spans from -1 to 1 along x, from 0 to 2 along y and
+    // from -1 to 5 along z.
+    let (x, y, z) = (Vec3(2., 0., 0.), Vec3(0., 2., 0.), Vec3(0., 0., 6.));
+    let corner = Vec3(-1., 0., -1.);
+    let room = [
+        quad(corner, x, z, white),
+        quad(corner + y, x, z, white),
+        quad(corner, x, y, white),
+        quad(corner + z, x, y, white),
+        quad(corner, y, z, red),
+        quad(corner + x, y, z, green),
+    ];
+
+    vec![
+        Object::Mesh(Mesh::new(room.into_iter().flatten().collect())),
+        Object::Mesh(Mesh::new(
+            quad(
+                Vec3(-0.4, 1.999, -0.6),
+                Vec3(0.8, 0., 0.),
+                Vec3(0., 0., 0.8),
+                light,
+            )
+            .into(),
+        )),
+        standing_box(Vec3(-0.33, 0., -0.4), Vec3(0.6, 1.2, 0.6), 15., white),
+        standing_box(Vec3(0.35, 0., 0.2), Vec3(0.6, 0.6, 0.6), -18., white),
+    ]
+}
+
+/// Glass spheres of increasing refraction index, from water to
+/// diamond, with a frosted one and a mirror behind them in front of
+/// colored spheres which show the refraction.
+pub fn glass_demo() -> Vec<Object> {
+    let ground = Material::lambertian(Color::new((0.5, 0.5, 0.5)), None);
+    let mut world = vec![Sphere::new(Vec3(0., -1000., 0.), 1000., ground)];
+
+    for (i, refraction_index) in [1.33, 1.5, 1.8, 2.42].into_iter().enumerate() {
+        let x = 3. * i as Float - 4.5;
+        world.push(Sphere::new(
+            Vec3(x, 1., 0.),
+            1.,
+            Material::dielectric(refraction_index, None),
+        ));
+    }
+
+    world.push(Sphere::new(
+        Vec3(-3., 0.6, 3.),
+        0.6,
+        Material::dielectric(1.5, Some(0.2)),
+    ));
+    world.push(Sphere::new(
+        Vec3(3., 0.6, 3.),
+        0.6,
+        Material::metal(Color::new((0.9, 0.9, 0.9)), None),
+    ));
+
+    let colors = [
+        (0.8, 0.1, 0.1),
+        (0.9, 0.6, 0.1),
+        (0.1, 0.6, 0.2),
+        (0.1, 0.2, 0.8),
+    ];
+    for (i, color) in colors.into_iter().enumerate() {
+        world.push(Sphere::new(
+            Vec3(3. * i as Float - 4.5, 0.5, -4.),
+            0.5,
+            Material::lambertian(Color::new(color), None),
+        ));
+    }
+
+    world.into_iter().map(Object::Sphere).collect()
+}
+
+/// Side of the square of terrain, in units.
+const TERRAIN_SIZE: Float = 100.;
+
+/// Cells of the heightfield along each side of the terrain.
+const TERRAIN_CELLS: usize = 160;
+
+/// Cells along each side of the tiles the terrain is split into, so
+/// that the BVH only tests the triangles of the tiles a ray crosses.
+const TERRAIN_TILE: usize = 8;
+
+/// Height of the surface of the lakes.
+const WATER_LEVEL: Float = 0.;
+
+/// Height above which the flat enough ground is covered in snow.
+const SNOW_LINE: Float = 9.;
+
+/// Two dimensional gradient noise, smoothly varying between about -1
+/// and 1 with features about one unit apart.
+struct Noise {
+    /// Shuffled numbers from 0 to 255, repeated twice so that the
+    /// hashes of the lattice don't need to wrap around.
+    permutation: Vec<usize>,
+}
+
+impl Noise {
+    fn new(rng: &mut impl Rng) -> Self {
+        let mut permutation = (0..256).collect::<Vec<_>>();
+        permutation.shuffle(rng);
+        permutation.extend_from_within(..);
+
+        Self { permutation }
+    }
+
+    fn at(&self, x: Float, y: Float) -> Float {
+        use crate::vec3::consts::FRAC_1_SQRT_2 as D;
+        const GRADIENTS: [(Float, Float); 8] = [
+            (1., 0.),
+            (-1., 0.),
+            (0., 1.),
+            (0., -1.),
+            (D, D),
+            (-D, D),
+            (D, -D),
+            (-D, -D),
+        ];
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (i, j) = ((x0 as i64 & 255) as usize, (y0 as i64 & 255) as usize);
+        let (dx, dy) = (x - x0, y - y0);
+
+        // Contribution of the gradient of the corner `(di, dj)` of the
+        // cell around the point.
+        let corner = |di: usize, dj: usize| {
+            let hash = self.permutation[self.permutation[i + di] + j + dj];
+            let (gx, gy) = GRADIENTS[hash % GRADIENTS.len()];
+
+            gx * (dx - di as Float) + gy * (dy - dj as Float)
+        };
+        let fade = |t: Float| t * t * t * (t * (t * 6. - 15.) + 10.);
+        let (u, v) = (fade(dx), fade(dy));
+
+        let lerp = |a: Float, b: Float, t: Float| a + t * (b - a);
+        lerp(
+            lerp(corner(0, 0), corner(1, 0), u),
+            lerp(corner(0, 1), corner(1, 1), u),
+            v,
+        )
+    }
+
+    /// Fractional Brownian motion: `octaves` layers of noise, each one
+    /// with twice the detail and half the amplitude of the previous.
+    fn fbm(&self, x: Float, y: Float, octaves: u32) -> Float {
+        let (mut sum, mut amplitude, mut frequency, mut total) = (0., 1., 1., 0.);
+
+        for _ in 0..octaves {
+            sum += amplitude * self.at(frequency * x, frequency * y);
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.;
+        }
+
+        sum / total
+    }
+
+    /// Ridged noise between 0 and 1: the creases of the noise turned
+    /// into sharp crests, like the ones of a mountain range. Each layer
+    /// is weighted by the previous one, so that the details gather on
+    /// the crests and the valleys stay smooth.
+    fn ridged(&self, x: Float, y: Float, octaves: u32) -> Float {
+        let (mut sum, mut amplitude, mut frequency, mut total) = (0., 1., 1., 0.);
+        let mut weight: Float = 1.;
+
+        for _ in 0..octaves {
+            let ridge = 1. - self.at(frequency * x, frequency * y).abs();
+            let ridge = ridge * ridge * weight;
+            weight = ridge.clamp(0., 1.);
+
+            sum += amplitude * ridge;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.;
+        }
+
+        sum / total
+    }
+}
+
+/// Rolling hills and ridged mountains rising from lakes, built from
+/// layers of noise. The ground is sand on the shores, grass on the
+/// gentle slopes, rock on the steep ones and snow on the summits.
+/// The same `seed` always generates the same terrain.
+pub fn terrain(seed: u64) -> Vec<Object> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Different noises for the shape of the hills, the crests of the
+    // mountains and where the mountains rise.
+    let (hills, crests, ranges) = (
+        Noise::new(&mut rng),
+        Noise::new(&mut rng),
+        Noise::new(&mut rng),
+    );
+
+    let height = |x: Float, z: Float| {
+        let base = hills.fbm(0.03 * x, 0.03 * z, 5);
+        let ridges = crests.ridged(0.04 * x, 0.04 * z, 5);
+        let t = ((ranges.fbm(0.015 * x, 0.015 * z, 2) + 0.1) / 0.5).clamp(0., 1.);
+        let mountains = t * t * (3. - 2. * t);
+
+        1. + 6. * base + 18. * mountains * ridges
+    };
+
+    let cell = TERRAIN_SIZE / TERRAIN_CELLS as Float;
+    let n = TERRAIN_CELLS + 1;
+    let mut points = Vec::with_capacity(n * n);
+    let mut normals = Vec::with_capacity(n * n);
+    for j in 0..n {
+        for i in 0..n {
+            let x = i as Float * cell - TERRAIN_SIZE / 2.;
+            let z = j as Float * cell - TERRAIN_SIZE / 2.;
+            points.push(Vec3(x, height(x, z), z));
+
+            // The normals of the vertices follow the slope of the
+            // heightfield, for smooth shading.
+            let dx = height(x + cell, z) - height(x - cell, z);
+            let dz = height(x, z + cell) - height(x, z - cell);
+            normals.push(Vec3::unit(Vec3(-dx, 2. * cell, -dz)));
+        }
+    }
+
+    let sand = Material::lambertian(Color::new((0.76, 0.7, 0.5)), None);
+    let grass = Material::lambertian(Color::new((0.25, 0.45, 0.15)), None);
+    let rock = Material::lambertian(Color::new((0.4, 0.37, 0.35)), None);
+    let snow = Material::lambertian(Color::new((0.9, 0.9, 0.92)), None);
+    let pick = |vertices: [Vec3; 3]| {
+        let normal = Vec3::unit(Vec3::cross(
+            vertices[1] - vertices[0],
+            vertices[2] - vertices[0],
+        ));
+        let altitude = vertices.iter().map(|v| v.y()).sum::<Float>() / 3.;
+        // Cosine of the angle of the slope: 1 on flat ground.
+        let flatness = normal.y().abs();
+
+        if altitude < WATER_LEVEL + 0.5 {
+            sand
+        } else if altitude > SNOW_LINE && flatness > 0.6 {
+            snow
+        } else if flatness < 0.75 {
+            rock
+        } else {
+            grass
+        }
+    };
+
+    let mut world = Vec::new();
+    for tile_j in (0..TERRAIN_CELLS).step_by(TERRAIN_TILE) {
+        for tile_i in (0..TERRAIN_CELLS).step_by(TERRAIN_TILE) {
+            let mut triangles = Vec::with_capacity(2 * TERRAIN_TILE * TERRAIN_TILE);
+
+            for j in tile_j..(tile_j + TERRAIN_TILE).min(TERRAIN_CELLS) {
+                for i in tile_i..(tile_i + TERRAIN_TILE).min(TERRAIN_CELLS) {
+                    let corners = [
+                        j * n + i,
+                        j * n + i + 1,
+                        (j + 1) * n + i + 1,
+                        (j + 1) * n + i,
+                    ];
+
+                    for [a, b, c] in [[0, 2, 1], [0, 3, 2]] {
+                        let vertices = [points[corners[a]], points[corners[b]], points[corners[c]]];
+                        let material = pick(vertices);
+                        triangles.push(Triangle::new(vertices, material).with_normals([
+                            normals[corners[a]],
+                            normals[corners[b]],
+                            normals[corners[c]],
+                        ]));
+                    }
+                }
+            }
+
+            world.push(Object::Mesh(Mesh::new(triangles)));
+        }
+    }
+
+    // The lakes fill the terrain up to the water level.
+    let water = Material::metal(Color::new((0.3, 0.45, 0.55)), Some(0.05));
+    let corner = Vec3(-TERRAIN_SIZE / 2., WATER_LEVEL, -TERRAIN_SIZE / 2.);
+    world.push(Object::Mesh(Mesh::new(
+        quad(
+            corner,
+            Vec3(0., 0., TERRAIN_SIZE),
+            Vec3(TERRAIN_SIZE, 0., 0.),
+            water,
+        )
+        .into(),
+    )));
+
+    world
+}
+
+/// Trees and bushes grown from L-systems on a lawn of blades of grass,
+/// with the place, the angles and the branching of each plant picked
+/// from the `seed`.
+pub fn garden(seed: u64) -> Vec<Object> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let ground = Material::lambertian(Color::new((0.35, 0.5, 0.25)), None);
+    let mut world = vec![Object::Sphere(Sphere::new(
+        Vec3(0., -1000., 0.),
+        1000.,
+        ground,
+    ))];
+
+    let bark = Material::lambertian(Color::new((0.35, 0.25, 0.15)), None);
+    let tree = Plant {
+        step: 0.2,
+        radius: 0.1,
+        thinning: 0.65,
+        leaf_size: 0.3,
+        jitter: 6.,
+        bark,
+        leaf: Material::lambertian(Color::new((0.2, 0.5, 0.1)), None),
+    };
+    let bush = Plant {
+        step: 0.06,
+        radius: 0.03,
+        thinning: 0.7,
+        leaf_size: 0.15,
+        jitter: 10.,
+        bark,
+        leaf: Material::lambertian(Color::new((0.45, 0.6, 0.1)), None),
+    };
+
+    // The trees at the back and the bushes in front, each one moved a
+    // little from its place on a row.
+    for (system, plant, z, count) in [
+        (LSystem::tree(), tree, -2., 3),
+        (LSystem::bush(), bush, 1.5, 4),
+    ] {
+        for i in 0..count {
+            let x = 10. / count as Float * (i as Float - (count - 1) as Float / 2.);
+            let base = Vec3(
+                x + rng.gen_range(-0.5..0.5),
+                0.,
+                z + rng.gen_range(-0.5..0.5),
+            );
+            let yaw = rng.gen_range(0. ..360.);
+
+            world.extend(lsystem::grow(&system, &plant, base, yaw, &mut rng));
+        }
+    }
+
+    world.extend(lawn(&mut rng));
+
+    world
+}
+
+/// Blades of grass on the part of the garden in view, bending away from
+/// their roots, in a few shades of green.
+fn lawn(rng: &mut impl Rng) -> Vec<Object> {
+    const BLADES: usize = 30_000;
+    let shades = [(0.25, 0.45, 0.1), (0.3, 0.5, 0.15), (0.35, 0.5, 0.1)];
+    let mut blades = vec![Vec::new(); shades.len()];
+
+    while blades.iter().map(Vec::len).sum::<usize>() < BLADES {
+        let root = Vec3(rng.gen_range(-15. ..15.), 0., rng.gen_range(-6. ..8.));
+        // The view widens away from the camera.
+        if root.0.abs() > 0.8 * (11. - root.2) + 1. {
+            continue;
+        }
+        let height = rng.gen_range(0.15..0.35);
+        let (sin, cos) = rng.gen_range(0. ..TAU).sin_cos();
+        let lean = rng.gen_range(0.2..0.6) * height * Vec3(cos, 0., sin);
+
+        blades[rng.gen_range(0..shades.len())].push(Curve {
+            points: [
+                root,
+                root + Vec3(0., height / 3., 0.),
+                root + 0.4 * lean + Vec3(0., 2. * height / 3., 0.),
+                root + lean + Vec3(0., 0.9 * height, 0.),
+            ],
+            widths: (0.012, 0.002),
+        });
+    }
+
+    blades
+        .into_iter()
+        .zip(shades)
+        .flat_map(|(blades, shade)| {
+            let material = Material::lambertian(Color::new(shade), None);
+            Curves::new(blades, CurveShape::Flat, material).split(CHUNK)
+        })
+        .map(Object::Curves)
+        .collect()
+}
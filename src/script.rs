@@ -0,0 +1,137 @@
+//! Rhai scripts of the RON scenes, which generate objects when the scene
+//! is loaded:
+//!
+//! ```ron
+//! (
+//!     materials: { "red": Lambertian(color: (0.8, 0.1, 0.1)) },
+//!     objects: [],
+//!     script: "
+//!         for i in 0..20 {
+//!             let x = i - 10;
+//!             sphere([x, 0.3 + noise(x * 0.3, 0, 0), random(-2, 2)], 0.3, \"red\");
+//!         }
+//!     ",
+//! )
+//! ```
+//!
+//! Scripts can call:
+//! - `sphere(center, radius, material)` to add a sphere at the `[x, y, z]`
+//!   center, using one of the materials of the scene;
+//! - `random()` for a number between 0 and 1, or `random(min, max)`, the
+//!   same `seed` of the scene always giving the same numbers;
+//! - `noise(x, y, z)` for smooth noise between 0 and 1.
+
+use crate::description::ObjectDescription;
+#[cfg(feature = "scripting")]
+use crate::vec3::Float;
+
+/// Run the `script`, returning the objects it adds.
+#[cfg(feature = "scripting")]
+pub fn run(script: &str, seed: u64) -> Result<Vec<ObjectDescription>, String> {
+    use std::{cell::RefCell, rc::Rc};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rhai::{Array, Dynamic, Engine, EvalAltResult, FLOAT};
+
+    type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+    fn float(value: &Dynamic) -> ScriptResult<Float> {
+        match value.as_float() {
+            Ok(x) => Ok(x as Float),
+            Err(_) => value
+                .as_int()
+                .map(|x| x as Float)
+                .map_err(|t| format!("expected a number, found {}", t).into()),
+        }
+    }
+
+    let objects = Rc::new(RefCell::new(vec![]));
+    let rng = Rc::new(RefCell::new(StdRng::seed_from_u64(seed)));
+    let mut engine = Engine::new();
+
+    let added = objects.clone();
+    engine.register_fn(
+        "sphere",
+        move |center: Array, radius: Dynamic, material: &str| -> ScriptResult<()> {
+            let [x, y, z] = center.as_slice() else {
+                return Err(format!(
+                    "the center must be an [x, y, z] array, found {} values",
+                    center.len()
+                )
+                .into());
+            };
+
+            added.borrow_mut().push(ObjectDescription::Sphere {
+                center: (float(x)?, float(y)?, float(z)?),
+                radius: float(&radius)?,
+                material: material.into(),
+                cull_backfaces: false,
+            });
+            Ok(())
+        },
+    );
+
+    let random = rng.clone();
+    engine.register_fn("random", move || {
+        random.borrow_mut().gen::<Float>() as FLOAT
+    });
+    engine.register_fn(
+        "random",
+        move |min: Dynamic, max: Dynamic| -> ScriptResult<FLOAT> {
+            let (min, max) = (float(&min)?, float(&max)?);
+
+            Ok((min + rng.borrow_mut().gen::<Float>() * (max - min)) as FLOAT)
+        },
+    );
+    engine.register_fn(
+        "noise",
+        |x: Dynamic, y: Dynamic, z: Dynamic| -> ScriptResult<FLOAT> {
+            Ok(noise(float(&x)?, float(&y)?, float(&z)?) as FLOAT)
+        },
+    );
+
+    engine.run(script).map_err(|e| e.to_string())?;
+    drop(engine);
+
+    Ok(Rc::into_inner(objects).unwrap().into_inner())
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn run(_: &str, _: u64) -> Result<Vec<ObjectDescription>, String> {
+    Err("scene scripts require building with `--features scripting`".into())
+}
+
+/// Value noise between 0 and 1, interpolating the random values of the
+/// corners of the unit cube containing the point.
+#[cfg(feature = "scripting")]
+fn noise(x: Float, y: Float, z: Float) -> Float {
+    fn corner(x: i32, y: i32, z: i32) -> Float {
+        let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+            ^ (y as u32).wrapping_mul(0xd816_3841)
+            ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0x5bd1_e995);
+        h ^= h >> 15;
+
+        h as Float / u32::MAX as Float
+    }
+
+    let smooth = |t: Float| t * t * (3. - 2. * t);
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+    let (x0, y0, z0) = (x0 as i32, y0 as i32, z0 as i32);
+    let lerp = |a: Float, b: Float, t: Float| a + t * (b - a);
+
+    let face = |dz: i32| {
+        let near = lerp(corner(x0, y0, z0 + dz), corner(x0 + 1, y0, z0 + dz), tx);
+        let far = lerp(
+            corner(x0, y0 + 1, z0 + dz),
+            corner(x0 + 1, y0 + 1, z0 + dz),
+            tx,
+        );
+
+        lerp(near, far, ty)
+    };
+
+    lerp(face(0), face(1), tz)
+}
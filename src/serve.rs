@@ -0,0 +1,153 @@
+use std::io::{self, Cursor, Read, Write};
+use std::thread;
+
+use tiny_http::{Method, Request, Response, Server};
+use tracing::{error, info};
+
+use raycasting_rs::camera::Camera;
+use raycasting_rs::description::CameraDescription;
+use raycasting_rs::output::{self, BitDepth, ImageFormat};
+use raycasting_rs::scene::Scene;
+use raycasting_rs::world::World;
+
+use crate::options::Options;
+
+/// Largest scene accepted in the body of a request.
+const MAX_SCENE_SIZE: u64 = 16 << 20;
+
+/// Separator of the images streamed in the response.
+const BOUNDARY: &str = "frame";
+
+/// Listen on `addr` for the scenes posted to `/render`, either as RON
+/// or as JSON when the body starts with `{`, one render at a time.
+///
+/// The response streams the PNG images of passes quadrupling the
+/// samples from one up to the requested ones as `multipart/x-mixed-replace`,
+/// which browsers show in place, so the last image is the final one.
+/// The settings of the command line are used when missing from the scene.
+pub fn serve(addr: &str, options: &Options) -> Result<(), String> {
+    let server =
+        Server::http(addr).map_err(|e| format!("failed to listen on `{}`: {}", addr, e))?;
+    info!("Listening on http://{}/render", server.server_addr());
+
+    for request in server.incoming_requests() {
+        let response = match (request.method(), request.url()) {
+            (Method::Post, "/render") => render_request(request, options),
+            (_, "/render") => request.respond(Response::empty(405)),
+            _ => request.respond(Response::empty(404)),
+        };
+
+        if let Err(e) = response {
+            error!("failed to send the response: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Respond to the `request` with the passes of the scene in its body,
+/// or with the error of an invalid scene.
+fn render_request(mut request: Request, options: &Options) -> io::Result<()> {
+    let mut text = String::new();
+    let scene = request
+        .as_reader()
+        .take(MAX_SCENE_SIZE)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("failed to read the scene: {}", e))
+        .and_then(|_| Scene::parse(&text));
+
+    let scene = match scene {
+        Ok(scene) => scene,
+        Err(e) => return request.respond(Response::from_string(e + "\n").with_status_code(400)),
+    };
+    info!("Rendering the scene of {:?}", request.remote_addr());
+
+    // The passes are written in the pipe while the response is read
+    // from it, a client closing the connection stops the render at
+    // the next pass.
+    let (reader, writer) = io::pipe()?;
+    thread::scope(|s| {
+        s.spawn(|| {
+            if let Err(e) = render_passes(&scene, options, writer) {
+                error!("{}", e);
+            }
+        });
+
+        let content_type = format!(
+            "Content-Type: multipart/x-mixed-replace; boundary={}",
+            BOUNDARY
+        );
+        let headers = vec![content_type.parse().unwrap()];
+        request.respond(Response::new(200.into(), headers, reader, None, None))
+    })
+}
+
+/// Render the `scene` in passes of increasing samples, writing each
+/// image as a part of the multipart response to `w`.
+fn render_passes(scene: &Scene, options: &Options, mut w: impl Write) -> Result<(), String> {
+    let (camera, render) = scene.settings();
+    let width = render.width.unwrap_or(options.width);
+    let aspect_ratio = render.aspect_ratio.unwrap_or(options.aspect_ratio);
+    let samples = render.samples.unwrap_or(options.samples);
+    let depth = render.max_depth.unwrap_or(options.max_depth);
+
+    let camera = Camera::from(
+        CameraDescription {
+            look_from: camera.look_from.or(Some(options.look_from.into())),
+            look_at: camera.look_at.or(Some(options.look_at.into())),
+            up: camera.up.or(Some(options.up.into())),
+            fov: camera.fov.or(Some(options.v_fov)),
+            defocus_angle: camera.defocus_angle.or(Some(options.defocus_angle)),
+            focus_dist: camera.focus_dist.or(Some(options.focus_dist)),
+        }
+        .settings(width, aspect_ratio),
+    )
+    .threads(options.threads)
+    .pin_threads(options.pin_threads)
+    .filter(options.filter)
+    .quiet();
+    let world = World::builder()
+        .add_many(scene.build())
+        .with_color_space(options.color_space)
+        .build()?;
+
+    let mut pass_samples = 1;
+    loop {
+        pass_samples = pass_samples.min(samples);
+        let (pixels, aovs) = crate::render(&camera, &world, options, None, pass_samples, depth)?
+            .expect("the served images are not streamed");
+        let pixels = crate::encode(pixels, &aovs, options);
+
+        let mut png = Cursor::new(vec![]);
+        let format = ImageFormat::Png {
+            depth: BitDepth::Eight,
+        };
+        output::write(
+            &mut png,
+            format,
+            camera.width(),
+            camera.height(),
+            &pixels,
+            None,
+        )
+        .map_err(|e| format!("failed to encode the image: {}", e))?;
+        let png = png.into_inner();
+
+        write!(
+            w,
+            "--{}\r\nContent-Type: image/png\r\nContent-Length: {}\r\n\r\n",
+            BOUNDARY,
+            png.len()
+        )
+        .and_then(|_| w.write_all(&png))
+        .and_then(|_| w.write_all(b"\r\n"))
+        .map_err(|e| format!("failed to send the image: {}", e))?;
+
+        if pass_samples == samples {
+            break;
+        }
+        pass_samples *= 4;
+    }
+
+    write!(w, "--{}--\r\n", BOUNDARY).map_err(|e| format!("failed to send the image: {}", e))
+}
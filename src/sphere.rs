@@ -1,30 +1,71 @@
-use std::ops::Range;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    aabb::Aabb,
     hit::{Hit, HitRecord},
+    interval::Interval,
     material::Material,
     ray::Ray,
-    vec3::Vec3,
+    vec3::{Float, Vec3},
 };
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sphere {
     pub center: Vec3,
-    pub radius: f32,
+    pub radius: Float,
     pub material: Material,
+    /// Whether the rays hitting the inside of the sphere pass through,
+    /// which lets a camera inside it see out.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub cull_backfaces: bool,
+}
+
+#[cfg(feature = "serde")]
+fn is_false(b: &bool) -> bool {
+    !*b
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f32, material: Material) -> Self {
+    pub fn new(center: Vec3, radius: Float, material: Material) -> Self {
         Self {
             center,
             radius,
             material,
+            cull_backfaces: false,
         }
     }
+
+    pub fn with_backface_culling(mut self, cull_backfaces: bool) -> Self {
+        self.cull_backfaces = cull_backfaces;
+        self
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let extent = Vec3(self.radius, self.radius, self.radius);
+
+        Aabb::new(self.center - extent, self.center + extent)
+    }
 }
 
-impl Hit<Ray> for Sphere {
-    fn hit(&self, r: &Ray, t_range: Range<f32>) -> Option<HitRecord> {
+impl Sphere {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.center.is_finite() {
+            return Err(format!("the center must be finite, found {}", self.center));
+        }
+        if !(self.radius > 0. && self.radius.is_finite()) {
+            return Err(format!(
+                "the radius must be positive, found {}",
+                self.radius
+            ));
+        }
+
+        self.material.validate()
+    }
+
+    /// Distance of the nearest hit of the ray `r` within `t_range`.
+    fn root(&self, r: &Ray, t_range: Interval) -> Option<Float> {
         let oc = r.start - self.center;
 
         let a = Vec3::dot(r.dir, r.dir);
@@ -32,21 +73,40 @@ impl Hit<Ray> for Sphere {
         let c = Vec3::dot(oc, oc) - self.radius * self.radius;
 
         let discriminant = half_b * half_b - a * c;
-        let sqrtd = f32::sqrt(discriminant);
+        // The ray misses the sphere, or its direction is zero or NaN.
+        if !(discriminant >= 0. && a > 0.) {
+            return None;
+        }
+        let sqrtd = Float::sqrt(discriminant);
 
-        /* Find the nearest root that lies in the range. */
+        /* Find the nearest root that lies in the range. The farther
+         * one is where the ray leaves the sphere, seeing its inside. */
         let mut root = -(half_b + sqrtd) / a;
-        if !t_range.contains(&root) {
+        if !t_range.surrounds(root) {
+            if self.cull_backfaces {
+                return None;
+            }
             root = (-half_b + sqrtd) / a;
-            if !t_range.contains(&root) {
+            if !t_range.surrounds(root) {
                 return None;
             }
         }
 
+        Some(root)
+    }
+}
+
+impl Hit<Ray> for Sphere {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let root = self.root(r, t_range)?;
         let p = r.at(root);
         let normal = (1.0 / self.radius) * (p - self.center);
-        let record = HitRecord::new(p, normal, root, self.material, r);
+        let record = HitRecord::new(p, normal, root, &self.material, r);
 
         Some(record)
     }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.root(r, t_range).is_some()
+    }
 }
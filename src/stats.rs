@@ -0,0 +1,182 @@
+//! Counters of the work done while tracing the rays, collected once
+//! enabled so that the renders can be compared quantitatively.
+
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Number of buckets of the bounce histogram.
+pub const BOUNCE_BUCKETS: usize = 32;
+
+/// Work done on a single object.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ObjectStats {
+    /// Rays tested against the object.
+    pub intersection_tests: u64,
+    /// Rays whose closest hit is on the object.
+    pub hits: u64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RenderStats {
+    /// Rays starting from the camera.
+    pub primary_rays: u64,
+    /// Rays scattered by the surfaces.
+    pub secondary_rays: u64,
+    /// Rays tested against an object.
+    pub intersection_tests: u64,
+    pub bvh_node_visits: u64,
+    /// Samples whose color was NaN or infinite, which are rendered black.
+    pub non_finite_samples: u64,
+    /// Number of paths by the bounces before they escape or are
+    /// absorbed, the last bucket also counts the longer ones.
+    pub bounces: [u64; BOUNCE_BUCKETS],
+    /// Work done on each object, by its index in the order the objects
+    /// were added to the world.
+    pub objects: Vec<ObjectStats>,
+}
+
+impl Default for RenderStats {
+    fn default() -> Self {
+        Self {
+            primary_rays: 0,
+            secondary_rays: 0,
+            intersection_tests: 0,
+            bvh_node_visits: 0,
+            non_finite_samples: 0,
+            bounces: [0; BOUNCE_BUCKETS],
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl RenderStats {
+    /// Average number of bounces of the paths.
+    pub fn average_bounces(&self) -> f64 {
+        let paths = self.bounces.iter().sum::<u64>();
+        let bounces = (0..)
+            .zip(self.bounces)
+            .map(|(bounces, paths)| bounces * paths)
+            .sum::<u64>();
+
+        match paths {
+            0 => 0.,
+            _ => bounces as f64 / paths as f64,
+        }
+    }
+
+    /// The `n` objects tested the most against the rays, with their
+    /// index, which are the first to simplify or split.
+    pub fn top_objects(&self, n: usize) -> Vec<(usize, ObjectStats)> {
+        let mut objects = self
+            .objects
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, object)| object.intersection_tests > 0)
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|(_, object)| std::cmp::Reverse(object.intersection_tests));
+        objects.truncate(n);
+
+        objects
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.intersection_tests += other.intersection_tests;
+        self.bvh_node_visits += other.bvh_node_visits;
+        self.non_finite_samples += other.non_finite_samples;
+        for (total, paths) in self.bounces.iter_mut().zip(other.bounces) {
+            *total += paths;
+        }
+        if self.objects.len() < other.objects.len() {
+            self.objects
+                .resize(other.objects.len(), ObjectStats::default());
+        }
+        for (total, object) in self.objects.iter_mut().zip(&other.objects) {
+            total.intersection_tests += object.intersection_tests;
+            total.hits += object.hits;
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TOTAL: Mutex<RenderStats> = Mutex::new(RenderStats {
+    primary_rays: 0,
+    secondary_rays: 0,
+    intersection_tests: 0,
+    bvh_node_visits: 0,
+    non_finite_samples: 0,
+    bounces: [0; BOUNCE_BUCKETS],
+    objects: Vec::new(),
+});
+
+thread_local! {
+    /// Counters of the pixel traced by the thread.
+    static PIXEL: RefCell<RenderStats> = RefCell::default();
+}
+
+/// Start counting the work of all the renders of the process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Counters of the pixels traced since the last call.
+pub fn take() -> RenderStats {
+    std::mem::take(&mut *TOTAL.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Update the counters of the current thread, when enabled.
+#[inline]
+pub(crate) fn count(f: impl FnOnce(&mut RenderStats)) {
+    if ENABLED.load(Ordering::Relaxed) {
+        PIXEL.with_borrow_mut(f);
+    }
+}
+
+/// Update the counters of the object with the index `object`, in the
+/// order the objects were added to the world.
+pub(crate) fn count_object(object: usize, f: impl FnOnce(&mut ObjectStats)) {
+    count(|s| {
+        if s.objects.len() <= object {
+            s.objects.resize(object + 1, ObjectStats::default());
+        }
+        f(&mut s.objects[object]);
+    });
+}
+
+/// Record a path which bounced `bounces` times.
+pub(crate) fn count_path(bounces: u32) {
+    count(|s| s.bounces[(bounces as usize).min(BOUNCE_BUCKETS - 1)] += 1);
+}
+
+/// Add the counters of the pixel traced by the current thread to
+/// the total, which is only locked once for each pixel.
+pub(crate) fn flush() {
+    if ENABLED.load(Ordering::Relaxed) {
+        PIXEL.with_borrow_mut(|pixel| {
+            TOTAL
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .add(pixel);
+            // The counters of the objects keep their memory, not to
+            // allocate it again for each pixel.
+            let mut objects = std::mem::take(&mut pixel.objects);
+            objects.clear();
+            *pixel = RenderStats {
+                objects,
+                ..RenderStats::default()
+            };
+        });
+    }
+}
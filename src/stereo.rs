@@ -0,0 +1,26 @@
+use crate::vec3::{Color, Vec3};
+
+/// Positions of the left and right eyes of a stereo pair centered at
+/// `look_from`, both converge on `look_at` which is seen with zero
+/// parallax. The eyes are separated by a thirtieth of the distance to
+/// the target, a common rule of thumb for comfortable depth.
+pub fn eye_pair(look_from: Vec3, look_at: Vec3) -> [Vec3; 2] {
+    let offset = look_at - look_from;
+    let right = Vec3::unit(Vec3::cross(offset, Vec3(0., 1., 0.)));
+    let half_separation = Vec3::norm(offset) / 60.;
+
+    [
+        look_from - half_separation * right,
+        look_from + half_separation * right,
+    ]
+}
+
+/// Combine the images seen by the `left` and `right` eyes in a red/cyan
+/// color anaglyph, the red channel is taken from the left image while
+/// green and blue from the right one.
+pub fn anaglyph(left: &[Color], right: &[Color]) -> Vec<Color> {
+    left.iter()
+        .zip(right)
+        .map(|(l, r)| Color::new((l.rgb.0, r.rgb.1, r.rgb.2)))
+        .collect()
+}
@@ -0,0 +1,214 @@
+//! Catmull–Clark subdivision surfaces, smoothing the polygons of a
+//! low-poly cage into a mesh of small quads.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    material::Material,
+    mesh::Mesh,
+    triangle::Triangle,
+    vec3::{Float, Vec3},
+};
+
+/// Levels of subdivision at most, each one making four times as many
+/// faces.
+pub const MAX_LEVELS: u32 = 6;
+
+/// Polygons sharing their vertices, which are smoothed by subdivision.
+/// The faces list the indices of their vertices counter-clockwise seen
+/// from the front, and each edge is shared by two faces at most. The
+/// edges of a single face are on the boundary of the surface, which
+/// stays sharp.
+#[derive(Debug, Clone)]
+pub struct Cage {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<Vec<usize>>,
+}
+
+/// Faces on each side of an edge, the second one missing on the
+/// boundary.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    index: usize,
+    faces: [Option<usize>; 2],
+}
+
+impl Cage {
+    pub fn new(vertices: Vec<Vec3>, faces: Vec<Vec<usize>>) -> Self {
+        Self { vertices, faces }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.faces.is_empty() {
+            return Err("the cage has no faces".into());
+        }
+        if let Some(p) = self.vertices.iter().find(|p| !p.is_finite()) {
+            return Err(format!("the vertices must be finite, found {}", p));
+        }
+        for (i, face) in self.faces.iter().enumerate() {
+            if face.len() < 3 {
+                return Err(format!("face {}: a face needs at least 3 vertices", i));
+            }
+            if face.iter().any(|v| *v >= self.vertices.len()) {
+                return Err(format!("face {}: the indices are out of range", i));
+            }
+        }
+        self.edges().map(|_| ())
+    }
+
+    /// Edges of the faces by their vertices, lower first.
+    fn edges(&self) -> Result<BTreeMap<(usize, usize), Edge>, String> {
+        let mut edges = BTreeMap::<_, Edge>::new();
+
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                if a == b {
+                    return Err(format!("face {}: the vertex {} is repeated", f, a));
+                }
+
+                let index = edges.len();
+                let edge = edges.entry((a.min(b), a.max(b))).or_insert(Edge {
+                    index,
+                    faces: [None; 2],
+                });
+                match edge.faces {
+                    [None, _] => edge.faces[0] = Some(f),
+                    [Some(_), None] => edge.faces[1] = Some(f),
+                    _ => {
+                        return Err(format!(
+                            "the edge from vertex {} to {} has more than two faces",
+                            a, b
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(edges)
+    }
+
+    /// The cage with each face split into quads, one for each of its
+    /// vertices, moving the points towards the smooth surface. The cage
+    /// must be valid.
+    pub fn subdivide(&self) -> Self {
+        let edges = self.edges().expect("the cage is valid");
+        let n = self.vertices.len();
+
+        let face_points = self
+            .faces
+            .iter()
+            .map(|face| {
+                let sum = face
+                    .iter()
+                    .fold(Vec3::ZERO, |sum, v| sum + self.vertices[*v]);
+                (1. / face.len() as Float) * sum
+            })
+            .collect::<Vec<_>>();
+
+        let mut edge_points = vec![Vec3::ZERO; edges.len()];
+        for (&(a, b), edge) in &edges {
+            let (a, b) = (self.vertices[a], self.vertices[b]);
+            edge_points[edge.index] = match edge.faces {
+                [Some(f), Some(g)] => 0.25 * (a + b + face_points[f] + face_points[g]),
+                _ => 0.5 * (a + b),
+            };
+        }
+
+        // Around each vertex, the sum of the points of its faces and of
+        // the midpoints of its edges, and its neighbors on the boundary.
+        let mut faces = vec![(Vec3::ZERO, 0); n];
+        for (face, point) in self.faces.iter().zip(&face_points) {
+            for v in face {
+                faces[*v].0 += *point;
+                faces[*v].1 += 1;
+            }
+        }
+        let mut midpoints = vec![(Vec3::ZERO, 0); n];
+        let mut boundary = vec![Vec::new(); n];
+        for (&(a, b), edge) in &edges {
+            let midpoint = 0.5 * (self.vertices[a] + self.vertices[b]);
+            for (v, other) in [(a, b), (b, a)] {
+                midpoints[v].0 += midpoint;
+                midpoints[v].1 += 1;
+                if edge.faces[1].is_none() {
+                    boundary[v].push(other);
+                }
+            }
+        }
+
+        let vertex_points = (0..n).map(|v| {
+            let p = self.vertices[v];
+            match boundary[v][..] {
+                [] if faces[v].1 > 0 => {
+                    let valence = midpoints[v].1 as Float;
+                    let f = (1. / faces[v].1 as Float) * faces[v].0;
+                    let r = (1. / valence) * midpoints[v].0;
+                    (1. / valence) * (f + 2. * r + (valence - 3.) * p)
+                }
+                [a, b] => 0.75 * p + 0.125 * (self.vertices[a] + self.vertices[b]),
+                // The corners where the boundary meets itself, and the
+                // vertices of no face, stay in place.
+                _ => p,
+            }
+        });
+
+        let vertices = vertex_points
+            .chain(edge_points)
+            .chain(face_points)
+            .collect::<Vec<_>>();
+        let edge = |a: usize, b: usize| n + edges[&(a.min(b), a.max(b))].index;
+        let faces = self
+            .faces
+            .iter()
+            .enumerate()
+            .flat_map(|(f, face)| {
+                let k = face.len();
+                let center = n + edges.len() + f;
+
+                (0..k).map(move |i| {
+                    let (prev, v, next) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k]);
+                    vec![v, edge(v, next), center, edge(prev, v)]
+                })
+            })
+            .collect();
+
+        Self { vertices, faces }
+    }
+
+    /// Mesh of the faces split into triangles, with the normals of the
+    /// vertices averaged from the faces around them so that it looks
+    /// smooth.
+    pub fn to_mesh(&self, material: Material) -> Mesh {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+        for face in &self.faces {
+            // The sum of the cross products around the face is twice its
+            // area along its normal, even when it isn't flat.
+            let normal = (0..face.len()).fold(Vec3::ZERO, |sum, i| {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                sum + Vec3::cross(self.vertices[a], self.vertices[b])
+            });
+            for v in face {
+                normals[*v] += normal;
+            }
+        }
+        let normals = normals.into_iter().map(Vec3::unit).collect::<Vec<_>>();
+
+        let triangles = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len() - 1).map(|j| {
+                    let corners = [face[0], face[j], face[j + 1]];
+                    let triangle = Triangle::new(corners.map(|v| self.vertices[v]), material);
+                    match corners.iter().all(|v| normals[*v].is_finite()) {
+                        true => triangle.with_normals(corners.map(|v| normals[v])),
+                        false => triangle,
+                    }
+                })
+            })
+            .collect();
+
+        Mesh::new(triangles)
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use base64::Engine;
+
+use crate::preview::PreviewBuffer;
+
+/// Widest image, in pixels, drawn with the graphics protocols.
+const MAX_GRAPHICS_WIDTH: u32 = 400;
+
+/// Time between the drawings, redrawing a graphics image is not cheap.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Height in pixels of the smallest expected terminal cell.
+const SIXEL_MIN_CELL_HEIGHT: u32 = 12;
+
+/// How an image is drawn inside the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TerminalGraphics {
+    /// Kitty graphics protocol.
+    Kitty,
+    /// DEC sixel graphics.
+    Sixel,
+    /// Colored Unicode half blocks, each cell shows two pixels.
+    HalfBlocks,
+}
+
+impl TerminalGraphics {
+    /// Guess the best protocol supported by the terminal from
+    /// the environment.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || program == "ghostty"
+        {
+            Self::Kitty
+        } else if term.contains("sixel")
+            || ["mlterm", "foot", "WezTerm", "contour"]
+                .iter()
+                .any(|t| term.contains(t) || program == *t)
+        {
+            Self::Sixel
+        } else {
+            Self::HalfBlocks
+        }
+    }
+}
+
+/// Draw the `buffer` on the standard error every `REFRESH_INTERVAL`,
+/// until `done` returns true. Each drawing replaces the previous one.
+pub fn show<D: Fn() -> bool>(buffer: &PreviewBuffer, graphics: TerminalGraphics, done: D) {
+    let layout = Layout::new(buffer.width(), buffer.height(), graphics);
+    let rows = layout.rows;
+    let mut stderr = io::stderr();
+
+    // Scroll the terminal before the first drawing, so that the saved
+    // cursor position still points at the top of the image afterwards.
+    let _ = write!(
+        stderr,
+        "\r{}\x1b[{}A\x1b7",
+        "\n".repeat(rows as usize),
+        rows
+    );
+
+    loop {
+        let finished = done();
+        let rgb = downscale(buffer, layout.width, layout.height);
+
+        // Leave the cursor below the image, where the progress is printed.
+        let _ = write!(
+            stderr,
+            "\x1b8\x1b7{}\x1b8\x1b[{}B",
+            layout.encode(graphics, &rgb),
+            rows
+        );
+        let _ = stderr.flush();
+
+        if finished {
+            break;
+        }
+
+        let mut waited = Duration::ZERO;
+        while waited < REFRESH_INTERVAL && !done() {
+            thread::sleep(Duration::from_millis(100));
+            waited += Duration::from_millis(100);
+        }
+    }
+}
+
+/// Size of the drawn image, fit to the width of the terminal.
+struct Layout {
+    /// Size in pixels of the encoded image.
+    width: u32,
+    height: u32,
+    /// Size in cells of the area covered by the image.
+    columns: u32,
+    rows: u32,
+}
+
+impl Layout {
+    fn new(width: u32, height: u32, graphics: TerminalGraphics) -> Self {
+        let columns = terminal_size::terminal_size_of(io::stderr())
+            .map(|(w, _)| w.0 as u32)
+            .unwrap_or(80)
+            .min(width)
+            .max(1);
+        // Terminal cells are about twice as tall as they are wide, so each
+        // cell covers a square of two pixels with the half blocks.
+        let scaled = |w: u32| ((height as f32 * w as f32 / width as f32) as u32).max(1);
+        let rows = scaled(columns).div_ceil(2);
+
+        let (width, height) = match graphics {
+            TerminalGraphics::HalfBlocks => (columns, scaled(columns)),
+            TerminalGraphics::Kitty | TerminalGraphics::Sixel => {
+                let w = width.min(MAX_GRAPHICS_WIDTH);
+                (w, scaled(w))
+            }
+        };
+
+        let rows = match graphics {
+            // Sixels are drawn at their own size, reserve enough rows
+            // for the smallest common fonts.
+            TerminalGraphics::Sixel => height.div_ceil(SIXEL_MIN_CELL_HEIGHT),
+            TerminalGraphics::Kitty | TerminalGraphics::HalfBlocks => rows,
+        };
+
+        Self {
+            width,
+            height,
+            columns,
+            rows,
+        }
+    }
+
+    fn encode(&self, graphics: TerminalGraphics, rgb: &[[u8; 3]]) -> String {
+        match graphics {
+            TerminalGraphics::Kitty => kitty(rgb, self),
+            TerminalGraphics::Sixel => sixel(rgb, self.width, self.height),
+            TerminalGraphics::HalfBlocks => half_blocks(rgb, self.width, self.height),
+        }
+    }
+}
+
+/// Nearest neighbour downscale of the `buffer` to `width`x`height`.
+fn downscale(buffer: &PreviewBuffer, width: u32, height: u32) -> Vec<[u8; 3]> {
+    let pixels = buffer.snapshot();
+    let (src_w, src_h) = (buffer.width(), buffer.height());
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let sx = x * src_w / width;
+            let sy = y * src_h / height;
+
+            pixels[(sy * src_w + sx) as usize].to_rgb8()
+        })
+        .collect()
+}
+
+fn half_blocks(rgb: &[[u8; 3]], width: u32, height: u32) -> String {
+    let mut out = String::new();
+
+    for y in (0..height).step_by(2) {
+        for x in 0..width {
+            let [r, g, b] = rgb[(y * width + x) as usize];
+            let _ = write!(out, "\x1b[38;2;{};{};{}m", r, g, b);
+
+            if y + 1 < height {
+                let [r, g, b] = rgb[((y + 1) * width + x) as usize];
+                let _ = write!(out, "\x1b[48;2;{};{};{}m", r, g, b);
+            }
+
+            out.push('▀');
+        }
+
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+fn kitty(rgb: &[[u8; 3]], layout: &Layout) -> String {
+    const CHUNK_SIZE: usize = 4096;
+
+    let data = base64::engine::general_purpose::STANDARD.encode(rgb.as_flattened());
+    let chunks = data.as_bytes().chunks(CHUNK_SIZE).collect::<Vec<_>>();
+    let mut out = String::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).unwrap();
+
+        // The first chunk carries the parameters, replacing the image
+        // with the same id and scaling it to the reserved cells.
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,i=1,q=2,C=1,f=24,s={},v={},c={},r={},m={};{}\x1b\\",
+                layout.width, layout.height, layout.columns, layout.rows, more, chunk
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+
+    out
+}
+
+fn sixel(rgb: &[[u8; 3]], width: u32, height: u32) -> String {
+    // Quantize to a 6x6x6 color cube.
+    let level = |c: u8| (c as u32 * 5 + 127) / 255;
+    let index = |[r, g, b]: [u8; 3]| (level(r) * 36 + level(g) * 6 + level(b)) as usize;
+
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let [r, g, b] = [i / 36, i / 6 % 6, i % 6].map(|l| l * 100 / 5);
+        let _ = write!(out, "#{};2;{};{};{}", i, r, g, b);
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut used = [false; 216];
+        for y in rows.clone() {
+            for x in 0..width {
+                used[index(rgb[(y * width + x) as usize])] = true;
+            }
+        }
+
+        for color in (0..216).filter(|c| used[*c]) {
+            let _ = write!(out, "#{}", color);
+
+            let sixels = (0..width).map(|x| {
+                let bits = rows
+                    .clone()
+                    .filter(|y| index(rgb[(y * width + x) as usize]) == color)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+
+                (b'?' + bits as u8) as char
+            });
+
+            // Run-length encode the sixels of the band.
+            let mut run: Option<(char, u32)> = None;
+            for c in sixels.map(Some).chain([None]) {
+                match (run, c) {
+                    (Some((prev, n)), Some(c)) if prev == c => run = Some((prev, n + 1)),
+                    (prev, c) => {
+                        match prev {
+                            Some((prev, n)) if n > 3 => {
+                                let _ = write!(out, "!{}{}", n, prev);
+                            }
+                            Some((prev, n)) => (0..n).for_each(|_| out.push(prev)),
+                            None => {}
+                        }
+                        run = c.map(|c| (c, 1));
+                    }
+                }
+            }
+
+            out.push('$');
+        }
+
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
@@ -0,0 +1,163 @@
+//! Text of solid letters, extruded from the outlines of the glyphs of
+//! a TrueType or OpenType font.
+
+use std::{fs, path::Path};
+
+use ttf_parser::{Face, OutlineBuilder};
+
+use crate::{
+    material::Material,
+    matrix::Matrix,
+    mesh::Mesh,
+    vec3::{Float, Vec3},
+};
+
+/// Straight segments replacing each curve of the outlines.
+const CURVE_SEGMENTS: usize = 6;
+
+/// Contours of a glyph, with the curves flattened into segments.
+#[derive(Default)]
+struct Outline {
+    contours: Vec<Vec<(Float, Float)>>,
+    current: Vec<(Float, Float)>,
+}
+
+impl Outline {
+    fn last(&self) -> (Float, Float) {
+        self.current.last().copied().unwrap_or_default()
+    }
+
+    /// Add the segments of the curve from the last point through the
+    /// point of `t` given by `at`.
+    fn flatten(&mut self, at: impl Fn(Float) -> (Float, Float)) {
+        for i in 1..=CURVE_SEGMENTS {
+            self.current.push(at(i as Float / CURVE_SEGMENTS as Float));
+        }
+    }
+}
+
+// The fonts are in single precision, whatever the `Float`.
+#[allow(clippy::unnecessary_cast)]
+fn point(x: f32, y: f32) -> (Float, Float) {
+    (x as Float, y as Float)
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.current.push(point(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.current.push(point(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.last(), point(x1, y1), point(x, y));
+
+        self.flatten(|t| {
+            let s = 1. - t;
+            let (a, b, c) = (s * s, 2. * s * t, t * t);
+            (
+                a * p0.0 + b * p1.0 + c * p2.0,
+                a * p0.1 + b * p1.1 + c * p2.1,
+            )
+        });
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p0, p1, p2, p3) = (self.last(), point(x1, y1), point(x2, y2), point(x, y));
+
+        self.flatten(|t| {
+            let s = 1. - t;
+            let (a, b, c, d) = (s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t);
+            (
+                a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+            )
+        });
+    }
+
+    fn close(&mut self) {
+        let mut contour = std::mem::take(&mut self.current);
+        // The contours often end on their first point again.
+        if contour.len() > 1 && contour.first() == contour.last() {
+            contour.pop();
+        }
+        contour.dedup();
+
+        if contour.len() >= 3 {
+            self.contours.push(contour);
+        }
+    }
+}
+
+/// Meshes of the letters of the `text` written with the font at `path`,
+/// one for each glyph. The letters are `size` tall from the baseline to
+/// the top of the em square and `depth` thick: they stand on the x axis
+/// from the origin, facing the z axis, with their back `depth` further.
+/// Each line of the text goes below the previous one.
+pub fn text(
+    path: &Path,
+    text: &str,
+    size: Float,
+    depth: Float,
+    material: Material,
+) -> Result<Vec<Mesh>, String> {
+    let data = fs::read(path).map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+    let face =
+        Face::parse(&data, 0).map_err(|e| format!("invalid font `{}`: {}", path.display(), e))?;
+
+    if !(size > 0. && size.is_finite()) {
+        return Err(format!("the size must be positive, found {}", size));
+    }
+    if !(depth > 0. && depth.is_finite()) {
+        return Err(format!("the depth must be positive, found {}", depth));
+    }
+    let scale = size / face.units_per_em() as Float;
+    let line_height =
+        scale * (face.ascender() as Float - face.descender() as Float + face.line_gap() as Float);
+
+    // The glyphs are extruded along y from their outline in the x-z
+    // plane, then stood up with their front at z = 0.
+    let stand = Matrix::from_frame(
+        Vec3(1., 0., 0.),
+        Vec3(0., 0., 1.),
+        Vec3(0., -1., 0.),
+        Vec3(0., 0., -depth),
+    );
+
+    let mut meshes = Vec::new();
+    for (line, characters) in text.lines().enumerate() {
+        let mut x = 0.;
+        let y = -(line as Float) * line_height;
+
+        for c in characters.chars() {
+            let Some(glyph) = face.glyph_index(c) else {
+                return Err(format!("the font has no glyph for `{}`", c));
+            };
+
+            let mut outline = Outline::default();
+            if face.outline_glyph(glyph, &mut outline).is_some() {
+                let contours = outline
+                    .contours
+                    .iter()
+                    .map(|contour| {
+                        contour
+                            .iter()
+                            .map(|(gx, gy)| (x + scale * gx, -(y + scale * gy)))
+                            .collect()
+                    })
+                    .collect::<Vec<_>>();
+                let mut mesh = Mesh::extrude_contours(&contours, depth, material)
+                    .map_err(|e| format!("the glyph of `{}`: {}", c, e))?;
+                mesh.transform(&stand);
+                meshes.push(mesh);
+            }
+
+            x += scale * face.glyph_hor_advance(glyph).unwrap_or_default() as Float;
+        }
+    }
+
+    Ok(meshes)
+}
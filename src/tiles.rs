@@ -0,0 +1,123 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use raycasting_rs::{camera::Tile, output::RowWriter, vec3::Color};
+
+/// Finished tiles spilled to a directory on disk, so that images larger
+/// than the available memory can be rendered and assembled afterwards.
+pub struct TileStore {
+    dir: PathBuf,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+}
+
+impl TileStore {
+    /// Create the directory of the tiles of a `width`x`height` image
+    /// saved at `output`, named after it with the `tiles` extension.
+    pub fn new(output: &Path, width: u32, height: u32, tile_size: u32) -> io::Result<Self> {
+        let dir = output.with_extension("tiles");
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            width,
+            height,
+            tile_size,
+        })
+    }
+
+    fn tile_path(&self, tile: Tile) -> PathBuf {
+        self.dir.join(format!("{}_{}.raw", tile.x, tile.y))
+    }
+
+    /// Spill the `pixels` of the `tile`, in row-major order, they must
+    /// already be encoded for display. The channels are stored in single
+    /// precision, whatever the `Float`.
+    pub fn save(&self, tile: Tile, pixels: &[Color]) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(self.tile_path(tile))?);
+
+        for c in pixels {
+            for channel in c.to_f32() {
+                w.write_all(&channel.to_le_bytes())?;
+            }
+        }
+
+        w.flush()
+    }
+
+    fn load(&self, tile: Tile) -> io::Result<Vec<Color>> {
+        let mut r = BufReader::new(File::open(self.tile_path(tile))?);
+        let mut channels = [[0; 4]; 3];
+
+        (0..tile.width * tile.height)
+            .map(|_| {
+                channels.iter_mut().try_for_each(|c| r.read_exact(c))?;
+                let [r, g, b] = channels.map(f32::from_le_bytes);
+
+                Ok(Color::from_f32([r, g, b]))
+            })
+            .collect()
+    }
+
+    /// Write the image with the `writer`, loading a single row of tiles
+    /// at a time, and remove the spilled tiles.
+    pub fn assemble(self, mut writer: RowWriter) -> io::Result<()> {
+        let tiles = Tile::split(self.width, self.height, self.tile_size);
+
+        for band in tiles.chunk_by(|a, b| a.y == b.y) {
+            let pixels = band
+                .iter()
+                .map(|tile| self.load(*tile))
+                .collect::<io::Result<Vec<_>>>()?;
+
+            for h in 0..band[0].height {
+                let row = band
+                    .iter()
+                    .zip(&pixels)
+                    .flat_map(|(tile, pixels)| {
+                        let start = (h * tile.width) as usize;
+                        &pixels[start..start + tile.width as usize]
+                    })
+                    .copied()
+                    .collect();
+
+                writer.write_row(band[0].y + h, row)?;
+            }
+        }
+
+        writer.finish()?;
+        fs::remove_dir_all(&self.dir)
+    }
+}
+
+#[cfg(all(test, feature = "f64"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_round_trip_in_double_precision() {
+        let output = std::env::temp_dir().join(format!("tiles-{}.png", std::process::id()));
+        let store = TileStore::new(&output, 4, 2, 2).unwrap();
+        let tile = Tile::split(4, 2, 2)[1];
+        let pixels = [
+            (0.25, 0.5, 1.),
+            (0., 0.125, 0.75),
+            (1., 1., 0.),
+            (0.5, 0., 0.5),
+        ]
+        .map(Color::new);
+
+        store.save(tile, &pixels).unwrap();
+        let loaded = store.load(tile).unwrap();
+        fs::remove_dir_all(&store.dir).unwrap();
+
+        assert_eq!(
+            loaded.iter().map(|c| c.to_f32()).collect::<Vec<_>>(),
+            pixels.iter().map(|c| c.to_f32()).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use crate::vec3::{Color, Float};
+
+/// Scale factor applied to the radiance by an exposure
+/// compensation of `ev` stops.
+pub fn exposure_scale(ev: Float) -> Float {
+    Float::powf(2., ev)
+}
+
+/// Operators used to compress the linear radiance of the framebuffer
+/// into the `[0, 1]` range before quantization.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ToneMap {
+    /// Cut off every component above 1.
+    #[default]
+    Clamp,
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's Uncharted 2 filmic curve.
+    Filmic,
+}
+
+impl ToneMap {
+    pub const NAMES: [&'static str; 4] = ["clamp", "reinhard", "aces", "filmic"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(Self::Clamp),
+            "reinhard" => Some(Self::Reinhard),
+            "aces" => Some(Self::Aces),
+            "filmic" => Some(Self::Filmic),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, c: Color) -> Color {
+        let mapped = match self {
+            Self::Clamp => c,
+            Self::Reinhard => c.map(|x| x / (1. + x)),
+            Self::Aces => c.map(|x| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)),
+            Self::Filmic => {
+                const EXPOSURE_BIAS: Float = 2.;
+                const WHITE_POINT: Float = 11.2;
+
+                let white_scale = 1. / Self::hable(WHITE_POINT);
+                c.map(|x| Self::hable(EXPOSURE_BIAS * x) * white_scale)
+            }
+        };
+
+        mapped.map(|x| x.clamp(0., 1.))
+    }
+
+    fn hable(x: Float) -> Float {
+        const A: Float = 0.15;
+        const B: Float = 0.50;
+        const C: Float = 0.10;
+        const D: Float = 0.20;
+        const E: Float = 0.02;
+        const F: Float = 0.30;
+
+        ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+    }
+}
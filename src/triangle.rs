@@ -0,0 +1,195 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hit::{Hit, HitRecord},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Color, Float, Vec3},
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    /// Normals of the vertices, interpolated for smooth shading.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub normals: Option<[Vec3; 3]>,
+    /// Colors of the vertices, interpolated in place of the color
+    /// of the material.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub colors: Option<[Color; 3]>,
+    pub material: Material,
+    /// Whether the rays hitting the back of the triangle pass through,
+    /// which suits the closed meshes whose inside is never seen.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_false"))]
+    pub cull_backfaces: bool,
+}
+
+#[cfg(feature = "serde")]
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vec3; 3], material: Material) -> Self {
+        Self {
+            vertices,
+            normals: None,
+            colors: None,
+            material,
+            cull_backfaces: false,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_colors(mut self, colors: [Color; 3]) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn with_backface_culling(mut self, cull_backfaces: bool) -> Self {
+        self.cull_backfaces = cull_backfaces;
+        self
+    }
+
+    /// Normal of the plane of the triangle on its front, and the normal
+    /// interpolated from the ones of the vertices at the barycentric
+    /// `weights`. Without normals, the front follows the
+    /// counter-clockwise winding of the vertices. With them, they tell
+    /// the front whatever the winding.
+    fn normals(&self, weights: [Float; 3]) -> (Vec3, Option<Vec3>) {
+        let [a, b, c] = self.vertices;
+        let geometric = Vec3::unit(Vec3::cross(b - a, c - a));
+
+        match self.normals {
+            Some(normals) => {
+                let normal =
+                    Vec3::unit((0..3).fold(Vec3::ZERO, |sum, i| sum + weights[i] * normals[i]));
+                match Vec3::dot(geometric, normal) < 0. {
+                    true => (-geometric, Some(normal)),
+                    false => (geometric, Some(normal)),
+                }
+            }
+            None => (geometric, None),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = |v: &[Vec3; 3]| v.iter().all(|v| v.is_finite());
+
+        if !finite(&self.vertices) {
+            return Err("the vertices must be finite".into());
+        }
+        if !self.normals.as_ref().is_none_or(finite) {
+            return Err("the normals must be finite".into());
+        }
+        if !self.colors.is_none_or(|c| finite(&c.map(|c| c.rgb))) {
+            return Err("the colors of the vertices must be finite".into());
+        }
+
+        self.material.validate()
+    }
+
+    /// Distance of the hit of the ray `r` within `t_range`, and the
+    /// barycentric weights of the vertices at the hit point.
+    fn intersect(&self, r: &Ray, t_range: Interval) -> Option<(Float, [Float; 3])> {
+        /* Watertight intersection, from "Watertight Ray/Triangle
+         * Intersection" (Woop et al.): the edges shared by two triangles
+         * are tested with the same numbers, so that no ray passes between
+         * them. */
+        let [a, b, c] = self.vertices;
+
+        // Permute the axes so that the ray goes along z, keeping the
+        // winding of the vertices.
+        let kz = (0..3).max_by(|i, j| r.dir[*i].abs().total_cmp(&r.dir[*j].abs()))?;
+        let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+        if r.dir[kz] < 0. {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        // Shear the vertices, relative to the ray start, so that the
+        // ray is the z axis.
+        let shear = Vec3(r.dir[kx] / r.dir[kz], r.dir[ky] / r.dir[kz], 1. / r.dir[kz]);
+        let [a, b, c] = [a, b, c].map(|v| {
+            let v = v - r.start;
+            Vec3(
+                v[kx] - shear.0 * v[kz],
+                v[ky] - shear.1 * v[kz],
+                shear.2 * v[kz],
+            )
+        });
+
+        // Scaled barycentric coordinates, the edge functions.
+        let edge = |p: Vec3, q: Vec3| {
+            let e = p.0 * q.1 - p.1 * q.0;
+            match e {
+                // On an edge the rounding decides, so it is computed again
+                // in double precision.
+                0. => (wide(p.0) * wide(q.1) - wide(p.1) * wide(q.0)) as Float,
+                e => e,
+            }
+        };
+        let (u, v, w) = (edge(c, b), edge(a, c), edge(b, a));
+        if (u < 0. || v < 0. || w < 0.) && (u > 0. || v > 0. || w > 0.) {
+            return None;
+        }
+
+        let det = u + v + w;
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        let t = (u * a.2 + v * b.2 + w * c.2) / det;
+        if !t_range.surrounds(t) {
+            return None;
+        }
+
+        let weights = [u / det, v / det, w / det];
+        if self.cull_backfaces && Vec3::dot(self.normals(weights).0, r.dir) >= 0. {
+            return None;
+        }
+
+        Some((t, weights))
+    }
+}
+
+impl Hit<Ray> for Triangle {
+    fn hit(&self, r: &Ray, t_range: Interval) -> Option<HitRecord<'_>> {
+        let (t, weights) = self.intersect(r, t_range)?;
+        let (geometric, shading) = self.normals(weights);
+        let record = HitRecord::new(r.at(t), geometric, t, &self.material, r);
+        let mut record = match shading {
+            Some(normal) => record.with_shading_normal(normal),
+            None => record,
+        };
+        if let Some(colors) = self.colors {
+            record = record.with_color(Color {
+                rgb: (0..3).fold(Vec3::ZERO, |sum, i| sum + weights[i] * colors[i].rgb),
+            });
+        }
+
+        Some(record)
+    }
+
+    fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.intersect(r, t_range).is_some()
+    }
+}
+
+/// The number in double precision.
+#[allow(clippy::unnecessary_cast)]
+fn wide(x: Float) -> f64 {
+    x as f64
+}
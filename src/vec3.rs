@@ -1,22 +1,41 @@
 use std::{
     fmt::Display,
     iter::Sum,
-    ops::{Add, AddAssign, Mul, Neg, Range, Sub, SubAssign},
+    ops::{
+        Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Range, Sub, SubAssign,
+    },
 };
 
 use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Scalar of the geometry, `f64` with the `f64` feature for the scenes
+/// whose large or far away objects show the precision errors of `f32`,
+/// like the acne of the surfaces.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Constants of the [`Float`] type.
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
-pub struct Vec3(pub f32, pub f32, pub f32);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Vec3(pub Float, pub Float, pub Float);
 
 impl Vec3 {
     pub const ZERO: Self = Self(0.0, 0.0, 0.0);
 
-    pub fn new(v: (f32, f32, f32)) -> Self {
+    pub fn new(v: (Float, Float, Float)) -> Self {
         Self(v.0, v.1, v.2)
     }
 
-    pub fn rand_with_range(range: Range<f32>) -> Vec3 {
+    pub fn rand_with_range(range: Range<Float>) -> Vec3 {
         let mut rng = rand::thread_rng();
         Self(
             rng.gen_range(range.clone()),
@@ -41,11 +60,25 @@ impl Vec3 {
         }
     }
 
-    pub fn scale(a: f32, v: Vec3) -> Vec3 {
+    /// Random direction of the hemisphere around the z axis, with a
+    /// density proportional to the cosine of its angle with the axis,
+    /// which is `z / PI` over the solid angle.
+    pub fn rand_cosine_direction() -> Self {
+        let mut rng = rand::thread_rng();
+        let (r1, r2) = (rng.gen::<Float>(), rng.gen::<Float>());
+
+        let phi = 2. * consts::PI * r1;
+        let (sin, cos) = phi.sin_cos();
+        let r = Float::sqrt(r2);
+
+        Self(cos * r, sin * r, Float::sqrt(1. - r2))
+    }
+
+    pub fn scale(a: Float, v: Vec3) -> Vec3 {
         Self(a * v.0, a * v.1, a * v.2)
     }
 
-    pub fn dot(v: Vec3, u: Vec3) -> f32 {
+    pub fn dot(v: Vec3, u: Vec3) -> Float {
         v.0 * u.0 + v.1 * u.1 + v.2 * u.2
     }
 
@@ -57,20 +90,24 @@ impl Vec3 {
         )
     }
 
-    pub fn norm(v: Vec3) -> f32 {
-        f32::sqrt(Self::dot(v, v))
+    pub fn norm(v: Vec3) -> Float {
+        Float::sqrt(Self::dot(v, v))
     }
 
     pub fn unit(v: Vec3) -> Vec3 {
         1.0 / Self::norm(v) * v
     }
 
-    pub fn len(self) -> f32 {
-        f32::sqrt(Vec3::dot(self, self))
+    pub fn is_finite(self) -> bool {
+        self.0.is_finite() && self.1.is_finite() && self.2.is_finite()
+    }
+
+    pub fn len(self) -> Float {
+        Float::sqrt(Vec3::dot(self, self))
     }
 
     pub fn elem_dot(v: Self, u: Self) -> Self {
-        Self(v.0 * u.0, v.1 * u.1, v.2 * u.2)
+        v * u
     }
 
     #[inline]
@@ -86,26 +123,36 @@ impl Vec3 {
     /// Calcluate the refracted vector, `r` and `n` must be
     /// in opposite direction
     #[inline]
-    pub fn refract(r: Self, n: Self, refraction_ratio: f32) -> Self {
+    pub fn refract(r: Self, n: Self, refraction_ratio: Float) -> Self {
         let cos_theta = Vec3::dot(-r, n).min(1.0);
         let r_out_perpendicular = refraction_ratio * (r + cos_theta.abs() * n);
-        let r_out_parallel = -f32::sqrt(f32::abs(
+        let r_out_parallel = -Float::sqrt(Float::abs(
             1.0 - Vec3::dot(r_out_perpendicular, r_out_perpendicular),
         )) * n;
 
-        let r_out = r_out_perpendicular + r_out_parallel;
-        r_out
+        r_out_perpendicular + r_out_parallel
     }
 
-    pub fn x(self) -> f32 {
+    /// The components in single precision, as the images store them.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn to_f32(self) -> [f32; 3] {
+        [self.0 as f32, self.1 as f32, self.2 as f32]
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    pub fn from_f32([x, y, z]: [f32; 3]) -> Self {
+        Self(x as Float, y as Float, z as Float)
+    }
+
+    pub fn x(self) -> Float {
         self.0
     }
 
-    pub fn y(self) -> f32 {
+    pub fn y(self) -> Float {
         self.1
     }
 
-    pub fn z(self) -> f32 {
+    pub fn z(self) -> Float {
         self.2
     }
 }
@@ -140,20 +187,78 @@ impl SubAssign for Vec3 {
     }
 }
 
-impl Mul<f32> for Vec3 {
+impl Mul<Float> for Vec3 {
     type Output = Self;
-    fn mul(self, rhs: f32) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self(rhs * self.0, rhs * self.1, rhs * self.2)
     }
 }
 
-impl Mul<Vec3> for f32 {
+impl Mul<Vec3> for Float {
     type Output = Vec3;
     fn mul(self, rhs: Vec3) -> Self::Output {
         Vec3(self * rhs.0, self * rhs.1, self * rhs.2)
     }
 }
 
+/// Component-wise product.
+impl Mul for Vec3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self(self.0 * rhs.0, self.1 * rhs.1, self.2 * rhs.2)
+    }
+}
+
+impl MulAssign<Float> for Vec3 {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<Float> for Vec3 {
+    type Output = Self;
+    fn div(self, rhs: Float) -> Self::Output {
+        Self(self.0 / rhs, self.1 / rhs, self.2 / rhs)
+    }
+}
+
+/// Component-wise quotient.
+impl Div for Vec3 {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self::Output {
+        Self(self.0 / rhs.0, self.1 / rhs.1, self.2 / rhs.2)
+    }
+}
+
+impl DivAssign<Float> for Vec3 {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = Float;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.0,
+            1 => &self.1,
+            2 => &self.2,
+            _ => panic!("index {} out of range for a Vec3", index),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.0,
+            1 => &mut self.1,
+            2 => &mut self.2,
+            _ => panic!("index {} out of range for a Vec3", index),
+        }
+    }
+}
+
 impl Neg for Vec3 {
     type Output = Self;
     fn neg(self) -> Self::Output {
@@ -161,13 +266,62 @@ impl Neg for Vec3 {
     }
 }
 
-impl Into<(f32, f32, f32)> for Vec3 {
-    fn into(self) -> (f32, f32, f32) {
-        (self.0, self.1, self.2)
+impl From<Vec3> for (Float, Float, Float) {
+    fn from(v: Vec3) -> Self {
+        (v.0, v.1, v.2)
+    }
+}
+
+impl From<[Float; 3]> for Vec3 {
+    fn from([x, y, z]: [Float; 3]) -> Self {
+        Self(x, y, z)
+    }
+}
+
+impl From<Vec3> for [Float; 3] {
+    fn from(v: Vec3) -> Self {
+        [v.0, v.1, v.2]
+    }
+}
+
+/// Vector of `glam` with the precision of [`Float`].
+#[cfg(all(feature = "glam", not(feature = "f64")))]
+type GlamVec3 = glam::Vec3;
+#[cfg(all(feature = "glam", feature = "f64"))]
+type GlamVec3 = glam::DVec3;
+
+#[cfg(feature = "glam")]
+impl From<GlamVec3> for Vec3 {
+    fn from(v: GlamVec3) -> Self {
+        Self(v.x, v.y, v.z)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<Vec3> for GlamVec3 {
+    fn from(v: Vec3) -> Self {
+        Self::new(v.0, v.1, v.2)
+    }
+}
+
+/// The 16 bytes aligned vector of `glam`, whose math uses SIMD.
+#[cfg(all(feature = "glam", not(feature = "f64")))]
+impl From<glam::Vec3A> for Vec3 {
+    fn from(v: glam::Vec3A) -> Self {
+        Self(v.x, v.y, v.z)
+    }
+}
+
+#[cfg(all(feature = "glam", not(feature = "f64")))]
+impl From<Vec3> for glam::Vec3A {
+    fn from(v: Vec3) -> Self {
+        Self::new(v.0, v.1, v.2)
     }
 }
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Color {
     pub rgb: Vec3,
 }
@@ -193,43 +347,183 @@ impl Color {
         rgb: Vec3(0.0, 0.0, 1.0),
     };
 
-    pub fn new(rgb: (f32, f32, f32)) -> Self {
+    pub fn new(rgb: (Float, Float, Float)) -> Self {
         Self {
             rgb: Vec3::new(rgb),
         }
     }
 
+    /// Color of the `[0, 255]` components, the inverse of [`Color::to_rgb8`].
+    pub fn from_u8(rgb: [u8; 3]) -> Self {
+        let [r, g, b] = rgb.map(|c| c as Float / 255.);
+
+        Self::new((r, g, b))
+    }
+
+    /// Color written as `#rrggbb` or `#rgb`, the `#` being optional, as
+    /// shown on the screen: the components are brought back to linear,
+    /// so that a white light renders the color itself.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let error = || format!("expected a color like `#aabbcc`, found `{}`", hex);
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.is_ascii() {
+            return Err(error());
+        }
+
+        let component = |s: &str| u8::from_str_radix(s, 16).map_err(|_| error());
+        let rgb = match digits.len() {
+            6 => [&digits[0..2], &digits[2..4], &digits[4..6]].map(component),
+            // Each digit is repeated, like in CSS.
+            3 => [&digits[0..1], &digits[1..2], &digits[2..3]].map(|d| Ok(component(d)? * 17)),
+            _ => return Err(error()),
+        };
+        let [r, g, b] = rgb;
+
+        Ok(Self::from_u8([r?, g?, b?]).to_linear())
+    }
+
+    /// Color of the `hue` in degrees, the `saturation` and the `value`
+    /// between 0 and 1, as linear components.
+    pub fn from_hsv(hue: Float, saturation: Float, value: Float) -> Self {
+        let h = hue.rem_euclid(360.) / 60.;
+        let chroma = value * saturation;
+        let x = chroma * (1. - (h % 2. - 1.).abs());
+
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+        let m = value - chroma;
+
+        Self::new((r + m, g + m, b + m))
+    }
+
+    /// Linear color of the light of a black body at `kelvin` degrees,
+    /// from the warm 1667 K of a candle to the cold 25000 K of a clear
+    /// sky, scaled to a unit luminance. Around 6500 K it is white.
+    pub fn from_temperature(kelvin: Float) -> Self {
+        // Chromaticity of the Planckian locus, from the cubic spline of
+        // Kang et al., "Design of Advanced Color Temperature Control
+        // System for HDTV Applications".
+        let t = kelvin.clamp(1667., 25000.);
+        let (t2, t3) = (t * t, t * t * t);
+        let x = match t <= 4000. {
+            true => -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910,
+            false => -3.0258469e9 / t3 + 2.107038e6 / t2 + 0.2226347e3 / t + 0.240390,
+        };
+        let y = if t <= 2222. {
+            -1.1063814 * x * x * x - 1.3481102 * x * x + 2.1855583 * x - 0.20219683
+        } else if t <= 4000. {
+            -0.9549476 * x * x * x - 1.3741859 * x * x + 2.09137 * x - 0.16748867
+        } else {
+            3.081758 * x * x * x - 5.873387 * x * x + 3.75113 * x - 0.37001483
+        };
+
+        // XYZ of unit luminance to linear sRGB.
+        let (cx, cy, cz) = (x / y, 1., (1. - x - y) / y);
+        let rgb = Self::new((
+            3.2404542 * cx - 1.5371385 * cy - 0.4985314 * cz,
+            -0.969266 * cx + 1.8760108 * cy + 0.0415560 * cz,
+            0.0556434 * cx - 0.2040259 * cy + 1.0572252 * cz,
+        ));
+
+        rgb.map(|c| c.max(0.))
+    }
+
+    /// Linear interpolation from `a`, at `t` 0, to `b`, at `t` 1.
+    pub fn lerp(a: Self, b: Self, t: Float) -> Self {
+        Self {
+            rgb: a.rgb + t * (b.rgb - a.rgb),
+        }
+    }
+
+    /// Clamp each component between `min` and `max`.
+    pub fn clamp(self, min: Float, max: Float) -> Self {
+        self.map(|c| c.clamp(min, max))
+    }
+
+    /// Clamp each component between 0 and 1, the range of the images.
+    pub fn saturate(self) -> Self {
+        self.clamp(0., 1.)
+    }
+
     pub fn blend(a: Self, b: Self) -> Self {
+        Self { rgb: a.rgb * b.rgb }
+    }
+
+    /// Relative luminance of the color, using the Rec. 709 coefficients.
+    pub fn luminance(self) -> Float {
+        0.2126 * self.rgb.0 + 0.7152 * self.rgb.1 + 0.0722 * self.rgb.2
+    }
+
+    /// Apply `f` to each component of the color.
+    pub fn map<F: Fn(Float) -> Float>(self, f: F) -> Self {
         Self {
-            rgb: Vec3::elem_dot(a.rgb, b.rgb),
+            rgb: Vec3(f(self.rgb.0), f(self.rgb.1), f(self.rgb.2)),
         }
     }
 
-    fn linera_to_gamma(c: f32) -> f32 {
-        f32::sqrt(c)
+    /// Encode the linear color with the sRGB transfer function, as the
+    /// images and the screens expect it.
+    pub fn to_gamma(self) -> Self {
+        self.map(|c| match c <= 0.0031308 {
+            true => 12.92 * c,
+            false => 1.055 * c.powf(1. / 2.4) - 0.055,
+        })
+    }
+
+    /// Decode a color encoded with the sRGB transfer function, the
+    /// inverse of [`Color::to_gamma`].
+    pub fn to_linear(self) -> Self {
+        self.map(|c| match c <= 0.04045 {
+            true => c / 12.92,
+            false => Float::powf((c + 0.055) / 1.055, 2.4),
+        })
     }
 
-    pub fn rand(range: Range<f32>) -> Self {
+    /// Translate each component to its `[0, 255]` value.
+    pub fn to_rgb8(self) -> [u8; 3] {
+        [self.rgb.0, self.rgb.1, self.rgb.2].map(|c| (255.999 * c) as u8)
+    }
+
+    /// Translate each component to its `[0, 65535]` value.
+    pub fn to_rgb16(self) -> [u16; 3] {
+        [self.rgb.0, self.rgb.1, self.rgb.2].map(|c| (65535.999 * c) as u16)
+    }
+
+    /// The components in single precision, as the images store them.
+    pub fn to_f32(self) -> [f32; 3] {
+        self.rgb.to_f32()
+    }
+
+    pub fn from_f32(rgb: [f32; 3]) -> Self {
+        Self {
+            rgb: Vec3::from_f32(rgb),
+        }
+    }
+
+    pub fn rand(range: Range<Float>) -> Self {
         Self {
             rgb: Vec3::rand_with_range(range),
         }
     }
 }
 
+impl Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let r = Self::linera_to_gamma(self.rgb.0);
-        let g = Self::linera_to_gamma(self.rgb.1);
-        let b = Self::linera_to_gamma(self.rgb.2);
-
-        // Write the translated [0,255] value of each color component.
-        write!(
-            f,
-            "{} {} {}",
-            (255.999 * r) as u8,
-            (255.999 * g) as u8,
-            (255.999 * b) as u8
-        )
+        let [r, g, b] = self.to_gamma().to_rgb8();
+
+        write!(f, "{} {} {}", r, g, b)
     }
 }
 
@@ -248,9 +542,54 @@ impl Add for Color {
     }
 }
 
-impl Mul<Color> for f32 {
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Self) {
+        self.rgb += rhs.rgb;
+    }
+}
+
+impl Sub for Color {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            rgb: self.rgb - rhs.rgb,
+        }
+    }
+}
+
+impl Mul<Color> for Float {
     type Output = Color;
     fn mul(self, rhs: Color) -> Self::Output {
         Color::new((self * rhs.rgb.0, self * rhs.rgb.1, self * rhs.rgb.2))
     }
 }
+
+impl Mul<Float> for Color {
+    type Output = Self;
+    fn mul(self, rhs: Float) -> Self::Output {
+        rhs * self
+    }
+}
+
+/// Component-wise product, like [`Color::blend`].
+impl Mul for Color {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::blend(self, rhs)
+    }
+}
+
+impl MulAssign<Float> for Color {
+    fn mul_assign(&mut self, rhs: Float) {
+        self.rgb *= rhs;
+    }
+}
+
+impl Div<Float> for Color {
+    type Output = Self;
+    fn div(self, rhs: Float) -> Self::Output {
+        Self {
+            rgb: self.rgb / rhs,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use raycasting_rs::vec3::{Color, Float};
+
+pub const EXTENSIONS: [&str; 4] = ["mp4", "webm", "mkv", "mov"];
+
+/// Whether `path` names a video, which is encoded with ffmpeg.
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Encode a video by piping raw frames into an `ffmpeg` child process,
+/// the codec is chosen by ffmpeg from the extension of the output.
+pub struct VideoEncoder {
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    width: u32,
+    height: u32,
+}
+
+impl VideoEncoder {
+    pub fn new(path: &Path, width: u32, height: u32, fps: Float) -> io::Result<Self> {
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &fps.to_string()])
+            .args(["-i", "-"])
+            // Most encoders need even dimensions for the chroma subsampling.
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("failed to run ffmpeg: {}", e)))?;
+
+        let stdin = BufWriter::new(child.stdin.take().unwrap());
+
+        Ok(Self {
+            child,
+            stdin,
+            width,
+            height,
+        })
+    }
+
+    /// Append a frame, the `pixels` must already be encoded for display.
+    pub fn write_frame(&mut self, pixels: &[Color]) -> io::Result<()> {
+        assert_eq!(pixels.len(), (self.width * self.height) as usize);
+
+        for c in pixels {
+            self.stdin.write_all(&c.to_rgb8())?;
+        }
+
+        Ok(())
+    }
+
+    /// Close the stream and wait for ffmpeg to write the video.
+    pub fn finish(self) -> io::Result<()> {
+        let Self {
+            mut child, stdin, ..
+        } = self;
+
+        stdin.into_inner().map_err(|e| e.into_error())?;
+        let status = child.wait()?;
+
+        match status.success() {
+            true => Ok(()),
+            false => Err(io::Error::other(format!("ffmpeg failed with {}", status))),
+        }
+    }
+}
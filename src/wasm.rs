@@ -0,0 +1,118 @@
+use wasm_bindgen::{prelude::*, Clamped};
+
+use crate::{
+    camera::Camera,
+    description::DEFAULT_ASPECT_RATIO,
+    render::Tracer,
+    scene::Scene,
+    vec3::{Color, Float},
+    world::World,
+};
+
+/// Progressive render of a built-in scene for an HTML canvas, which
+/// improves a few rows at a time so that the page stays responsive:
+///
+/// ```js
+/// const renderer = new Renderer("three-spheres", canvas.width);
+/// canvas.height = renderer.height();
+/// const frame = () => {
+///     renderer.render_rows(16);
+///     const image = new ImageData(renderer.pixels(), renderer.width());
+///     canvas.getContext("2d").putImageData(image, 0, 0);
+///     requestAnimationFrame(frame);
+/// };
+/// requestAnimationFrame(frame);
+/// ```
+#[wasm_bindgen]
+pub struct Renderer {
+    camera: Camera,
+    world: World,
+    tracer: Tracer,
+    /// Sum of the samples of each pixel.
+    sum: Vec<Color>,
+    /// Number of samples of the rows before the next one.
+    passes: u32,
+    next_row: u32,
+    /// RGBA pixels shown in the canvas.
+    pixels: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    /// Render the built-in scene `name` `width` pixels wide.
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: &str, width: u32) -> Result<Renderer, JsError> {
+        let scene = Scene::from_name(name).ok_or_else(|| {
+            JsError::new(&format!(
+                "unknown scene `{}`, expected one of: {}",
+                name,
+                Scene::NAMES.join(", ")
+            ))
+        })?;
+
+        let (camera, render) = scene.settings();
+        let aspect_ratio = render.aspect_ratio.unwrap_or(DEFAULT_ASPECT_RATIO);
+        // The browser has a single thread for the page.
+        let camera = Camera::from(camera.settings(width.max(1), aspect_ratio))
+            .threads(Some(1))
+            .quiet();
+        let size = (camera.width() * camera.height()) as usize;
+
+        Ok(Self {
+            camera,
+            world: scene.build().into(),
+            tracer: Tracer::new(render.max_depth.unwrap_or(20)),
+            sum: vec![Color::BLACK; size],
+            passes: 0,
+            next_row: 0,
+            pixels: vec![0; 4 * size],
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.camera.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.camera.height()
+    }
+
+    /// Number of samples of every pixel of the image.
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    /// Add a sample to each pixel of the next `rows` rows, starting a
+    /// new pass from the top once the bottom is reached.
+    pub fn render_rows(&mut self, rows: u32) {
+        let width = self.camera.width() as usize;
+
+        for _ in 0..rows {
+            let h = self.next_row;
+            let samples = self
+                .camera
+                .ray_row(h, 1, |r| self.tracer.pixel(r, &self.world).0);
+            let start = h as usize * width;
+
+            for (x, sample) in samples.into_iter().enumerate() {
+                let sum = &mut self.sum[start + x];
+                *sum = *sum + sample;
+
+                let color = (1. / (self.passes + 1) as Float) * *sum;
+                let [r, g, b] = color.to_gamma().to_rgb8();
+                self.pixels[4 * (start + x)..4 * (start + x + 1)].copy_from_slice(&[r, g, b, 255]);
+            }
+
+            self.next_row += 1;
+            if self.next_row == self.camera.height() {
+                self.next_row = 0;
+                self.passes += 1;
+            }
+        }
+    }
+
+    /// RGBA pixels of the image rendered so far, for an `ImageData`.
+    pub fn pixels(&self) -> Clamped<Vec<u8>> {
+        Clamped(self.pixels.clone())
+    }
+}
@@ -0,0 +1,243 @@
+#[cfg(feature = "serde")]
+use std::{fs, path::Path};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use crate::object;
+
+use crate::{
+    bvh::{Bvh, BvhStats},
+    camera::Camera,
+    colorspace::ColorSpace,
+    description::RenderSettings,
+    hit::HitRecord,
+    interval::Interval,
+    object::Object,
+    ray::Ray,
+    stats,
+    vec3::{Color, Vec3},
+};
+
+/// Camera and objects of a scene, stored as JSON so that scenes can
+/// be generated by other tools.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WorldFile {
+    pub camera: Camera,
+    pub objects: Vec<Object>,
+    /// Samples and depth of the render, the image size is the one of
+    /// the camera.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub render: RenderSettings,
+}
+
+#[cfg(feature = "serde")]
+impl WorldFile {
+    pub fn load_json(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+
+        Self::parse_json(&text).map_err(|e| format!("invalid scene `{}`: {}", path.display(), e))
+    }
+
+    pub fn parse_json(text: &str) -> Result<Self, String> {
+        let world: Self = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        object::validate(&world.objects)?;
+
+        Ok(world)
+    }
+
+    pub fn save_json(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).unwrap();
+
+        fs::write(path, text + "\n")
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))
+    }
+}
+
+/// Color of the rays escaping the objects of the world.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Background {
+    /// Gradient from white at the horizon to light blue at the zenith.
+    #[default]
+    Sky,
+    Color(Color),
+}
+
+impl Background {
+    /// Color seen along the direction `dir`.
+    pub fn color(&self, dir: Vec3) -> Color {
+        match self {
+            Self::Sky => {
+                let a = 0.5 * (Vec3::unit(dir).y() + 1.0);
+
+                (1.0 - a) * Color::new((1.0, 1.0, 1.0)) + a * Color::new((0.5, 0.7, 1.0))
+            }
+            Self::Color(color) => *color,
+        }
+    }
+}
+
+/// Objects rendered by the [`Tracer`](crate::render::Tracer), which
+/// can't change once built so that their [`Bvh`] stays valid:
+///
+/// ```
+/// use raycasting_rs::{
+///     material::Material,
+///     sphere::Sphere,
+///     vec3::{Color, Vec3},
+///     world::{Background, World},
+/// };
+///
+/// let glass = Material::dielectric(1.5, None);
+/// let world = World::builder()
+///     .add(Sphere::new(Vec3(0., 0., -1.), 0.5, glass))
+///     .add_many(raycasting_rs::scenes::three_spheres())
+///     .with_background(Background::Color(Color::new((0.1, 0.1, 0.1))))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(world.objects().len(), 6);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    /// The objects in the order of the leaves of the BVH.
+    objects: Vec<Object>,
+    /// Index of each object in the order they were added.
+    ids: Vec<usize>,
+    bvh: Bvh,
+    background: Background,
+    color_space: ColorSpace,
+}
+
+impl World {
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::default()
+    }
+
+    /// The objects in the order of the BVH, where the ones close in space
+    /// are next to each other, instead of the order they were added.
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    pub fn bvh_stats(&self) -> BvhStats {
+        self.bvh.stats()
+    }
+
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Working color space of the materials and of the light.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Color of the background along the direction `dir`, in the
+    /// working color space.
+    pub fn background_color(&self, dir: Vec3) -> Color {
+        ColorSpace::Rec709.convert(self.background.color(dir), self.color_space)
+    }
+
+    /// Closest hit of the ray `r` within `t_range`, together with the
+    /// index of the object hit in the order they were added.
+    pub fn hit(&self, r: &Ray, t_range: Interval) -> Option<(usize, HitRecord<'_>)> {
+        self.traverse(r, t_range).0
+    }
+
+    /// Like [`World::hit`], also returning the number of nodes of the
+    /// BVH visited to find the hit.
+    pub fn traverse(&self, r: &Ray, t_range: Interval) -> (Option<(usize, HitRecord<'_>)>, u32) {
+        let (hit, visits) = self.bvh.traverse(&self.objects, r, t_range);
+        let hit = hit.map(|(object, hit)| (self.ids[object], hit));
+        if let Some((id, _)) = hit {
+            stats::count_object(id, |s| s.hits += 1);
+        }
+
+        (hit, visits)
+    }
+
+    /// Whether the ray `r` hits any object within `t_range`, cheaper
+    /// than [`World::hit`] as it stops at the first hit.
+    pub fn hit_any(&self, r: &Ray, t_range: Interval) -> bool {
+        self.bvh.hit_any(&self.objects, r, t_range)
+    }
+}
+
+impl From<Vec<Object>> for World {
+    fn from(objects: Vec<Object>) -> Self {
+        // The materials are already in the default color space.
+        World::builder().add_many(objects).assemble()
+    }
+}
+
+/// Builder of a [`World`], collecting the objects and the settings
+/// applied once all of them are known.
+#[derive(Debug, Clone, Default)]
+pub struct WorldBuilder {
+    objects: Vec<Object>,
+    background: Background,
+    color_space: ColorSpace,
+}
+
+impl WorldBuilder {
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, object: impl Into<Object>) -> Self {
+        self.objects.push(object.into());
+        self
+    }
+
+    pub fn add_many<O: Into<Object>>(mut self, objects: impl IntoIterator<Item = O>) -> Self {
+        self.objects.extend(objects.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Compute the light in the `color_space`, the colors of the
+    /// materials are converted to it from Rec. 709.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Build the world, failing when a custom shape can't be built with
+    /// its materials converted to the color space.
+    pub fn build(mut self) -> Result<World, String> {
+        if self.color_space != ColorSpace::Rec709 {
+            for (i, object) in self.objects.iter_mut().enumerate() {
+                object
+                    .map_materials(|material| {
+                        material.solid_color =
+                            ColorSpace::Rec709.convert(material.solid_color, self.color_space);
+                    })
+                    .map_err(|e| format!("object {}: {}", i, e))?;
+            }
+        }
+
+        Ok(self.assemble())
+    }
+
+    fn assemble(self) -> World {
+        let _span = tracing::debug_span!("bvh", objects = self.objects.len()).entered();
+
+        let (bvh, ids) = Bvh::new(&self.objects);
+        let mut objects = self.objects.into_iter().map(Some).collect::<Vec<_>>();
+        let objects = ids.iter().map(|id| objects[*id].take().unwrap()).collect();
+
+        World {
+            objects,
+            ids,
+            bvh,
+            background: self.background,
+            color_space: self.color_space,
+        }
+    }
+}
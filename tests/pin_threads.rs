@@ -0,0 +1,101 @@
+//! Rendering with the threads pinned to the cores gives the same image
+//! as without, with the rows of each thread in their place, and each
+//! pinned thread runs on a single core.
+
+#[cfg(target_os = "linux")]
+use raycasting_rs::affinity;
+use raycasting_rs::{
+    buffer::ImageBuffer,
+    camera::Camera,
+    vec3::{Float, Vec3},
+};
+
+/// Position in pixels of the rays of each pixel on the image, on
+/// average, rendered with `threads` threads.
+fn positions(threads: u32, pin: bool) -> ImageBuffer<(Float, Float)> {
+    let camera = Camera::new(1.5, 30, 40., Vec3::ZERO, Vec3(0., 0., -1.), 0., 1.)
+        .threads(Some(threads))
+        .pin_threads(pin)
+        .quiet();
+
+    camera.ray_map(
+        16,
+        |samples| {
+            let (x, y) = samples.iter().fold((0., 0.), |(x, y), sample| {
+                let (px, py) = camera.project(sample.ray.at(1.)).unwrap();
+                (x + px, y + py)
+            });
+            let n = samples.len() as Float;
+            (x / n, y / n)
+        },
+        |_, _| {},
+    )
+}
+
+fn assert_in_place(image: &ImageBuffer<(Float, Float)>) {
+    assert_eq!((image.width(), image.height()), (30, 20));
+
+    for (y, row) in image.rows().enumerate() {
+        for (x, (px, py)) in row.iter().enumerate() {
+            assert!(
+                (px - x as Float).abs() < 0.5 && (py - y as Float).abs() < 0.5,
+                "the pixel ({}, {}) has the rays of ({}, {})",
+                x,
+                y,
+                px,
+                py
+            );
+        }
+    }
+}
+
+#[test]
+fn pinned_threads_render_the_same_image() {
+    assert_in_place(&positions(3, false));
+    assert_in_place(&positions(3, true));
+}
+
+#[test]
+fn single_pinned_thread_renders_the_same_image() {
+    assert_in_place(&positions(1, true));
+}
+
+/// Cores allowed to each thread mapping the rays, as seen from `f`.
+#[cfg(target_os = "linux")]
+fn worker_cores(threads: u32, pin: bool) -> Vec<Vec<usize>> {
+    let camera = Camera::new(1.5, 30, 40., Vec3::ZERO, Vec3(0., 0., -1.), 0., 1.)
+        .threads(Some(threads))
+        .pin_threads(pin)
+        .quiet();
+
+    camera
+        .ray_map(1, |_| affinity::allowed_cores().unwrap(), |_, _| {})
+        .into_pixels()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pinned_threads_run_on_a_single_core() {
+    let allowed = affinity::allowed_cores().unwrap();
+
+    for threads in [1, 3] {
+        for cores in worker_cores(threads, true) {
+            assert_eq!(cores.len(), 1, "{} threads", threads);
+            assert!(allowed.contains(&cores[0]));
+        }
+        // The calling thread isn't left pinned.
+        assert_eq!(affinity::allowed_cores().unwrap(), allowed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unpinned_threads_run_on_all_the_cores() {
+    let allowed = affinity::allowed_cores().unwrap();
+
+    for threads in [1, 3] {
+        assert!(worker_cores(threads, false)
+            .iter()
+            .all(|cores| *cores == allowed));
+    }
+}
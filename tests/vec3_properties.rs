@@ -0,0 +1,121 @@
+//! Invariants of the vector and optics math of `vec3`, checked on
+//! random vectors.
+
+use proptest::prelude::*;
+
+use raycasting_rs::vec3::{Float, Vec3};
+
+/// Tolerance of the comparisons, relative to the size of the values.
+const EPSILON: Float = 1e-3;
+
+fn approx_eq(a: Float, b: Float) -> bool {
+    (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.)
+}
+
+fn vec_approx_eq(v: Vec3, u: Vec3) -> bool {
+    approx_eq(v.0, u.0) && approx_eq(v.1, u.1) && approx_eq(v.2, u.2)
+}
+
+/// Vectors with components from -100 to 100.
+fn vector() -> impl Strategy<Value = Vec3> {
+    let range = || -100. as Float..100.;
+    (range(), range(), range()).prop_map(Vec3::new)
+}
+
+/// Vectors which are long enough to have an accurate direction.
+fn direction() -> impl Strategy<Value = Vec3> {
+    vector().prop_filter("too short", |v| Vec3::norm(*v) > 1e-2)
+}
+
+proptest! {
+    #[test]
+    fn unit_has_norm_one(v in direction()) {
+        prop_assert!(approx_eq(Vec3::norm(Vec3::unit(v)), 1.));
+    }
+
+    #[test]
+    fn unit_keeps_the_direction(v in direction()) {
+        let u = Vec3::unit(v);
+        prop_assert!(vec_approx_eq(Vec3::norm(v) * u, v));
+    }
+
+    #[test]
+    fn dot_is_symmetric(v in vector(), u in vector()) {
+        prop_assert!(approx_eq(Vec3::dot(v, u), Vec3::dot(u, v)));
+    }
+
+    #[test]
+    fn dot_with_itself_is_the_squared_norm(v in vector()) {
+        let norm = Vec3::norm(v);
+        prop_assert!(approx_eq(Vec3::dot(v, v), norm * norm));
+    }
+
+    #[test]
+    fn cross_is_anticommutative(v in vector(), u in vector()) {
+        prop_assert!(vec_approx_eq(Vec3::cross(v, u), -Vec3::cross(u, v)));
+    }
+
+    #[test]
+    fn cross_is_orthogonal(v in direction(), u in direction()) {
+        let (v, u) = (Vec3::unit(v), Vec3::unit(u));
+        let w = Vec3::cross(v, u);
+
+        prop_assert!(approx_eq(Vec3::dot(w, v), 0.));
+        prop_assert!(approx_eq(Vec3::dot(w, u), 0.));
+    }
+
+    #[test]
+    fn cross_norm_is_the_area(v in direction(), u in direction()) {
+        // Lagrange's identity: |v × u|² = |v|² |u|² - (v · u)².
+        let (v, u) = (Vec3::unit(v), Vec3::unit(u));
+        let cross = Vec3::norm(Vec3::cross(v, u));
+        let dot = Vec3::dot(v, u);
+
+        prop_assert!(approx_eq(cross * cross, 1. - dot * dot));
+    }
+
+    #[test]
+    fn reflect_preserves_the_length(v in vector(), n in direction()) {
+        let r = Vec3::reflect(v, Vec3::unit(n));
+        prop_assert!(approx_eq(Vec3::norm(r), Vec3::norm(v)));
+    }
+
+    #[test]
+    fn reflect_mirrors_the_normal_component(v in vector(), n in direction()) {
+        let n = Vec3::unit(n);
+        let r = Vec3::reflect(v, n);
+
+        prop_assert!(approx_eq(Vec3::dot(r, n), -Vec3::dot(v, n)));
+        prop_assert!(vec_approx_eq(Vec3::reflect(r, n), v));
+    }
+
+    #[test]
+    fn refract_obeys_snell_law(
+        r in direction(),
+        n in direction(),
+        ratio in 0.3 as Float..3.,
+    ) {
+        let (r, n) = (Vec3::unit(r), Vec3::unit(n));
+        // The ray must come from the side the normal points to.
+        let n = if Vec3::dot(r, n) > 0. { -n } else { n };
+        let sin_in = Vec3::norm(Vec3::cross(r, n));
+        prop_assume!(ratio * sin_in < 0.99);
+
+        let out = Vec3::refract(r, n, ratio);
+        let sin_out = Vec3::norm(Vec3::cross(Vec3::unit(out), n));
+
+        prop_assert!(approx_eq(Vec3::norm(out), 1.));
+        prop_assert!(approx_eq(sin_out, ratio * sin_in));
+        // The refracted ray crosses the surface.
+        prop_assert!(Vec3::dot(out, n) < 0.);
+    }
+
+    #[test]
+    fn refract_with_the_same_index_goes_straight(r in direction(), n in direction()) {
+        let (r, n) = (Vec3::unit(r), Vec3::unit(n));
+        let n = if Vec3::dot(r, n) > 0. { -n } else { n };
+        prop_assume!(Vec3::dot(r, n) < -1e-2);
+
+        prop_assert!(vec_approx_eq(Vec3::refract(r, n, 1.), r));
+    }
+}